@@ -1,26 +1,366 @@
 // standard library
+mod array;
 mod collections;
 mod env;
 mod fs;
 mod http;
 mod io;
-mod json;
+mod iterator;
+pub mod json;
 mod math;
+mod path;
+mod promise;
+mod range;
+pub mod regex;
+mod string;
 mod thread;
 mod time;
 
+use std::{fmt, rc::Rc};
+
 use lazy_static::lazy_static;
 
+pub use self::env::set_args;
+
 use hashbrown::HashMap;
 
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    sendable::Shared,
+    value::{Value, ValueKind},
+    Interpreter,
+};
+
+pub type NativeFn = fn(&mut Interpreter, Vec<Value>) -> RuntimeResult<Value>;
+
+#[derive(Debug)]
+pub struct NativeFunction {
+    pub name: &'static str,
+    pub params: &'static [Param],
+    pub func: NativeFn,
+}
+
+#[derive(Debug)]
+pub struct Param {
+    pub name: &'static str,
+    pub kind: ValueKind,
+    pub mode: ParamMode,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParamMode {
+    Required,
+    // may be omitted or nil
+    Optional,
+    // takes every remaining argument, must be the last param
+    Variadic,
+}
+
+#[derive(Debug)]
+pub struct NativeModule {
+    pub name: &'static str,
+    pub functions: &'static [NativeFunction],
+    pub constants: &'static [(&'static str, Constant)],
+}
+
+#[derive(Debug)]
+pub enum Constant {
+    Number(f64),
+    Str(&'static str),
+    // computed when accessed, for process context like the script arguments
+    Computed(fn() -> Value),
+}
+
 lazy_static! {
-    static ref HTTP_FUNCTIONS: HashMap<&'static str, &'static str> = {
+    static ref MODULES: HashMap<&'static str, &'static NativeModule> = {
         let mut hash = HashMap::new();
-        hash.insert("test", "test");
+        hash.insert(collections::MODULE.name, &collections::MODULE);
+        hash.insert(env::MODULE.name, &env::MODULE);
+        hash.insert(fs::MODULE.name, &fs::MODULE);
+        hash.insert(http::MODULE.name, &http::MODULE);
+        hash.insert(io::MODULE.name, &io::MODULE);
+        hash.insert(json::MODULE.name, &json::MODULE);
+        hash.insert(math::MODULE.name, &math::MODULE);
+        hash.insert(path::MODULE.name, &path::MODULE);
+        hash.insert(promise::MODULE.name, &promise::MODULE);
+        hash.insert(regex::MODULE.name, &regex::MODULE);
+        hash.insert(thread::MODULE.name, &thread::MODULE);
+        hash.insert(time::MODULE.name, &time::MODULE);
         hash
     };
 }
 
-pub fn print(expr: &str) {
-    println!("{expr}")
+// functions available in every script without any import
+pub static GLOBALS: &[NativeFunction] = &[
+    NativeFunction::new(
+        "print",
+        &[Param::variadic("values", ValueKind::Any)],
+        io::print,
+    ),
+    NativeFunction::new(
+        "bytes",
+        &[Param::new("value", ValueKind::Any)],
+        |_, args| match &args[0] {
+            Value::Str(s) => Ok(Value::Bytes(s.as_bytes().into())),
+            Value::Bytes(bytes) => Ok(Value::Bytes(bytes.clone())),
+            Value::Array(array) => {
+                let bytes = array
+                    .borrow()
+                    .iter()
+                    .map(|value| match value {
+                        Value::Number(n) if n.fract() == 0.0 && (0.0..=255.0).contains(n) => {
+                            Ok(*n as u8)
+                        }
+                        value => Err(RuntimeError::invalid_argument(
+                            "bytes",
+                            format!("expected integers from 0 to 255, found `{value}`"),
+                        )),
+                    })
+                    .collect::<RuntimeResult<Vec<_>>>()?;
+
+                Ok(Value::Bytes(bytes.into()))
+            }
+            value => Err(RuntimeError::argument_type(
+                "bytes",
+                "value",
+                ValueKind::Array,
+                value.kind(),
+            )),
+        },
+    ),
+    NativeFunction::new(
+        "iter",
+        &[Param::new("value", ValueKind::Any)],
+        iterator::iter,
+    ),
+    NativeFunction::new(
+        "typeof",
+        &[Param::new("value", ValueKind::Any)],
+        |_, args| Ok(Value::str(&args[0].kind().to_string())),
+    ),
+    NativeFunction::new(
+        "assert",
+        &[
+            Param::new("condition", ValueKind::Any),
+            Param::optional("message", ValueKind::Str),
+        ],
+        |_, args| {
+            if args[0].is_truthy() {
+                return Ok(Value::Nil);
+            }
+
+            let message = optional(&args, 1).map(|m| m.to_string());
+            Err(RuntimeError::assertion_failed(message))
+        },
+    ),
+];
+
+pub fn get_module(name: &str) -> Option<&'static NativeModule> {
+    MODULES.get(name).copied()
+}
+
+/// the native method called `name` on values of this type, if any
+pub fn method(value: &Value, name: &str) -> Option<&'static NativeFunction> {
+    let methods = match value {
+        Value::Str(_) => string::STRING_METHODS,
+        Value::Array(_) => array::ARRAY_METHODS,
+        Value::Range(_) => range::RANGE_METHODS,
+        Value::Iterator(_) => iterator::ITERATOR_METHODS,
+        Value::Promise(_) => promise::PROMISE_METHODS,
+        Value::Regex(_) => regex::REGEX_METHODS,
+        Value::Set(_) => collections::SET_METHODS,
+        Value::Map(_) => collections::MAP_METHODS,
+        Value::Deque(_) => collections::DEQUE_METHODS,
+        Value::Stack(_) => collections::STACK_METHODS,
+        Value::PriorityQueue(_) => collections::PRIORITY_QUEUE_METHODS,
+        Value::Shared(Shared::Thread(_)) => thread::THREAD_METHODS,
+        Value::Shared(Shared::Channel(_)) => thread::CHANNEL_METHODS,
+        Value::Shared(Shared::Mutex(_)) => thread::MUTEX_METHODS,
+        Value::Shared(Shared::Atomic(_)) => thread::ATOMIC_METHODS,
+        _ => return None,
+    };
+
+    methods.iter().find(|method| method.name == name)
+}
+
+pub fn modules() -> Vec<&'static NativeModule> {
+    let mut modules = MODULES.values().copied().collect::<Vec<_>>();
+    modules.sort_by_key(|module| module.name);
+    modules
+}
+
+impl NativeFunction {
+    pub const fn new(name: &'static str, params: &'static [Param], func: NativeFn) -> Self {
+        Self { name, params, func }
+    }
+
+    pub fn check_args(&self, args: &[Value]) -> RuntimeResult<()> {
+        let required = self
+            .params
+            .iter()
+            .filter(|param| param.mode == ParamMode::Required)
+            .count();
+        let max = match self.params.last() {
+            Some(param) if param.mode == ParamMode::Variadic => None,
+            _ => Some(self.params.len()),
+        };
+
+        if args.len() < required || max.is_some_and(|max| args.len() > max) {
+            return Err(RuntimeError::arity(self.name, required, max, args.len()));
+        }
+
+        for (i, arg) in args.iter().enumerate() {
+            let param = &self.params[i.min(self.params.len() - 1)];
+
+            let is_omitted = param.mode != ParamMode::Required && *arg == Value::Nil;
+            if !is_omitted && !param.kind.accepts(arg) {
+                return Err(RuntimeError::argument_type(
+                    self.name,
+                    param.name,
+                    param.kind,
+                    arg.kind(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Param {
+    pub const fn new(name: &'static str, kind: ValueKind) -> Self {
+        Self {
+            name,
+            kind,
+            mode: ParamMode::Required,
+        }
+    }
+    pub const fn optional(name: &'static str, kind: ValueKind) -> Self {
+        Self {
+            name,
+            kind,
+            mode: ParamMode::Optional,
+        }
+    }
+    pub const fn variadic(name: &'static str, kind: ValueKind) -> Self {
+        Self {
+            name,
+            kind,
+            mode: ParamMode::Variadic,
+        }
+    }
+}
+
+impl NativeModule {
+    /// every member of the module, bound by `import * from "module"`
+    pub fn members(&self) -> Vec<(String, Value)> {
+        let constants = self
+            .constants
+            .iter()
+            .map(|(name, constant)| (name.to_string(), constant.to_value()));
+        let functions = self
+            .functions
+            .iter()
+            .map(|function| (function.name.to_owned(), Value::Native(function)));
+
+        constants.chain(functions).collect()
+    }
+
+    pub fn member(&self, name: &str) -> Option<Value> {
+        if let Some((_, constant)) = self.constants.iter().find(|(n, _)| *n == name) {
+            return Some(constant.to_value());
+        }
+
+        self.functions
+            .iter()
+            .find(|function| function.name == name)
+            .map(Value::Native)
+    }
+
+    /// the module as an object, bound by `import * as name from "module"`
+    pub fn to_value(&self) -> Value {
+        Value::object(self.members().into_iter().collect())
+    }
+}
+
+impl Constant {
+    pub fn to_value(&self) -> Value {
+        match self {
+            Constant::Number(n) => Value::Number(*n),
+            Constant::Str(s) => Value::str(s),
+            Constant::Computed(compute) => compute(),
+        }
+    }
+}
+
+// argument accessors for native functions, the types being checked before any call
+
+pub fn number(args: &[Value], index: usize) -> f64 {
+    match &args[index] {
+        Value::Number(n) => *n,
+        _ => unreachable!(),
+    }
+}
+
+pub fn string(args: &[Value], index: usize) -> Rc<str> {
+    match &args[index] {
+        Value::Str(s) => Rc::clone(s),
+        _ => unreachable!(),
+    }
+}
+
+pub fn optional(args: &[Value], index: usize) -> Option<&Value> {
+    args.get(index).filter(|arg| **arg != Value::Nil)
+}
+
+/// a count or a position, which must be a non-negative integer
+pub fn count(function: &str, param: &str, n: f64) -> RuntimeResult<usize> {
+    if n.fract() != 0.0 || n < 0.0 {
+        return Err(RuntimeError::invalid_argument(
+            function,
+            format!("`{param}` must be a non-negative integer, found `{n}`"),
+        ));
+    }
+
+    Ok(n as usize)
+}
+
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.name)?;
+
+        for (i, param) in self.params.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{param}")?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mode {
+            ParamMode::Required => write!(f, "{}: {}", self.name, self.kind),
+            ParamMode::Optional => write!(f, "{}?: {}", self.name, self.kind),
+            ParamMode::Variadic => write!(f, "...{}: {}", self.name, self.kind),
+        }
+    }
+}
+
+impl fmt::Display for NativeModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.name)?;
+
+        for (name, constant) in self.constants {
+            writeln!(f, "    {name} = {}", constant.to_value())?;
+        }
+        for function in self.functions {
+            writeln!(f, "    {function}")?;
+        }
+
+        Ok(())
+    }
 }
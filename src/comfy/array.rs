@@ -0,0 +1,345 @@
+// methods of arrays, callbacks receiving each element and, for script functions, its index
+
+use std::cmp::Ordering;
+
+use hashbrown::HashSet;
+
+use super::{count, number, optional, string, NativeFunction, Param};
+use crate::{
+    interpreter::{
+        array_index,
+        collections::Key,
+        errors::{RuntimeError, RuntimeResult},
+        iteration::collect_values,
+        operations::compare,
+        value::{Array, Value, ValueKind},
+        Interpreter,
+    },
+    parser::operations::binary::BinaryOperator,
+};
+
+const CALLBACK: &[Param] = &[Param::new("function", ValueKind::Function)];
+const VALUE: &[Param] = &[Param::new("value", ValueKind::Any)];
+
+pub static ARRAY_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("all", CALLBACK, |interpreter, args| {
+        for (i, value) in elements(&args).into_iter().enumerate() {
+            if !call(interpreter, &args[1], value, i)?.is_truthy() {
+                return Ok(Value::Bool(false));
+            }
+        }
+
+        Ok(Value::Bool(true))
+    }),
+    NativeFunction::new("any", CALLBACK, |interpreter, args| {
+        for (i, value) in elements(&args).into_iter().enumerate() {
+            if call(interpreter, &args[1], value, i)?.is_truthy() {
+                return Ok(Value::Bool(true));
+            }
+        }
+
+        Ok(Value::Bool(false))
+    }),
+    NativeFunction::new(
+        "chunk",
+        &[Param::new("size", ValueKind::Number)],
+        |_, args| {
+            let size = count("chunk", "size", number(&args, 1))?;
+            if size == 0 {
+                return Err(RuntimeError::invalid_argument(
+                    "chunk",
+                    "`size` must be at least 1".to_owned(),
+                ));
+            }
+
+            let chunks = array(&args)
+                .borrow()
+                .chunks(size)
+                .map(|chunk| Value::array(chunk.to_vec()))
+                .collect();
+
+            Ok(Value::array(chunks))
+        },
+    ),
+    NativeFunction::new("contains", VALUE, |_, args| {
+        Ok(Value::Bool(array(&args).borrow().contains(&args[1])))
+    }),
+    NativeFunction::new("enumerate", &[], |_, args| {
+        let pairs = array(&args)
+            .borrow()
+            .iter()
+            .enumerate()
+            .map(|(i, value)| Value::array(vec![Value::Number(i as f64), value.clone()]))
+            .collect();
+
+        Ok(Value::array(pairs))
+    }),
+    NativeFunction::new("filter", CALLBACK, |interpreter, args| {
+        let mut kept = Vec::new();
+
+        for (i, value) in elements(&args).into_iter().enumerate() {
+            if call(interpreter, &args[1], value.clone(), i)?.is_truthy() {
+                kept.push(value);
+            }
+        }
+
+        Ok(Value::array(kept))
+    }),
+    NativeFunction::new("find", CALLBACK, |interpreter, args| {
+        for (i, value) in elements(&args).into_iter().enumerate() {
+            if call(interpreter, &args[1], value.clone(), i)?.is_truthy() {
+                return Ok(value);
+            }
+        }
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new(
+        "flat",
+        &[Param::optional("depth", ValueKind::Number)],
+        |_, args| {
+            let depth = match optional(&args, 1) {
+                Some(_) => count("flat", "depth", number(&args, 1))?,
+                None => 1,
+            };
+
+            let mut flattened = Vec::new();
+            flatten(&elements(&args), depth, &mut flattened);
+
+            Ok(Value::array(flattened))
+        },
+    ),
+    NativeFunction::new(
+        "insert",
+        &[
+            Param::new("index", ValueKind::Number),
+            Param::new("value", ValueKind::Any),
+        ],
+        |_, args| {
+            let mut array = array(&args).borrow_mut();
+
+            // inserting at the length appends, negative indices counting from the end
+            let index = match number(&args, 1) {
+                n if n < 0.0 => array_index(n, array.len())?,
+                n => array_index(n, array.len() + 1)?,
+            };
+            array.insert(index, args[2].clone());
+
+            Ok(Value::Nil)
+        },
+    ),
+    NativeFunction::new(
+        "join",
+        &[Param::optional("separator", ValueKind::Str)],
+        |_, args| {
+            let separator = match optional(&args, 1) {
+                Some(_) => string(&args, 1).to_string(),
+                None => String::new(),
+            };
+
+            let parts = array(&args)
+                .borrow()
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>();
+
+            Ok(Value::str(&parts.join(&separator)))
+        },
+    ),
+    NativeFunction::new("len", &[], |_, args| {
+        Ok(Value::Number(array(&args).borrow().len() as f64))
+    }),
+    NativeFunction::new("map", CALLBACK, |interpreter, args| {
+        let mapped = elements(&args)
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| call(interpreter, &args[1], value, i))
+            .collect::<RuntimeResult<Vec<_>>>()?;
+
+        Ok(Value::array(mapped))
+    }),
+    NativeFunction::new("pop", &[], |_, args| {
+        Ok(array(&args).borrow_mut().pop().unwrap_or(Value::Nil))
+    }),
+    NativeFunction::new(
+        "push",
+        &[Param::variadic("values", ValueKind::Any)],
+        |_, args| {
+            let mut array = array(&args).borrow_mut();
+            array.extend_from_slice(&args[1..]);
+
+            Ok(Value::Number(array.len() as f64))
+        },
+    ),
+    NativeFunction::new(
+        "reduce",
+        &[
+            Param::new("function", ValueKind::Function),
+            Param::optional("initial", ValueKind::Any),
+        ],
+        |interpreter, args| {
+            let mut values = elements(&args).into_iter();
+
+            // without an initial value, the first element is the accumulator
+            let mut accumulator = match args.get(2) {
+                Some(initial) => initial.clone(),
+                None => values.next().ok_or_else(|| {
+                    RuntimeError::invalid_argument(
+                        "reduce",
+                        "an empty array needs an initial value".to_owned(),
+                    )
+                })?,
+            };
+
+            for value in values {
+                accumulator = interpreter.call(&args[1], vec![accumulator, value])?;
+            }
+
+            Ok(accumulator)
+        },
+    ),
+    NativeFunction::new(
+        "remove",
+        &[Param::new("index", ValueKind::Number)],
+        |_, args| {
+            let mut array = array(&args).borrow_mut();
+            let index = array_index(number(&args, 1), array.len())?;
+
+            Ok(array.remove(index))
+        },
+    ),
+    NativeFunction::new("reverse", &[], |_, args| {
+        array(&args).borrow_mut().reverse();
+        Ok(args[0].clone())
+    }),
+    NativeFunction::new(
+        "sort",
+        &[Param::optional("comparator", ValueKind::Function)],
+        |interpreter, args| {
+            // sorted outside of the array, which the comparator may read
+            let values = merge_sort(elements(&args), &mut |a, b| match optional(&args, 1) {
+                Some(comparator) => interpreter
+                    .call(comparator, vec![a.clone(), b.clone()])
+                    .and_then(|result| comparison(&result)),
+                None => compare(a, BinaryOperator::Smaller, b),
+            })?;
+
+            *array(&args).borrow_mut() = values;
+            Ok(args[0].clone())
+        },
+    ),
+    NativeFunction::new("unique", &[], |_, args| {
+        let mut seen = HashSet::new();
+        let mut unique: Vec<Value> = Vec::new();
+
+        for value in elements(&args) {
+            // values that cannot be hashed, like arrays, are compared one by one
+            let is_new = match Key::new(&value) {
+                Ok(key) => seen.insert(key),
+                Err(_) => !unique.contains(&value),
+            };
+
+            if is_new {
+                unique.push(value);
+            }
+        }
+
+        Ok(Value::array(unique))
+    }),
+    NativeFunction::new(
+        "zip",
+        &[Param::new("other", ValueKind::Any)],
+        |interpreter, args| {
+            let other = collect_values(interpreter, &args[1])?;
+
+            let pairs = elements(&args)
+                .into_iter()
+                .zip(other)
+                .map(|(a, b)| Value::array(vec![a, b]))
+                .collect();
+
+            Ok(Value::array(pairs))
+        },
+    ),
+];
+
+/// calls a callback with an element, script functions also receiving its index
+pub fn call(
+    interpreter: &mut Interpreter,
+    function: &Value,
+    value: Value,
+    index: usize,
+) -> RuntimeResult<Value> {
+    let args = match function {
+        Value::Function(_) => vec![value, Value::Number(index as f64)],
+        _ => vec![value],
+    };
+
+    interpreter.call(function, args)
+}
+
+/// the ordering returned by a comparator, negative numbers meaning less
+fn comparison(result: &Value) -> RuntimeResult<Ordering> {
+    match result {
+        Value::Number(n) if !n.is_nan() => Ok(n.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+        value => Err(RuntimeError::invalid_argument(
+            "sort",
+            format!(
+                "the comparator must return a number, found `{}`",
+                value.kind()
+            ),
+        )),
+    }
+}
+
+/// a stable sort stopping at the first error of the comparator, which may not be consistent
+/// as the one of `slice::sort_by` must be
+fn merge_sort(
+    mut values: Vec<Value>,
+    compare: &mut impl FnMut(&Value, &Value) -> RuntimeResult<Ordering>,
+) -> RuntimeResult<Vec<Value>> {
+    if values.len() < 2 {
+        return Ok(values);
+    }
+
+    let right = values.split_off(values.len() / 2);
+    let left = merge_sort(values, compare)?;
+    let right = merge_sort(right, compare)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // equal values keep their order, the left one going first
+        let next = match compare(a, b)? {
+            Ordering::Greater => right.next(),
+            _ => left.next(),
+        };
+        merged.extend(next);
+    }
+    merged.extend(left.chain(right));
+
+    Ok(merged)
+}
+
+fn flatten(values: &[Value], depth: usize, flattened: &mut Vec<Value>) {
+    for value in values {
+        match value {
+            Value::Array(nested) if depth > 0 => {
+                flatten(&nested.borrow(), depth - 1, flattened);
+            }
+            value => flattened.push(value.clone()),
+        }
+    }
+}
+
+fn array(args: &[Value]) -> &Array {
+    match &args[0] {
+        Value::Array(array) => array,
+        _ => unreachable!(),
+    }
+}
+
+/// a snapshot of the elements, so that callbacks may modify the array
+fn elements(args: &[Value]) -> Vec<Value> {
+    array(args).borrow().clone()
+}
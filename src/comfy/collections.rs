@@ -1 +1,371 @@
-// collections: linked lists, Set, HashMap, Queue, Stack, Tree, Deque, etc
+// Set, Map, Deque, Stack and PriorityQueue, with their methods,
+// a method receiving the value it is called on as its first argument
+
+use std::{cell::RefCell, rc::Rc};
+
+use hashbrown::{HashMap, HashSet};
+
+use super::{number, optional, NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    collections::{sorted_keys, Deque, Heap, Key, Map, PriorityQueue, Set, Stack},
+    errors::{RuntimeError, RuntimeResult},
+    iteration::collect_values,
+    value::{Value, ValueKind},
+    Interpreter,
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "collections",
+    functions: FUNCTIONS,
+    constants: &[],
+};
+
+const VALUES: &[Param] = &[Param::optional("values", ValueKind::Any)];
+const VALUE: &[Param] = &[Param::new("value", ValueKind::Any)];
+const KEY: &[Param] = &[Param::new("key", ValueKind::Any)];
+const OTHER: &[Param] = &[Param::new("other", ValueKind::Set)];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new("Deque", VALUES, |interpreter, args| {
+        let values = initial_values(interpreter, &args)?;
+        Ok(Value::Deque(Rc::new(RefCell::new(values.into()))))
+    }),
+    NativeFunction::new(
+        "Map",
+        &[Param::optional("entries", ValueKind::Any)],
+        |interpreter, args| {
+            let mut map = HashMap::new();
+
+            match optional(&args, 0) {
+                None => {}
+                Some(Value::Object(object)) => {
+                    for (key, value) in object.borrow().iter() {
+                        map.insert(Key::Str(key.as_str().into()), value.clone());
+                    }
+                }
+                Some(Value::Map(other)) => map = other.borrow().clone(),
+                Some(entries) => {
+                    for entry in collect_values(interpreter, entries)? {
+                        let (key, value) = pair(&entry)?;
+                        map.insert(Key::new(&key)?, value);
+                    }
+                }
+            }
+
+            Ok(Value::Map(Rc::new(RefCell::new(map))))
+        },
+    ),
+    NativeFunction::new("PriorityQueue", &[], |_, _| {
+        Ok(Value::PriorityQueue(Rc::new(RefCell::new(Heap::default()))))
+    }),
+    NativeFunction::new("Set", VALUES, |interpreter, args| {
+        let set = initial_values(interpreter, &args)?
+            .iter()
+            .map(Key::new)
+            .collect::<RuntimeResult<HashSet<_>>>()?;
+
+        Ok(Value::Set(Rc::new(RefCell::new(set))))
+    }),
+    NativeFunction::new("Stack", VALUES, |interpreter, args| {
+        let values = initial_values(interpreter, &args)?;
+        Ok(Value::Stack(Rc::new(RefCell::new(values))))
+    }),
+];
+
+pub static SET_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("add", VALUE, |_, args| {
+        let key = Key::new(&args[1])?;
+        Ok(Value::Bool(set(&args).borrow_mut().insert(key)))
+    }),
+    NativeFunction::new("clear", &[], |_, args| {
+        set(&args).borrow_mut().clear();
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("difference", OTHER, |_, args| {
+        let other = other_set(&args);
+        let difference = set(&args).borrow().difference(&other).cloned().collect();
+
+        Ok(Value::Set(Rc::new(RefCell::new(difference))))
+    }),
+    NativeFunction::new("has", VALUE, |_, args| {
+        let key = Key::new(&args[1])?;
+        Ok(Value::Bool(set(&args).borrow().contains(&key)))
+    }),
+    NativeFunction::new("intersection", OTHER, |_, args| {
+        let other = other_set(&args);
+        let intersection = set(&args).borrow().intersection(&other).cloned().collect();
+
+        Ok(Value::Set(Rc::new(RefCell::new(intersection))))
+    }),
+    NativeFunction::new("isEmpty", &[], |_, args| {
+        Ok(Value::Bool(set(&args).borrow().is_empty()))
+    }),
+    NativeFunction::new("isSubset", OTHER, |_, args| {
+        let other = other_set(&args);
+        Ok(Value::Bool(set(&args).borrow().is_subset(&other)))
+    }),
+    NativeFunction::new("len", &[], |_, args| {
+        Ok(Value::Number(set(&args).borrow().len() as f64))
+    }),
+    NativeFunction::new("remove", VALUE, |_, args| {
+        let key = Key::new(&args[1])?;
+        Ok(Value::Bool(set(&args).borrow_mut().remove(&key)))
+    }),
+    NativeFunction::new("toArray", &[], |_, args| {
+        let set = set(&args).borrow();
+        let values = sorted_keys(set.iter()).into_iter().map(Key::to_value);
+
+        Ok(Value::array(values.collect()))
+    }),
+    NativeFunction::new("union", OTHER, |_, args| {
+        let other = other_set(&args);
+        let union = set(&args).borrow().union(&other).cloned().collect();
+
+        Ok(Value::Set(Rc::new(RefCell::new(union))))
+    }),
+];
+
+pub static MAP_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("clear", &[], |_, args| {
+        map(&args).borrow_mut().clear();
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("entries", &[], |_, args| {
+        let map = map(&args).borrow();
+        let entries = sorted_keys(map.keys())
+            .into_iter()
+            .map(|key| Value::array(vec![key.to_value(), map[key].clone()]));
+
+        Ok(Value::array(entries.collect()))
+    }),
+    NativeFunction::new(
+        "get",
+        &[
+            Param::new("key", ValueKind::Any),
+            Param::optional("default", ValueKind::Any),
+        ],
+        |_, args| {
+            let key = Key::new(&args[1])?;
+            let default = args.get(2).cloned().unwrap_or(Value::Nil);
+
+            Ok(map(&args).borrow().get(&key).cloned().unwrap_or(default))
+        },
+    ),
+    NativeFunction::new("has", KEY, |_, args| {
+        let key = Key::new(&args[1])?;
+        Ok(Value::Bool(map(&args).borrow().contains_key(&key)))
+    }),
+    NativeFunction::new("isEmpty", &[], |_, args| {
+        Ok(Value::Bool(map(&args).borrow().is_empty()))
+    }),
+    NativeFunction::new("keys", &[], |_, args| {
+        let map = map(&args).borrow();
+        let keys = sorted_keys(map.keys()).into_iter().map(Key::to_value);
+
+        Ok(Value::array(keys.collect()))
+    }),
+    NativeFunction::new("len", &[], |_, args| {
+        Ok(Value::Number(map(&args).borrow().len() as f64))
+    }),
+    NativeFunction::new("remove", KEY, |_, args| {
+        let key = Key::new(&args[1])?;
+        Ok(map(&args).borrow_mut().remove(&key).unwrap_or(Value::Nil))
+    }),
+    NativeFunction::new(
+        "set",
+        &[
+            Param::new("key", ValueKind::Any),
+            Param::new("value", ValueKind::Any),
+        ],
+        |_, args| {
+            let key = Key::new(&args[1])?;
+            map(&args).borrow_mut().insert(key, args[2].clone());
+
+            Ok(Value::Nil)
+        },
+    ),
+    NativeFunction::new("values", &[], |_, args| {
+        let map = map(&args).borrow();
+        let values = sorted_keys(map.keys())
+            .into_iter()
+            .map(|key| map[key].clone());
+
+        Ok(Value::array(values.collect()))
+    }),
+];
+
+pub static DEQUE_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("clear", &[], |_, args| {
+        deque(&args).borrow_mut().clear();
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("isEmpty", &[], |_, args| {
+        Ok(Value::Bool(deque(&args).borrow().is_empty()))
+    }),
+    NativeFunction::new("len", &[], |_, args| {
+        Ok(Value::Number(deque(&args).borrow().len() as f64))
+    }),
+    NativeFunction::new("peekBack", &[], |_, args| {
+        Ok(or_nil(deque(&args).borrow().back().cloned()))
+    }),
+    NativeFunction::new("peekFront", &[], |_, args| {
+        Ok(or_nil(deque(&args).borrow().front().cloned()))
+    }),
+    NativeFunction::new("popBack", &[], |_, args| {
+        Ok(or_nil(deque(&args).borrow_mut().pop_back()))
+    }),
+    NativeFunction::new("popFront", &[], |_, args| {
+        Ok(or_nil(deque(&args).borrow_mut().pop_front()))
+    }),
+    NativeFunction::new("pushBack", VALUE, |_, args| {
+        deque(&args).borrow_mut().push_back(args[1].clone());
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("pushFront", VALUE, |_, args| {
+        deque(&args).borrow_mut().push_front(args[1].clone());
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("toArray", &[], |_, args| {
+        Ok(Value::array(
+            deque(&args).borrow().iter().cloned().collect(),
+        ))
+    }),
+];
+
+pub static STACK_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("clear", &[], |_, args| {
+        stack(&args).borrow_mut().clear();
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("isEmpty", &[], |_, args| {
+        Ok(Value::Bool(stack(&args).borrow().is_empty()))
+    }),
+    NativeFunction::new("len", &[], |_, args| {
+        Ok(Value::Number(stack(&args).borrow().len() as f64))
+    }),
+    NativeFunction::new("peek", &[], |_, args| {
+        Ok(or_nil(stack(&args).borrow().last().cloned()))
+    }),
+    NativeFunction::new("pop", &[], |_, args| {
+        Ok(or_nil(stack(&args).borrow_mut().pop()))
+    }),
+    NativeFunction::new("push", VALUE, |_, args| {
+        stack(&args).borrow_mut().push(args[1].clone());
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("toArray", &[], |_, args| {
+        Ok(Value::array(stack(&args).borrow().clone()))
+    }),
+];
+
+pub static PRIORITY_QUEUE_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("clear", &[], |_, args| {
+        heap(&args).borrow_mut().clear();
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("isEmpty", &[], |_, args| {
+        Ok(Value::Bool(heap(&args).borrow().len() == 0))
+    }),
+    NativeFunction::new("len", &[], |_, args| {
+        Ok(Value::Number(heap(&args).borrow().len() as f64))
+    }),
+    NativeFunction::new("peek", &[], |_, args| {
+        Ok(or_nil(heap(&args).borrow().peek().cloned()))
+    }),
+    NativeFunction::new("pop", &[], |_, args| {
+        Ok(or_nil(heap(&args).borrow_mut().pop()))
+    }),
+    NativeFunction::new(
+        "push",
+        &[
+            Param::new("value", ValueKind::Any),
+            Param::new("priority", ValueKind::Number),
+        ],
+        |_, args| {
+            let priority = number(&args, 2);
+            if priority.is_nan() {
+                return Err(RuntimeError::invalid_argument(
+                    "push",
+                    "the priority cannot be NaN".to_owned(),
+                ));
+            }
+            heap(&args).borrow_mut().push(args[1].clone(), priority);
+
+            Ok(Value::Nil)
+        },
+    ),
+    NativeFunction::new("toArray", &[], |_, args| {
+        let values = heap(&args).borrow().sorted().into_iter().map(|(_, v)| v);
+        Ok(Value::array(values.collect()))
+    }),
+];
+
+// the values a collection starts with, from anything a `for ... in` loop accepts
+fn initial_values(interpreter: &mut Interpreter, args: &[Value]) -> RuntimeResult<Vec<Value>> {
+    match optional(args, 0) {
+        Some(values) => collect_values(interpreter, values),
+        None => Ok(vec![]),
+    }
+}
+
+fn pair(entry: &Value) -> RuntimeResult<(Value, Value)> {
+    if let Value::Array(array) = entry {
+        if let [key, value] = array.borrow().as_slice() {
+            return Ok((key.clone(), value.clone()));
+        }
+    }
+
+    Err(RuntimeError::invalid_argument(
+        "Map",
+        format!("expected `[key, value]` pairs, found `{}`", entry.repr()),
+    ))
+}
+
+fn or_nil(value: Option<Value>) -> Value {
+    value.unwrap_or(Value::Nil)
+}
+
+// receiver accessors, the receiver type being guaranteed by the method lookup
+
+fn set(args: &[Value]) -> &Set {
+    match &args[0] {
+        Value::Set(set) => set,
+        _ => unreachable!(),
+    }
+}
+
+// the other set as a copy, so that `set.union(set)` does not borrow it twice
+fn other_set(args: &[Value]) -> HashSet<Key> {
+    match &args[1] {
+        Value::Set(set) => set.borrow().clone(),
+        _ => unreachable!(),
+    }
+}
+
+fn map(args: &[Value]) -> &Map {
+    match &args[0] {
+        Value::Map(map) => map,
+        _ => unreachable!(),
+    }
+}
+
+fn deque(args: &[Value]) -> &Deque {
+    match &args[0] {
+        Value::Deque(deque) => deque,
+        _ => unreachable!(),
+    }
+}
+
+fn stack(args: &[Value]) -> &Stack {
+    match &args[0] {
+        Value::Stack(stack) => stack,
+        _ => unreachable!(),
+    }
+}
+
+fn heap(args: &[Value]) -> &PriorityQueue {
+    match &args[0] {
+        Value::PriorityQueue(heap) => heap,
+        _ => unreachable!(),
+    }
+}
@@ -1 +1,230 @@
-// using env variables etc
+// environment variables and the context of the running process
+
+use std::{env, fs, io::ErrorKind, sync::OnceLock};
+
+use hashbrown::HashMap;
+
+use super::{number, optional, string, Constant, NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    value::{Value, ValueKind},
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "env",
+    functions: FUNCTIONS,
+    constants: &[("args", Constant::Computed(args))],
+};
+
+// the arguments following the script path on the command line
+static ARGS: OnceLock<Vec<String>> = OnceLock::new();
+
+pub fn set_args(args: Vec<String>) {
+    ARGS.get_or_init(|| args);
+}
+
+fn args() -> Value {
+    let args = ARGS.get().map(Vec::as_slice).unwrap_or_default();
+    Value::array(args.iter().map(|arg| Value::str(arg)).collect())
+}
+
+const NAME: &[Param] = &[Param::new("name", ValueKind::Str)];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new("chdir", &[Param::new("path", ValueKind::Str)], |_, args| {
+        let path = string(&args, 0);
+        env::set_current_dir(&*path).map_err(|e| RuntimeError::io(&e, &path))?;
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("cwd", &[], |_, _| {
+        let cwd = env::current_dir().map_err(|e| RuntimeError::io(&e, "."))?;
+        Ok(Value::str(&cwd.to_string_lossy()))
+    }),
+    NativeFunction::new(
+        "exit",
+        &[Param::optional("code", ValueKind::Number)],
+        |_, args| {
+            let code = match optional(&args, 0) {
+                Some(_) => number(&args, 0),
+                None => 0.0,
+            };
+
+            if code.fract() != 0.0 || code < i32::MIN as f64 || code > i32::MAX as f64 {
+                return Err(RuntimeError::invalid_argument(
+                    "exit",
+                    format!("the exit code must be an integer, found `{code}`"),
+                ));
+            }
+
+            Err(RuntimeError::exit(code as i32))
+        },
+    ),
+    NativeFunction::new("get", NAME, |_, args| {
+        let name = string(&args, 0);
+
+        match env::var(&*name) {
+            Ok(value) => Ok(Value::str(&value)),
+            Err(_) => Ok(Value::Nil),
+        }
+    }),
+    NativeFunction::new(
+        "load",
+        &[
+            Param::optional("path", ValueKind::Str),
+            Param::optional("override", ValueKind::Bool),
+        ],
+        |_, args| {
+            let path = optional(&args, 0).map(|_| string(&args, 0));
+            let override_existing = matches!(optional(&args, 1), Some(Value::Bool(true)));
+
+            let text = match &path {
+                Some(path) => {
+                    fs::read_to_string(&**path).map_err(|e| RuntimeError::io(&e, path))?
+                }
+                // the default `.env` file is optional
+                None => match fs::read_to_string(".env") {
+                    Ok(text) => text,
+                    Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+                    Err(e) => return Err(RuntimeError::io(&e, ".env")),
+                },
+            };
+
+            let path = path.as_deref().unwrap_or(".env");
+            let mut loaded = HashMap::new();
+
+            for (name, value) in parse_dotenv(&text, path)? {
+                if override_existing || env::var_os(&name).is_none() {
+                    env::set_var(&name, &value);
+                }
+                loaded.insert(name, Value::str(&value));
+            }
+
+            Ok(Value::object(loaded))
+        },
+    ),
+    NativeFunction::new("remove", NAME, |_, args| {
+        let name = string(&args, 0);
+        check_name("remove", &name)?;
+        env::remove_var(&*name);
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new(
+        "set",
+        &[
+            Param::new("name", ValueKind::Str),
+            Param::new("value", ValueKind::Str),
+        ],
+        |_, args| {
+            let name = string(&args, 0);
+            let value = string(&args, 1);
+
+            check_name("set", &name)?;
+            if value.contains('\0') {
+                return Err(RuntimeError::invalid_argument(
+                    "set",
+                    "the value cannot contain a NUL character".to_owned(),
+                ));
+            }
+            env::set_var(&*name, &*value);
+
+            Ok(Value::Nil)
+        },
+    ),
+    NativeFunction::new("vars", &[], |_, _| {
+        let vars = env::vars_os()
+            .map(|(name, value)| {
+                (
+                    name.to_string_lossy().into_owned(),
+                    Value::str(&value.to_string_lossy()),
+                )
+            })
+            .collect();
+
+        Ok(Value::object(vars))
+    }),
+];
+
+// names the OS would reject, std panics on them
+fn check_name(function: &str, name: &str) -> RuntimeResult<()> {
+    if name.is_empty() || name.contains(['=', '\0']) {
+        return Err(RuntimeError::invalid_argument(
+            function,
+            format!("`{name}` is not a valid environment variable name"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// parses the `KEY=value` lines of a `.env` file, ignoring blank lines and `#` comments
+fn parse_dotenv(text: &str, path: &str) -> RuntimeResult<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let invalid = |reason: &str| {
+            RuntimeError::invalid_argument(
+                "load",
+                format!("line {} of `{path}` {reason}", number + 1),
+            )
+        };
+
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid("is not a `KEY=value` pair"))?;
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(invalid("is not a `KEY=value` pair"));
+        }
+
+        // rejected by the OS, as `set` does
+        let value = dotenv_value(value.trim());
+        if name.contains('\0') || value.contains('\0') {
+            return Err(invalid("contains a NUL character"));
+        }
+
+        vars.push((name.to_owned(), value));
+    }
+
+    Ok(vars)
+}
+
+fn dotenv_value(value: &str) -> String {
+    if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        return value[1..value.len() - 1].to_owned();
+    }
+
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        let inner = &value[1..value.len() - 1];
+        let mut result = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+
+        while let Some(c) = chars.next() {
+            match (c, chars.clone().next()) {
+                ('\\', Some('n')) => result.push('\n'),
+                ('\\', Some('t')) => result.push('\t'),
+                ('\\', Some(c @ ('"' | '\\'))) => result.push(c),
+                _ => {
+                    result.push(c);
+                    continue;
+                }
+            }
+            chars.next();
+        }
+
+        return result;
+    }
+
+    // unquoted values end at an inline comment
+    match value.find(" #") {
+        Some(index) => value[..index].trim_end().to_owned(),
+        None => value.to_owned(),
+    }
+}
@@ -1 +1,378 @@
-// handling filesystem, paths and etct
+// handling filesystem, paths and etc
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use hashbrown::HashMap;
+
+use super::{string, NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    value::{Value, ValueKind},
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "fs",
+    functions: FUNCTIONS,
+    constants: &[],
+};
+
+const PATH: &[Param] = &[Param::new("path", ValueKind::Str)];
+const PATH_DATA: &[Param] = &[
+    Param::new("path", ValueKind::Str),
+    Param::new("data", ValueKind::Any),
+];
+const FROM_TO: &[Param] = &[
+    Param::new("from", ValueKind::Str),
+    Param::new("to", ValueKind::Str),
+];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new("append", PATH_DATA, |_, args| {
+        let path = string(&args, 0);
+        let data = data_bytes("append", &args[1])?;
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&*path)
+            .and_then(|mut file| file.write_all(&data))
+            .map_err(|e| RuntimeError::io(&e, &path))?;
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("copy", FROM_TO, |_, args| {
+        let (from, to) = (string(&args, 0), string(&args, 1));
+
+        copy(Path::new(&*from), Path::new(&*to)).map_err(|e| RuntimeError::io(&e, &from))?;
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("exists", PATH, |_, args| {
+        Ok(Value::Bool(Path::new(&*string(&args, 0)).exists()))
+    }),
+    NativeFunction::new(
+        "glob",
+        &[Param::new("pattern", ValueKind::Str)],
+        |_, args| {
+            let pattern = string(&args, 0);
+            let paths = glob(&pattern)?;
+
+            Ok(paths_to_array(paths))
+        },
+    ),
+    // read lazily, so that large files are streamed line by line
+    NativeFunction::new("lines", PATH, |_, args| {
+        let path = string(&args, 0);
+
+        let file = fs::File::open(&*path).map_err(|e| RuntimeError::io(&e, &path))?;
+        let lines = BufReader::new(file).lines().map(move |line| match line {
+            Ok(line) => Ok(Value::str(&line)),
+            Err(e) => Err(RuntimeError::io(&e, &path)),
+        });
+
+        Ok(Value::iterator(lines))
+    }),
+    NativeFunction::new("list", PATH, |_, args| {
+        let path = string(&args, 0);
+
+        let mut names = fs::read_dir(&*path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|e| e.file_name().to_string_lossy().to_string()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|e| RuntimeError::io(&e, &path))?;
+        names.sort();
+
+        Ok(Value::array(
+            names.iter().map(|name| Value::str(name)).collect(),
+        ))
+    }),
+    NativeFunction::new("mkdir", PATH, |_, args| {
+        let path = string(&args, 0);
+
+        fs::create_dir_all(&*path).map_err(|e| RuntimeError::io(&e, &path))?;
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("move", FROM_TO, |_, args| {
+        let (from, to) = (string(&args, 0), string(&args, 1));
+
+        fs::rename(&*from, &*to).map_err(|e| RuntimeError::io(&e, &from))?;
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("read", PATH, |_, args| {
+        let path = string(&args, 0);
+
+        let content = fs::read_to_string(&*path).map_err(|e| RuntimeError::io(&e, &path))?;
+
+        Ok(Value::str(&content))
+    }),
+    // reads on another thread, the promise resolving to the content
+    NativeFunction::new("readAsync", PATH, |interpreter, args| {
+        let path = string(&args, 0).to_string();
+
+        Ok(interpreter.run_in_background(move || {
+            let content = fs::read_to_string(&path).map_err(|e| RuntimeError::io(&e, &path));
+            Box::new(move || content.map(|content| Value::str(&content)))
+        }))
+    }),
+    NativeFunction::new("readBytes", PATH, |_, args| {
+        let path = string(&args, 0);
+
+        let content = fs::read(&*path).map_err(|e| RuntimeError::io(&e, &path))?;
+
+        Ok(Value::Bytes(content.into()))
+    }),
+    NativeFunction::new("remove", PATH, |_, args| {
+        let path = string(&args, 0);
+
+        let result = match fs::symlink_metadata(&*path) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&*path),
+            _ => fs::remove_file(&*path),
+        };
+        result.map_err(|e| RuntimeError::io(&e, &path))?;
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("stat", PATH, |_, args| {
+        let path = string(&args, 0);
+
+        let metadata = fs::metadata(&*path).map_err(|e| RuntimeError::io(&e, &path))?;
+        let is_symlink = fs::symlink_metadata(&*path).is_ok_and(|m| m.file_type().is_symlink());
+
+        let mut stat = HashMap::new();
+        stat.insert("size".to_owned(), Value::Number(metadata.len() as f64));
+        stat.insert("isFile".to_owned(), Value::Bool(metadata.is_file()));
+        stat.insert("isDir".to_owned(), Value::Bool(metadata.is_dir()));
+        stat.insert("isSymlink".to_owned(), Value::Bool(is_symlink));
+        stat.insert(
+            "readonly".to_owned(),
+            Value::Bool(metadata.permissions().readonly()),
+        );
+        stat.insert("modified".to_owned(), timestamp(metadata.modified()));
+        stat.insert("created".to_owned(), timestamp(metadata.created()));
+
+        Ok(Value::object(stat))
+    }),
+    NativeFunction::new("tempDir", &[], |_, _| {
+        Ok(Value::str(&std::env::temp_dir().to_string_lossy()))
+    }),
+    NativeFunction::new("walk", PATH, |_, args| {
+        let path = string(&args, 0);
+
+        let mut paths = Vec::new();
+        walk(Path::new(&*path), &mut paths).map_err(|e| RuntimeError::io(&e, &path))?;
+
+        Ok(paths_to_array(paths))
+    }),
+    NativeFunction::new("write", PATH_DATA, |_, args| {
+        let path = string(&args, 0);
+        let data = data_bytes("write", &args[1])?;
+
+        fs::write(&*path, data).map_err(|e| RuntimeError::io(&e, &path))?;
+
+        Ok(Value::Nil)
+    }),
+];
+
+/// the content written by `write` and `append`, either a string or bytes
+fn data_bytes(function: &str, data: &Value) -> RuntimeResult<Vec<u8>> {
+    match data {
+        Value::Str(s) => Ok(s.as_bytes().to_vec()),
+        Value::Bytes(bytes) => Ok(bytes.to_vec()),
+        value => Err(RuntimeError::argument_type(
+            function,
+            "data",
+            ValueKind::Str,
+            value.kind(),
+        )),
+    }
+}
+
+/// milliseconds since the unix epoch, nil when the platform does not provide it
+fn timestamp(time: std::io::Result<SystemTime>) -> Value {
+    time.ok()
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(Value::Nil, |duration| {
+            Value::Number(duration.as_millis() as f64)
+        })
+}
+
+fn paths_to_array(mut paths: Vec<PathBuf>) -> Value {
+    paths.sort();
+
+    Value::array(
+        paths
+            .iter()
+            .map(|path| Value::str(&path.to_string_lossy()))
+            .collect(),
+    )
+}
+
+fn copy(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy(&entry.path(), &to.join(entry.file_name()))?;
+    }
+
+    Ok(())
+}
+
+/// every file below a directory, symlinks are not followed
+fn walk(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            walk(&path, paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// expands a pattern where `*` and `?` match within a path component,
+/// `[abc]` matches a set of characters and `**` matches any number of directories
+fn glob(pattern: &str) -> RuntimeResult<Vec<PathBuf>> {
+    let components = pattern.split('/').collect::<Vec<_>>();
+
+    for component in &components {
+        if component.contains("**") && *component != "**" {
+            return Err(RuntimeError::invalid_glob(
+                pattern,
+                "`**` must be a whole path component",
+            ));
+        }
+        if component.matches('[').count() != component.matches(']').count() {
+            return Err(RuntimeError::invalid_glob(pattern, "unclosed `[`"));
+        }
+    }
+
+    // the literal prefix is where the search starts
+    let is_literal = |c: &&&str| !c.contains(['*', '?', '[']);
+    let prefix_len = components.iter().take_while(is_literal).count();
+
+    let base = if prefix_len == 0 {
+        PathBuf::new()
+    } else if pattern.starts_with('/') && prefix_len == 1 {
+        PathBuf::from("/")
+    } else {
+        PathBuf::from(components[..prefix_len].join("/"))
+    };
+
+    if prefix_len == components.len() {
+        return Ok(if base.exists() { vec![base] } else { vec![] });
+    }
+
+    let mut matches = Vec::new();
+    glob_in(&base, &components[prefix_len..], &mut matches);
+    matches.dedup();
+
+    Ok(matches)
+}
+
+fn glob_in(dir: &Path, components: &[&str], matches: &mut Vec<PathBuf>) {
+    let Some((component, rest)) = components.split_first() else {
+        matches.push(dir.to_path_buf());
+        return;
+    };
+
+    if *component == "**" {
+        // zero directories
+        glob_in(dir, rest, matches);
+    }
+
+    let read_from = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let Ok(entries) = fs::read_dir(read_from) else {
+        return;
+    };
+
+    let mut entries = entries.filter_map(Result::ok).collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = dir.join(&name);
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+
+        if *component == "**" {
+            if is_dir && !name.starts_with('.') {
+                glob_in(&path, components, matches);
+            }
+        } else if component_matches(component, &name) {
+            if rest.is_empty() {
+                matches.push(path);
+            } else if is_dir {
+                glob_in(&path, rest, matches);
+            }
+        }
+    }
+}
+
+fn component_matches(pattern: &str, name: &str) -> bool {
+    // hidden files are only matched explicitly
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+
+    matches_from(&pattern, &name)
+}
+
+fn matches_from(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| matches_from(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && matches_from(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let Some(end) = pattern.iter().position(|c| *c == ']') else {
+                return false;
+            };
+            let Some(c) = name.first() else {
+                return false;
+            };
+
+            let set = &pattern[1..end];
+            let (negated, set) = match set.first() {
+                Some('!') => (true, &set[1..]),
+                _ => (false, set),
+            };
+
+            let mut found = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == '-' {
+                    found |= (set[i]..=set[i + 2]).contains(c);
+                    i += 3;
+                } else {
+                    found |= set[i] == *c;
+                    i += 1;
+                }
+            }
+
+            found != negated && matches_from(&pattern[end + 1..], &name[1..])
+        }
+        Some(p) => name.first() == Some(p) && matches_from(&pattern[1..], &name[1..]),
+    }
+}
@@ -1,12 +1,444 @@
-// standard library http package
-use lazy_static::lazy_static;
+// standard library http package, a blocking client returning responses as objects
+// and a server calling handlers on the serving thread
+
+mod server;
+
+use std::{
+    io::Read,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use hashbrown::HashMap;
 
-lazy_static! {
-    static ref HTTP_FUNCTIONS: HashMap<&'static str, &'static str> = {
-        let mut hash = HashMap::new();
-        hash.insert("test", "test");
-        hash
+use super::{json, optional, string, NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    value::{Method, Value, ValueKind},
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "http",
+    functions: FUNCTIONS,
+    constants: &[],
+};
+
+const URL_OPTIONS: &[Param] = &[
+    Param::new("url", ValueKind::Str),
+    Param::optional("options", ValueKind::Object),
+];
+const URL_BODY_OPTIONS: &[Param] = &[
+    Param::new("url", ValueKind::Str),
+    Param::optional("body", ValueKind::Any),
+    Param::optional("options", ValueKind::Object),
+];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new("Router", &[], server::router),
+    NativeFunction::new("delete", URL_OPTIONS, |_, args| {
+        send("DELETE", &string(&args, 0), optional(&args, 1), None)
+    }),
+    // sends the request on another thread, the promise resolving to the response;
+    // `method` is read from the options and defaults to GET
+    NativeFunction::new("fetch", URL_OPTIONS, |interpreter, args| {
+        let (method, options) = match optional(&args, 1) {
+            Some(Value::Object(properties)) => {
+                let mut properties = properties.borrow().clone();
+                let method = match properties.remove("method") {
+                    Some(method) => http_method("fetch", &method)?,
+                    None => "GET".to_owned(),
+                };
+                (method, Some(Value::object(properties)))
+            }
+            _ => ("GET".to_owned(), None),
+        };
+
+        let request = Request::new(&method, &string(&args, 0), options.as_ref(), None)?;
+
+        Ok(interpreter.run_in_background(move || {
+            let response = request.perform();
+            Box::new(move || response.map(Response::into_value))
+        }))
+    }),
+    NativeFunction::new("get", URL_OPTIONS, |_, args| {
+        send("GET", &string(&args, 0), optional(&args, 1), None)
+    }),
+    NativeFunction::new(
+        "json",
+        &[
+            Param::new("value", ValueKind::Any),
+            Param::optional("status", ValueKind::Number),
+        ],
+        server::json_response,
+    ),
+    NativeFunction::new("post", URL_BODY_OPTIONS, |_, args| {
+        send(
+            "POST",
+            &string(&args, 0),
+            optional(&args, 2),
+            optional(&args, 1),
+        )
+    }),
+    NativeFunction::new("put", URL_BODY_OPTIONS, |_, args| {
+        send(
+            "PUT",
+            &string(&args, 0),
+            optional(&args, 2),
+            optional(&args, 1),
+        )
+    }),
+    NativeFunction::new(
+        "request",
+        &[
+            Param::new("method", ValueKind::Str),
+            Param::new("url", ValueKind::Str),
+            Param::optional("options", ValueKind::Object),
+        ],
+        |_, args| {
+            let method = http_method("request", &args[0])?;
+
+            send(&method, &string(&args, 1), optional(&args, 2), None)
+        },
+    ),
+    NativeFunction::new(
+        "serve",
+        &[
+            Param::new("port", ValueKind::Number),
+            Param::new("handler", ValueKind::Any),
+            Param::optional("options", ValueKind::Object),
+        ],
+        server::serve,
+    ),
+    NativeFunction::new(
+        "status",
+        &[
+            Param::new("code", ValueKind::Number),
+            Param::optional("body", ValueKind::Any),
+        ],
+        server::status_response,
+    ),
+];
+
+// methods of the response and request objects, bound to their body
+static TEXT: NativeFunction = NativeFunction::new("text", &[], |_, args| {
+    Ok(Value::str(&String::from_utf8_lossy(body(&args))))
+});
+static JSON: NativeFunction = NativeFunction::new("json", &[], |_, args| {
+    json::parse(&String::from_utf8_lossy(body(&args)))
+});
+static BYTES: NativeFunction =
+    NativeFunction::new("bytes", &[], |_, args| Ok(Value::Bytes(body(&args).into())));
+
+const OPTIONS: &str = "headers, query, json, body or timeout";
+
+/// everything a request is made of besides its method and url
+#[derive(Default)]
+struct Options {
+    headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    body: Option<(Vec<u8>, &'static str)>,
+    timeout: Option<Duration>,
+}
+
+fn send(
+    method: &str,
+    url: &str,
+    options: Option<&Value>,
+    body: Option<&Value>,
+) -> RuntimeResult<Value> {
+    Request::new(method, url, options, body)?
+        .perform()
+        .map(Response::into_value)
+}
+
+// a request ready to be sent, from any thread
+struct Request {
+    method: String,
+    url: String,
+    options: Options,
+}
+
+// a response with its body read, turned into an object on the script's thread
+struct Response {
+    status: u16,
+    status_text: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn new(
+        method: &str,
+        url: &str,
+        options: Option<&Value>,
+        body: Option<&Value>,
+    ) -> RuntimeResult<Self> {
+        let mut options = match options {
+            Some(options) => Options::new(method, options)?,
+            None => Options::default(),
+        };
+
+        if let Some(body) = body {
+            options.body = Some(encode_body(method, body)?);
+        }
+
+        Ok(Request {
+            method: method.to_owned(),
+            url: url.to_owned(),
+            options,
+        })
+    }
+
+    fn perform(self) -> RuntimeResult<Response> {
+        let Request {
+            method,
+            url,
+            options,
+        } = self;
+        let mut request = ureq::request(&method, &url);
+
+        for (name, value) in &options.headers {
+            request = request.set(name, value);
+        }
+        for (name, value) in &options.query {
+            request = request.query(name, value);
+        }
+        if let Some(timeout) = options.timeout {
+            request = request.timeout(timeout);
+        }
+
+        let result = match &options.body {
+            Some((bytes, content_type)) => {
+                let has_content_type = options
+                    .headers
+                    .iter()
+                    .any(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+                if !has_content_type {
+                    request = request.set("Content-Type", content_type);
+                }
+
+                request.send_bytes(bytes)
+            }
+            None => request.call(),
+        };
+
+        let response = match result {
+            Ok(response) => response,
+            // error statuses are still responses, scripts check `status` or `ok`
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(ureq::Error::Transport(transport)) => {
+                let kind = match std::error::Error::source(&transport)
+                    .and_then(|source| source.downcast_ref::<std::io::Error>())
+                {
+                    Some(err) if is_timeout(err) => "TimedOut".to_owned(),
+                    _ => format!("{:?}", transport.kind()),
+                };
+
+                return Err(RuntimeError::http(
+                    &method,
+                    &url,
+                    kind,
+                    transport.to_string(),
+                ));
+            }
+        };
+
+        Response::read(&method, response)
+    }
+}
+
+impl Options {
+    fn new(method: &str, options: &Value) -> RuntimeResult<Self> {
+        let Value::Object(properties) = options else {
+            unreachable!()
+        };
+
+        let mut options = Options::default();
+
+        for (name, value) in properties.borrow().iter() {
+            match name.as_str() {
+                "headers" => options.headers = pairs(method, name, value)?,
+                "query" => options.query = pairs(method, name, value)?,
+                "json" => {
+                    let text = json::stringify(value, None)?;
+                    options.body = Some((text.into_bytes(), "application/json"));
+                }
+                "body" => options.body = Some(encode_body(method, value)?),
+                "timeout" => options.timeout = Some(timeout(method, value)?),
+                _ => {
+                    return Err(RuntimeError::invalid_argument(
+                        method,
+                        format!("unknown option `{name}`, expected {OPTIONS}"),
+                    ))
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// an HTTP method written in any case, as sent
+fn http_method(function: &str, value: &Value) -> RuntimeResult<String> {
+    let method = match value {
+        Value::Str(method) => method.to_uppercase(),
+        _ => String::new(),
+    };
+
+    if method.is_empty() || !method.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(RuntimeError::invalid_argument(
+            function,
+            format!("`{value}` is not an HTTP method"),
+        ));
+    }
+
+    Ok(method)
+}
+
+/// strings and bytes are sent as they are, any other value as JSON
+fn encode_body(method: &str, body: &Value) -> RuntimeResult<(Vec<u8>, &'static str)> {
+    let encoded = match body {
+        Value::Str(s) => (s.as_bytes().to_vec(), "text/plain; charset=utf-8"),
+        Value::Bytes(bytes) => (bytes.to_vec(), "application/octet-stream"),
+        value => {
+            let text = json::stringify(value, None).map_err(|mut err| {
+                err.notes
+                    .push(format!("while encoding the body of `{method}`"));
+                err
+            })?;
+            (text.into_bytes(), "application/json")
+        }
     };
+
+    Ok(encoded)
+}
+
+/// the properties of a headers or query object, values being converted to strings
+fn pairs(method: &str, option: &str, value: &Value) -> RuntimeResult<Vec<(String, String)>> {
+    let Value::Object(properties) = value else {
+        return Err(RuntimeError::invalid_argument(
+            method,
+            format!("`{option}` must be an object, found `{}`", value.kind()),
+        ));
+    };
+
+    let properties = properties.borrow();
+    let mut names = properties.keys().collect::<Vec<_>>();
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|name| match &properties[name] {
+            Value::Str(s) => Ok((name.clone(), s.to_string())),
+            value @ (Value::Number(_) | Value::Bool(_)) => Ok((name.clone(), value.to_string())),
+            value => Err(RuntimeError::invalid_argument(
+                method,
+                format!(
+                    "`{option}.{name}` must be a string, a number or a boolean, found `{}`",
+                    value.kind()
+                ),
+            )),
+        })
+        .collect()
+}
+
+fn timeout(method: &str, value: &Value) -> RuntimeResult<Duration> {
+    let invalid = || {
+        RuntimeError::invalid_argument(
+            method,
+            format!("`timeout` must be a duration or milliseconds, found `{value}`"),
+        )
+    };
+
+    match value {
+        // negative, not a number, or too long to be represented or to end at a deadline
+        Value::Number(ms) | Value::Duration(ms) => Duration::try_from_secs_f64(ms / 1000.0)
+            .ok()
+            .filter(|timeout| Instant::now().checked_add(*timeout).is_some())
+            .ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+fn is_timeout(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+    )
+}
+
+impl Response {
+    /// the status, headers and the whole body
+    fn read(method: &str, response: ureq::Response) -> RuntimeResult<Self> {
+        let status = response.status();
+        let status_text = response.status_text().to_owned();
+        let url = response.get_url().to_owned();
+
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .map(|name| {
+                let value = response.all(&name).join(", ");
+                (name.to_lowercase(), value)
+            })
+            .collect();
+
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut body)
+            .map_err(|err| {
+                let kind = if is_timeout(&err) {
+                    "TimedOut".to_owned()
+                } else {
+                    format!("{:?}", err.kind())
+                };
+                RuntimeError::http(method, &url, kind, err.to_string())
+            })?;
+
+        Ok(Response {
+            status,
+            status_text,
+            url,
+            headers,
+            body,
+        })
+    }
+
+    fn into_value(self) -> Value {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::str(value)))
+            .collect();
+
+        let mut object = HashMap::new();
+        object.insert("status".to_owned(), Value::Number(self.status as f64));
+        object.insert("statusText".to_owned(), Value::str(&self.status_text));
+        object.insert(
+            "ok".to_owned(),
+            Value::Bool((200..300).contains(&self.status)),
+        );
+        object.insert("url".to_owned(), Value::str(&self.url));
+        object.insert("headers".to_owned(), Value::object(headers));
+
+        let body = Value::Bytes(self.body.into());
+        for function in [&TEXT, &JSON, &BYTES] {
+            let method = Method {
+                receiver: body.clone(),
+                function,
+            };
+            object.insert(function.name.to_owned(), Value::Method(Rc::new(method)));
+        }
+
+        Value::object(object)
+    }
+}
+
+fn body(args: &[Value]) -> &[u8] {
+    match &args[0] {
+        Value::Bytes(bytes) => bytes,
+        _ => unreachable!(),
+    }
 }
@@ -0,0 +1,483 @@
+// serving requests on the calling thread, handlers being functions or routers
+
+use std::{io::Cursor, rc::Rc, sync::Arc, time::Duration};
+
+use hashbrown::HashMap;
+use tiny_http::{Header, Request, Response, Server};
+
+use super::JSON;
+use crate::{
+    comfy::{json, number, optional, string, NativeFunction, Param},
+    interpreter::{
+        errors::{RuntimeError, RuntimeResult},
+        sendable::Shared,
+        value::{Method, Value, ValueKind},
+        Interpreter,
+    },
+};
+
+// how often a server waiting for requests checks its shutdown channel
+const SHUTDOWN_POLL: Duration = Duration::from_millis(50);
+
+const SERVE_OPTIONS: &str = "host, ready or shutdown";
+
+static ROUTE: NativeFunction = NativeFunction::new("route", ROUTE_PARAMS, |_, args| {
+    let Value::Array(routes) = &args[0] else {
+        unreachable!()
+    };
+
+    let pattern = string(&args, 2);
+    if !pattern.starts_with('/') {
+        return Err(RuntimeError::invalid_argument(
+            "route",
+            format!("patterns start with `/`, found `{pattern}`"),
+        ));
+    }
+
+    let method = Value::str(&string(&args, 1).to_uppercase());
+    let route = Value::array(vec![method, Value::Str(pattern), args[3].clone()]);
+    routes.borrow_mut().push(route);
+
+    Ok(Value::Nil)
+});
+static HANDLE: NativeFunction = NativeFunction::new("handle", HANDLE_PARAMS, dispatch);
+
+const ROUTE_PARAMS: &[Param] = &[
+    Param::new("method", ValueKind::Str),
+    Param::new("pattern", ValueKind::Str),
+    Param::new("handler", ValueKind::Function),
+];
+const HANDLE_PARAMS: &[Param] = &[Param::new("request", ValueKind::Object)];
+
+/// a router as an object, `route` registering routes and `handle` dispatching requests
+pub fn router(_: &mut Interpreter, _: Vec<Value>) -> RuntimeResult<Value> {
+    let routes = Value::array(Vec::new());
+
+    let mut object = HashMap::new();
+    for function in [&ROUTE, &HANDLE] {
+        let method = Method {
+            receiver: routes.clone(),
+            function,
+        };
+        object.insert(function.name.to_owned(), Value::Method(Rc::new(method)));
+    }
+
+    Ok(Value::object(object))
+}
+
+/// a JSON response, `status` defaulting to 200
+pub fn json_response(_: &mut Interpreter, args: Vec<Value>) -> RuntimeResult<Value> {
+    let status = match optional(&args, 1) {
+        Some(_) => status_code("json", number(&args, 1))?,
+        None => 200,
+    };
+
+    let mut headers = HashMap::new();
+    headers.insert("content-type".to_owned(), Value::str("application/json"));
+
+    let body = Value::str(&json::stringify(&args[0], None)?);
+    Ok(response_object(status, headers, body))
+}
+
+/// a response with a status code and an optional body
+pub fn status_response(_: &mut Interpreter, args: Vec<Value>) -> RuntimeResult<Value> {
+    let status = status_code("status", number(&args, 0))?;
+    let body = optional(&args, 1).cloned().unwrap_or(Value::Nil);
+
+    Ok(response_object(status, HashMap::new(), body))
+}
+
+/// serves requests until the shutdown channel is closed or receives a value,
+/// the request being handled when it fires still getting its response
+pub fn serve(interpreter: &mut Interpreter, args: Vec<Value>) -> RuntimeResult<Value> {
+    let port = number(&args, 0);
+    if port.fract() != 0.0 || !(0.0..=65535.0).contains(&port) {
+        return Err(RuntimeError::invalid_argument(
+            "serve",
+            format!("`{port}` is not a port number"),
+        ));
+    }
+
+    let handler = handler(&args[1])?;
+
+    let mut host = Rc::from("0.0.0.0");
+    let mut ready = None;
+    let mut shutdown = None;
+
+    if let Some(Value::Object(options)) = optional(&args, 2) {
+        for (name, value) in options.borrow().iter() {
+            match (name.as_str(), value) {
+                ("host", Value::Str(s)) => host = Rc::clone(s),
+                ("ready", value) if value.kind() == ValueKind::Function => {
+                    ready = Some(value.clone())
+                }
+                ("shutdown", Value::Shared(Shared::Channel(channel))) => {
+                    shutdown = Some(Arc::clone(channel))
+                }
+                _ => {
+                    return Err(RuntimeError::invalid_argument(
+                        "serve",
+                        format!("invalid option `{name}`, expected {SERVE_OPTIONS}"),
+                    ))
+                }
+            }
+        }
+    }
+
+    let address = format!("{host}:{port}");
+    let server = Server::http(&address).map_err(|err| {
+        let err = std::io::Error::other(err.to_string());
+        RuntimeError::io(&err, &address)
+    })?;
+
+    if let Some(ready) = ready {
+        let port = server.server_addr().to_ip().map_or(0, |addr| addr.port());
+        interpreter.call(&ready, vec![Value::Number(port as f64)])?;
+    }
+
+    loop {
+        if let Some(channel) = &shutdown {
+            if channel.is_closed() || channel.try_recv().is_some() {
+                break;
+            }
+        }
+
+        let request = match server.recv_timeout(SHUTDOWN_POLL) {
+            Ok(Some(request)) => request,
+            Ok(None) => continue,
+            Err(err) => return Err(RuntimeError::io(&err, &address)),
+        };
+
+        handle(interpreter, &handler, request)?;
+    }
+
+    Ok(Value::Nil)
+}
+
+/// the function called for each request, routers being called through `handle`
+fn handler(value: &Value) -> RuntimeResult<Value> {
+    if let Value::Object(object) = value {
+        if let Some(handle) = object.borrow().get("handle") {
+            if handle.kind() == ValueKind::Function {
+                return Ok(handle.clone());
+            }
+        }
+    }
+
+    if value.kind() == ValueKind::Function {
+        return Ok(value.clone());
+    }
+
+    Err(RuntimeError::invalid_argument(
+        "serve",
+        format!(
+            "the handler must be a function or a router, found `{}`",
+            value.kind()
+        ),
+    ))
+}
+
+fn handle(
+    interpreter: &mut Interpreter,
+    handler: &Value,
+    mut request: Request,
+) -> RuntimeResult<()> {
+    let method = request.method().as_str().to_owned();
+    let url = request.url().to_owned();
+
+    let response = match request_object(&mut request) {
+        // async handlers answer once their promise resolves
+        Ok(object) => match interpreter
+            .call(handler, vec![object])
+            .and_then(|value| interpreter.settle(value))
+        {
+            // `env.exit` stops the server as it would stop any script
+            Err(err) if err.exit_code.is_some() => return Err(err),
+            result => result.and_then(|value| to_response(&value)),
+        },
+        Err(err) => Err(err),
+    };
+
+    let response = response.unwrap_or_else(|err| {
+        // a failing handler answers with an error, the server keeps running
+        let _ = interpreter.flush_stdout();
+        eprintln!("\x1b[31m{method} {url}\x1b[0m: {}", err.message);
+        text_response(500, "Internal Server Error")
+    });
+
+    // the client may be gone already, which is no reason to stop serving
+    let _ = request.respond(response);
+
+    Ok(())
+}
+
+/// the request as an object, with empty params filled by routers
+fn request_object(request: &mut Request) -> RuntimeResult<Value> {
+    let url = request.url().to_owned();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+    let mut headers = HashMap::new();
+    for header in request.headers() {
+        let name = header.field.as_str().as_str().to_lowercase();
+        let value = header.value.as_str();
+
+        let value = match headers.remove(&name) {
+            Some(Value::Str(previous)) => format!("{previous}, {value}"),
+            _ => value.to_owned(),
+        };
+        headers.insert(name, Value::str(&value));
+    }
+
+    let mut query_object = HashMap::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        query_object.insert(decode(name, true), Value::str(&decode(value, true)));
+    }
+
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .read_to_end(&mut body)
+        .map_err(|err| RuntimeError::io(&err, &url))?;
+
+    let mut object = HashMap::new();
+    object.insert("method".to_owned(), Value::str(request.method().as_str()));
+    object.insert("path".to_owned(), Value::str(&decode(path, false)));
+    object.insert("params".to_owned(), Value::object(HashMap::new()));
+    object.insert("query".to_owned(), Value::object(query_object));
+    object.insert("headers".to_owned(), Value::object(headers));
+    object.insert(
+        "body".to_owned(),
+        Value::str(&String::from_utf8_lossy(&body)),
+    );
+
+    let json = Method {
+        receiver: Value::Bytes(body.into()),
+        function: &JSON,
+    };
+    object.insert("json".to_owned(), Value::Method(Rc::new(json)));
+
+    Ok(Value::object(object))
+}
+
+/// calls the handler of the first route matching the request,
+/// answering 404 when no pattern matches and 405 when no method does
+fn dispatch(interpreter: &mut Interpreter, args: Vec<Value>) -> RuntimeResult<Value> {
+    let (Value::Array(routes), Value::Object(request)) = (&args[0], &args[1]) else {
+        unreachable!()
+    };
+
+    let field = |name: &str| match request.borrow().get(name) {
+        Some(Value::Str(s)) => Rc::clone(s),
+        _ => Rc::from(""),
+    };
+    let method = field("method").to_uppercase();
+    let path = field("path");
+
+    let mut allowed = Vec::new();
+
+    let routes = routes.borrow().clone();
+    for route in routes {
+        let Value::Array(route) = route else {
+            unreachable!()
+        };
+        let route = route.borrow().clone();
+        let [Value::Str(route_method), Value::Str(pattern), handler] = &route[..] else {
+            unreachable!()
+        };
+
+        let Some(params) = match_path(pattern, &path) else {
+            continue;
+        };
+
+        if **route_method != *method && &**route_method != "*" {
+            allowed.push(route_method.to_string());
+            continue;
+        }
+
+        let params = params
+            .into_iter()
+            .map(|(name, value)| (name, Value::str(&value)))
+            .collect();
+        request
+            .borrow_mut()
+            .insert("params".to_owned(), Value::object(params));
+
+        return interpreter.call(handler, vec![args[1].clone()]);
+    }
+
+    if allowed.is_empty() {
+        return Ok(response_object(
+            404,
+            HashMap::new(),
+            Value::str("Not Found"),
+        ));
+    }
+
+    let mut headers = HashMap::new();
+    headers.insert("allow".to_owned(), Value::str(&allowed.join(", ")));
+    Ok(response_object(
+        405,
+        headers,
+        Value::str("Method Not Allowed"),
+    ))
+}
+
+/// the params captured by a pattern such as `/users/:id/*` if the path matches it,
+/// a trailing `*` capturing the rest of the path
+fn match_path(pattern: &str, path: &str) -> Option<Vec<(String, String)>> {
+    let mut pattern = pattern.split('/').filter(|segment| !segment.is_empty());
+    let mut path = path.split('/').filter(|segment| !segment.is_empty());
+
+    let mut params = Vec::new();
+
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return Some(params),
+            (Some("*"), segment) => {
+                let rest = segment.into_iter().chain(path).collect::<Vec<_>>();
+                params.push(("*".to_owned(), rest.join("/")));
+                return Some(params);
+            }
+            (Some(expected), Some(segment)) => match expected.strip_prefix(':') {
+                Some(name) => params.push((name.to_owned(), segment.to_owned())),
+                None if expected == segment => {}
+                None => return None,
+            },
+            _ => return None,
+        }
+    }
+}
+
+/// what a handler returned as a response: nil is 204, strings and bytes are 200,
+/// objects with a numeric `status` are responses, anything else is sent as JSON
+fn to_response(value: &Value) -> RuntimeResult<Response<Cursor<Vec<u8>>>> {
+    let response = match value {
+        Value::Nil => text_response(204, ""),
+        Value::Object(object)
+            if matches!(object.borrow().get("status"), Some(Value::Number(_))) =>
+        {
+            let object = object.borrow();
+
+            let status = match &object["status"] {
+                Value::Number(n) => status_code("serve", *n)?,
+                _ => unreachable!(),
+            };
+            let mut response =
+                body_response(object.get("body").unwrap_or(&Value::Nil))?.with_status_code(status);
+
+            match object.get("headers") {
+                Some(Value::Object(headers)) => {
+                    let headers = headers.borrow();
+                    let mut names = headers.keys().collect::<Vec<_>>();
+                    names.sort();
+
+                    for name in names {
+                        let value = match &headers[name] {
+                            Value::Str(s) => s.to_string(),
+                            value @ (Value::Number(_) | Value::Bool(_)) => value.to_string(),
+                            value => {
+                                return Err(RuntimeError::invalid_argument(
+                                    "serve",
+                                    format!("the header `{name}` cannot be a `{}`", value.kind()),
+                                ))
+                            }
+                        };
+                        response.add_header(header(name, &value)?);
+                    }
+                }
+                None | Some(Value::Nil) => {}
+                Some(value) => {
+                    return Err(RuntimeError::invalid_argument(
+                        "serve",
+                        format!(
+                            "response headers must be an object, found `{}`",
+                            value.kind()
+                        ),
+                    ))
+                }
+            }
+
+            response
+        }
+        value => body_response(value)?,
+    };
+
+    Ok(response)
+}
+
+fn body_response(body: &Value) -> RuntimeResult<Response<Cursor<Vec<u8>>>> {
+    let (bytes, content_type) = match body {
+        Value::Nil => (Vec::new(), None),
+        Value::Str(s) => (s.as_bytes().to_vec(), Some("text/plain; charset=utf-8")),
+        Value::Bytes(bytes) => (bytes.to_vec(), Some("application/octet-stream")),
+        value => (
+            json::stringify(value, None)?.into_bytes(),
+            Some("application/json"),
+        ),
+    };
+
+    let mut response = Response::from_data(bytes);
+    if let Some(content_type) = content_type {
+        response.add_header(header("content-type", content_type)?);
+    }
+
+    Ok(response)
+}
+
+fn text_response(status: u16, text: &str) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(text).with_status_code(status)
+}
+
+fn response_object(status: u16, headers: HashMap<String, Value>, body: Value) -> Value {
+    let mut object = HashMap::new();
+    object.insert("status".to_owned(), Value::Number(status as f64));
+    object.insert("headers".to_owned(), Value::object(headers));
+    object.insert("body".to_owned(), body);
+
+    Value::object(object)
+}
+
+fn header(name: &str, value: &str) -> RuntimeResult<Header> {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).map_err(|_| {
+        RuntimeError::invalid_argument("serve", format!("invalid header `{name}: {value}`"))
+    })
+}
+
+fn status_code(function: &str, n: f64) -> RuntimeResult<u16> {
+    if n.fract() != 0.0 || !(100.0..=999.0).contains(&n) {
+        return Err(RuntimeError::invalid_argument(
+            function,
+            format!("`{n}` is not an HTTP status code"),
+        ));
+    }
+
+    Ok(n as u16)
+}
+
+/// percent-decodes a path or a query component, `+` being a space in queries
+fn decode(text: &str, is_query: bool) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' if is_query => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -1 +1,139 @@
-// working with input/output: BufReader, stdin, stout Cursor, read and write files
+// reading stdin and writing to stdout/stderr, for scripts used in shell pipelines
+
+use std::io::{self, BufRead, Read, Write};
+
+use super::{NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    value::{Value, ValueKind},
+    Interpreter,
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "io",
+    functions: FUNCTIONS,
+    constants: &[],
+};
+
+const VALUES: &[Param] = &[Param::variadic("values", ValueKind::Any)];
+// the values to print as an array, and an object of `sep` and/or `end` strings
+const VALUES_OPTIONS: &[Param] = &[
+    Param::new("values", ValueKind::Array),
+    Param::new("options", ValueKind::Object),
+];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new("eprint", VALUES, |interpreter, args| {
+        write_stderr(interpreter, &format_values(&args, " ", "\n"))
+    }),
+    NativeFunction::new("eprintWith", VALUES_OPTIONS, |interpreter, args| {
+        let text = format_with(&args, "eprintWith")?;
+        write_stderr(interpreter, &text)
+    }),
+    NativeFunction::new("flush", &[], |interpreter, _| {
+        interpreter.flush_stdout()?;
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("lines", &[], |interpreter, _| {
+        interpreter.flush_stdout()?;
+
+        let lines = io::stdin().lines().map(|line| match line {
+            Ok(line) => Ok(Value::str(&line)),
+            Err(e) => Err(RuntimeError::io(&e, "<stdin>")),
+        });
+
+        Ok(Value::iterator(lines))
+    }),
+    NativeFunction::new("print", VALUES, print),
+    NativeFunction::new("printWith", VALUES_OPTIONS, |interpreter, args| {
+        let text = format_with(&args, "printWith")?;
+        interpreter.write_stdout(&text)?;
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("readAll", &[], |interpreter, _| {
+        interpreter.flush_stdout()?;
+
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| RuntimeError::io(&e, "<stdin>"))?;
+
+        Ok(Value::str(&text))
+    }),
+    NativeFunction::new("readLine", &[], |interpreter, _| {
+        interpreter.flush_stdout()?;
+
+        let mut line = String::new();
+        let read = io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| RuntimeError::io(&e, "<stdin>"))?;
+
+        if read == 0 {
+            return Ok(Value::Nil);
+        }
+
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        Ok(Value::str(line))
+    }),
+    NativeFunction::new("write", VALUES, |interpreter, args| {
+        interpreter.write_stdout(&format_values(&args, "", ""))?;
+        Ok(Value::Nil)
+    }),
+];
+
+/// prints the values separated by spaces and followed by a newline, objects included,
+/// `io.printWith` taking the separator and the end as options
+pub fn print(interpreter: &mut Interpreter, args: Vec<Value>) -> RuntimeResult<Value> {
+    interpreter.write_stdout(&format_values(&args, " ", "\n"))?;
+    Ok(Value::Nil)
+}
+
+fn write_stderr(interpreter: &mut Interpreter, text: &str) -> RuntimeResult<Value> {
+    // keeps the order of interleaved stdout and stderr output
+    interpreter.flush_stdout()?;
+    io::stderr()
+        .write_all(text.as_bytes())
+        .map_err(|e| RuntimeError::io(&e, "<stderr>"))?;
+
+    Ok(Value::Nil)
+}
+
+fn format_values(values: &[Value], sep: &str, end: &str) -> String {
+    let values = values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    values.join(sep) + end
+}
+
+/// the values of an array joined as the `sep` and `end` options say, by default as `print`
+/// does
+fn format_with(args: &[Value], function: &str) -> RuntimeResult<String> {
+    let (Value::Array(values), Value::Object(options)) = (&args[0], &args[1]) else {
+        unreachable!()
+    };
+
+    let (mut sep, mut end) = (" ".to_owned(), "\n".to_owned());
+    for (key, value) in options.borrow().iter() {
+        let Value::Str(value) = value else {
+            return Err(RuntimeError::invalid_argument(
+                function,
+                format!("option `{key}` must be a string, found {}", value.kind()),
+            ));
+        };
+
+        match key.as_str() {
+            "sep" => sep = value.to_string(),
+            "end" => end = value.to_string(),
+            _ => {
+                return Err(RuntimeError::invalid_argument(
+                    function,
+                    format!("unknown option `{key}`, expected `sep` or `end`"),
+                ))
+            }
+        }
+    }
+
+    Ok(format_values(&values.borrow(), &sep, &end))
+}
@@ -0,0 +1,109 @@
+// methods of iterators, adapters returning new iterators that only advance the source
+// when a value is needed
+
+use std::{cell::RefCell, rc::Rc};
+
+use super::{array::call, count, number, NativeFunction, Param};
+use crate::interpreter::{
+    errors::RuntimeResult,
+    iteration::{self, ValueIter},
+    value::{NativeIterator, Value, ValueKind},
+    Interpreter,
+};
+
+const CALLBACK: &[Param] = &[Param::new("function", ValueKind::Function)];
+const COUNT: &[Param] = &[Param::new("count", ValueKind::Number)];
+
+pub static ITERATOR_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("filter", CALLBACK, |_, args| {
+        let (source, function) = (iterator(&args), args[1].clone());
+        let mut index = 0;
+
+        Ok(Value::iterator_from_fn(move |interpreter| {
+            while let Some(value) = iteration::next(&source, interpreter)? {
+                index += 1;
+                if call(interpreter, &function, value.clone(), index - 1)?.is_truthy() {
+                    return Ok(Some(value));
+                }
+            }
+
+            Ok(None)
+        }))
+    }),
+    NativeFunction::new("map", CALLBACK, |_, args| {
+        let (source, function) = (iterator(&args), args[1].clone());
+        let mut index = 0;
+
+        Ok(Value::iterator_from_fn(move |interpreter| {
+            let Some(value) = iteration::next(&source, interpreter)? else {
+                return Ok(None);
+            };
+            index += 1;
+
+            call(interpreter, &function, value, index - 1).map(Some)
+        }))
+    }),
+    // the next value, nil once the iterator is exhausted
+    NativeFunction::new("next", &[], |interpreter, args| {
+        let next = iteration::next(&iterator(&args), interpreter)?;
+        Ok(next.unwrap_or(Value::Nil))
+    }),
+    NativeFunction::new("skip", COUNT, |_, args| {
+        let source = iterator(&args);
+        let mut skipped = count("skip", "count", number(&args, 1))?;
+
+        Ok(Value::iterator_from_fn(move |interpreter| {
+            while skipped > 0 {
+                skipped -= 1;
+                if iteration::next(&source, interpreter)?.is_none() {
+                    return Ok(None);
+                }
+            }
+
+            iteration::next(&source, interpreter)
+        }))
+    }),
+    NativeFunction::new("take", COUNT, |_, args| {
+        let source = iterator(&args);
+        let mut remaining = count("take", "count", number(&args, 1))?;
+
+        Ok(Value::iterator_from_fn(move |interpreter| {
+            if remaining == 0 {
+                return Ok(None);
+            }
+            remaining -= 1;
+
+            iteration::next(&source, interpreter)
+        }))
+    }),
+    NativeFunction::new("toArray", &[], |interpreter, args| {
+        let source = iterator(&args);
+        let mut values = Vec::new();
+
+        while let Some(value) = iteration::next(&source, interpreter)? {
+            values.push(value);
+        }
+
+        Ok(Value::array(values))
+    }),
+];
+
+/// any value a `for ... in` loop accepts as a lazy iterator, iterators being returned as they are
+pub fn iter(_: &mut Interpreter, args: Vec<Value>) -> RuntimeResult<Value> {
+    if let Value::Iterator(_) = &args[0] {
+        return Ok(args[0].clone());
+    }
+
+    let mut iter = ValueIter::new(&args[0])?;
+
+    Ok(Value::iterator_from_fn(move |interpreter| {
+        iter.next_value(interpreter)
+    }))
+}
+
+fn iterator(args: &[Value]) -> Rc<RefCell<NativeIterator>> {
+    match &args[0] {
+        Value::Iterator(iterator) => Rc::clone(iterator),
+        _ => unreachable!(),
+    }
+}
@@ -1 +1,459 @@
 // working with json serialization and deserialization
+
+use std::rc::Rc;
+
+use hashbrown::HashMap;
+
+use super::{count, number, optional, string, NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    value::{format_number, Value, ValueKind},
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "json",
+    functions: FUNCTIONS,
+    constants: &[],
+};
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new("parse", &[Param::new("text", ValueKind::Str)], |_, args| {
+        parse(&string(&args, 0))
+    }),
+    NativeFunction::new(
+        "pretty",
+        &[Param::new("value", ValueKind::Any)],
+        |_, args| Ok(Value::str(&stringify(&args[0], Some(2))?)),
+    ),
+    NativeFunction::new(
+        "stringify",
+        &[
+            Param::new("value", ValueKind::Any),
+            Param::optional("indent", ValueKind::Number),
+        ],
+        |_, args| {
+            let indent = match optional(&args, 1) {
+                Some(_) => Some(indent(number(&args, 1))?),
+                None => None,
+            };
+            Ok(Value::str(&stringify(&args[0], indent)?))
+        },
+    ),
+];
+
+// the widest indent, the limit JavaScript has too
+const MAX_INDENT: usize = 10;
+
+fn indent(n: f64) -> RuntimeResult<usize> {
+    let indent = count("stringify", "indent", n)?;
+
+    if indent > MAX_INDENT {
+        return Err(RuntimeError::invalid_argument(
+            "stringify",
+            format!("`indent` must be at most {MAX_INDENT}, found `{indent}`"),
+        ));
+    }
+
+    Ok(indent)
+}
+
+// deep enough for any sensible document while keeping the parser away from a stack overflow
+const MAX_DEPTH: usize = 512;
+
+pub fn parse(text: &str) -> RuntimeResult<Value> {
+    let mut parser = JsonParser { text, position: 0 };
+
+    parser.skip_whitespace();
+    let value = parser.parse_value(0)?;
+    parser.skip_whitespace();
+
+    if parser.position < text.len() {
+        return Err(parser.error("unexpected trailing characters"));
+    }
+
+    Ok(value)
+}
+
+/// converts a value to JSON, pretty-printed when an indent is given,
+/// object keys being sorted so that the output is stable
+pub fn stringify(value: &Value, indent: Option<usize>) -> RuntimeResult<String> {
+    let mut writer = JsonWriter {
+        output: String::new(),
+        indent: indent.filter(|indent| *indent > 0),
+        ancestors: Vec::new(),
+    };
+
+    writer.write_value(value, 0)?;
+
+    Ok(writer.output)
+}
+
+struct JsonParser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> RuntimeResult<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("expected `{expected}`, found `{c}`"))),
+            None => Err(self.error(&format!("expected `{expected}`, found end of input"))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        let trimmed = rest.trim_start_matches([' ', '\t', '\n', '\r']);
+        self.position += rest.len() - trimmed.len();
+    }
+
+    fn error(&self, message: &str) -> RuntimeError {
+        let consumed = &self.text[..self.position];
+        let line = consumed.matches('\n').count() + 1;
+        let line_start = consumed.rfind('\n').map_or(0, |i| i + 1);
+        let column = consumed[line_start..].chars().count() + 1;
+
+        RuntimeError::json_syntax(message, line, column)
+    }
+
+    fn parse_value(&mut self, depth: usize) -> RuntimeResult<Value> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("too deeply nested"));
+        }
+
+        match self.peek() {
+            Some('{') => self.parse_object(depth),
+            Some('[') => self.parse_array(depth),
+            Some('"') => Ok(Value::str(&self.parse_string()?)),
+            Some('-' | '0'..='9') => self.parse_number(),
+            Some(_) => self.parse_keyword(),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_keyword(&mut self) -> RuntimeResult<Value> {
+        for (keyword, value) in [
+            ("true", Value::Bool(true)),
+            ("false", Value::Bool(false)),
+            ("null", Value::Nil),
+        ] {
+            if self.rest().starts_with(keyword) {
+                self.position += keyword.len();
+                return Ok(value);
+            }
+        }
+
+        let found = self.peek().unwrap_or_default();
+        Err(self.error(&format!("unexpected character `{found}`")))
+    }
+
+    fn parse_object(&mut self, depth: usize) -> RuntimeResult<Value> {
+        self.expect('{')?;
+        self.skip_whitespace();
+
+        let mut object = HashMap::new();
+
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Value::object(object));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.parse_string()?;
+
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+
+            let value = self.parse_value(depth + 1)?;
+            object.insert(key, value);
+
+            self.skip_whitespace();
+            let before = self.position;
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => {
+                    self.position = before;
+                    return Err(self.error("expected `,` or `}` after an object member"));
+                }
+            }
+        }
+
+        Ok(Value::object(object))
+    }
+
+    fn parse_array(&mut self, depth: usize) -> RuntimeResult<Value> {
+        self.expect('[')?;
+        self.skip_whitespace();
+
+        let mut elements = Vec::new();
+
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Value::array(elements));
+        }
+
+        loop {
+            self.skip_whitespace();
+            elements.push(self.parse_value(depth + 1)?);
+
+            self.skip_whitespace();
+            let before = self.position;
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => break,
+                _ => {
+                    self.position = before;
+                    return Err(self.error("expected `,` or `]` after an array element"));
+                }
+            }
+        }
+
+        Ok(Value::array(elements))
+    }
+
+    fn parse_string(&mut self) -> RuntimeResult<String> {
+        self.expect('"')?;
+
+        let mut result = String::new();
+
+        loop {
+            let start = self.position;
+            let c = self
+                .next()
+                .ok_or_else(|| self.error("unterminated string"))?;
+
+            match c {
+                '"' => break,
+                '\\' => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape()?,
+                        _ => {
+                            self.position = start;
+                            return Err(self.error("invalid escape sequence"));
+                        }
+                    };
+                    result.push(escaped);
+                }
+                c if (c as u32) < 0x20 => {
+                    self.position = start;
+                    return Err(self.error("control character in string"));
+                }
+                c => result.push(c),
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn parse_unicode_escape(&mut self) -> RuntimeResult<char> {
+        let high = self.parse_hex4()?;
+
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+
+        // a high surrogate must be followed by a low one
+        if !self.rest().starts_with("\\u") {
+            return Err(self.error("unpaired surrogate in unicode escape"));
+        }
+        self.position += 2;
+
+        let low = self.parse_hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error("unpaired surrogate in unicode escape"));
+        }
+
+        let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn parse_hex4(&mut self) -> RuntimeResult<u32> {
+        let digits = self.rest().get(..4).unwrap_or_default();
+
+        if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(self.error("expected 4 hexadecimal digits"));
+        }
+        self.position += 4;
+
+        Ok(u32::from_str_radix(digits, 16).unwrap())
+    }
+
+    fn parse_number(&mut self) -> RuntimeResult<Value> {
+        let start = self.position;
+        let bytes = self.text.as_bytes();
+        let digits = |position: &mut usize| {
+            let from = *position;
+            while bytes.get(*position).is_some_and(u8::is_ascii_digit) {
+                *position += 1;
+            }
+            *position - from
+        };
+
+        let mut position = start;
+        if bytes[position] == b'-' {
+            position += 1;
+        }
+
+        let integer_start = position;
+        let integer_digits = digits(&mut position);
+        let leading_zero = integer_digits > 1 && bytes[integer_start] == b'0';
+
+        let mut valid = integer_digits > 0 && !leading_zero;
+
+        if bytes.get(position) == Some(&b'.') {
+            position += 1;
+            valid &= digits(&mut position) > 0;
+        }
+        if matches!(bytes.get(position), Some(b'e' | b'E')) {
+            position += 1;
+            if matches!(bytes.get(position), Some(b'+' | b'-')) {
+                position += 1;
+            }
+            valid &= digits(&mut position) > 0;
+        }
+
+        if !valid {
+            return Err(self.error("invalid number"));
+        }
+
+        self.position = position;
+        let number = self.text[start..position].parse::<f64>().unwrap();
+
+        Ok(Value::Number(number))
+    }
+}
+
+struct JsonWriter {
+    output: String,
+    indent: Option<usize>,
+    // arrays and objects currently being written, to detect cycles
+    ancestors: Vec<*const ()>,
+}
+
+impl JsonWriter {
+    fn write_value(&mut self, value: &Value, depth: usize) -> RuntimeResult<()> {
+        match value {
+            Value::Nil => self.output.push_str("null"),
+            Value::Bool(b) => self.output.push_str(&b.to_string()),
+            Value::Number(n) if n.is_finite() => self.output.push_str(&format_number(*n)),
+            Value::Str(s) => write_string(&mut self.output, s),
+            Value::Array(array) => {
+                self.enter(Rc::as_ptr(array) as *const ())?;
+
+                let elements = array.borrow();
+                self.output.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    if i != 0 {
+                        self.output.push(',');
+                    }
+                    self.new_line(depth + 1);
+                    self.write_value(element, depth + 1)?;
+                }
+                if !elements.is_empty() {
+                    self.new_line(depth);
+                }
+                self.output.push(']');
+
+                self.ancestors.pop();
+            }
+            Value::Object(object) => {
+                self.enter(Rc::as_ptr(object) as *const ())?;
+
+                let object = object.borrow();
+                let mut keys = object.keys().collect::<Vec<_>>();
+                keys.sort();
+
+                self.output.push('{');
+                for (i, key) in keys.iter().enumerate() {
+                    if i != 0 {
+                        self.output.push(',');
+                    }
+                    self.new_line(depth + 1);
+                    write_string(&mut self.output, key);
+                    self.output.push(':');
+                    if self.indent.is_some() {
+                        self.output.push(' ');
+                    }
+                    self.write_value(&object[*key], depth + 1)?;
+                }
+                if !keys.is_empty() {
+                    self.new_line(depth);
+                }
+                self.output.push('}');
+
+                self.ancestors.pop();
+            }
+            value => return Err(RuntimeError::json_unrepresentable(value.kind())),
+        }
+
+        Ok(())
+    }
+
+    fn enter(&mut self, pointer: *const ()) -> RuntimeResult<()> {
+        if self.ancestors.contains(&pointer) {
+            return Err(RuntimeError::json_cycle());
+        }
+
+        self.ancestors.push(pointer);
+        Ok(())
+    }
+
+    fn new_line(&mut self, depth: usize) {
+        if let Some(indent) = self.indent {
+            self.output.push('\n');
+            self.output.push_str(&" ".repeat(indent * depth));
+        }
+    }
+}
+
+pub fn write_string(output: &mut String, s: &str) {
+    output.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{8}' => output.push_str("\\b"),
+            '\u{c}' => output.push_str("\\f"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+
+    output.push('"');
+}
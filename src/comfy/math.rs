@@ -1,136 +1,196 @@
 // working with maths: cos, sin, tan, floor, round, ceil, PI constant etc
 
-use lazy_static::lazy_static;
 use rand::Rng;
 
-use crate::parser::ast::literal_value::LiteralValue;
-use crate::parser::ast::Expression;
-use std::f32::consts;
-lazy_static! {
-    static ref PI: Expression = {
-        Expression::Literal {
-            value: LiteralValue::Number(consts::PI),
-            raw: consts::PI.to_string(),
-        }
-    };
-    static ref FRAC_1_PI: Expression = {
-        Expression::Literal {
-            value: LiteralValue::Number(consts::FRAC_1_PI),
-            raw: consts::FRAC_1_PI.to_string(),
-        }
-    };
-    static ref E: Expression = {
-        Expression::Literal {
-            value: LiteralValue::Number(consts::E),
-            raw: consts::E.to_string(),
-        }
-    };
-    static ref LN_10: Expression = {
-        Expression::Literal {
-            value: LiteralValue::Number(consts::LN_10),
-            raw: consts::LN_10.to_string(),
-        }
-    };
-    static ref LN_2: Expression = {
-        Expression::Literal {
-            value: LiteralValue::Number(consts::LN_2),
-            raw: consts::LN_2.to_string(),
-        }
-    };
-    static ref LOG10_2: Expression = {
-        Expression::Literal {
-            value: LiteralValue::Number(consts::LOG10_2),
-            raw: consts::LOG10_2.to_string(),
-        }
-    };
-    static ref SQRT_2: Expression = {
-        Expression::Literal {
-            value: LiteralValue::Number(consts::SQRT_2),
-            raw: consts::SQRT_2.to_string(),
-        }
-    };
-}
-
-pub fn cos(expr: f32) -> f32 {
+use super::{number, Constant, NativeFunction, NativeModule, Param};
+use crate::interpreter::value::{Value, ValueKind};
+use std::f64::consts;
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "math",
+    functions: FUNCTIONS,
+    constants: CONSTANTS,
+};
+
+static CONSTANTS: &[(&str, Constant)] = &[
+    ("PI", Constant::Number(consts::PI)),
+    ("FRAC_1_PI", Constant::Number(consts::FRAC_1_PI)),
+    ("E", Constant::Number(consts::E)),
+    ("LN_10", Constant::Number(consts::LN_10)),
+    ("LN_2", Constant::Number(consts::LN_2)),
+    ("LOG10_2", Constant::Number(consts::LOG10_2)),
+    ("SQRT_2", Constant::Number(consts::SQRT_2)),
+];
+
+const X: &[Param] = &[Param::new("x", ValueKind::Number)];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new("abs", X, |_, args| Ok(Value::Number(abs(number(&args, 0))))),
+    NativeFunction::new("acos", X, |_, args| {
+        Ok(Value::Number(acos(number(&args, 0))))
+    }),
+    NativeFunction::new("asin", X, |_, args| {
+        Ok(Value::Number(asin(number(&args, 0))))
+    }),
+    NativeFunction::new("atan", X, |_, args| {
+        Ok(Value::Number(atan(number(&args, 0))))
+    }),
+    NativeFunction::new("ceil", X, |_, args| {
+        Ok(Value::Number(ceil(number(&args, 0))))
+    }),
+    NativeFunction::new(
+        "clamp",
+        &[
+            Param::new("x", ValueKind::Number),
+            Param::new("min", ValueKind::Number),
+            Param::new("max", ValueKind::Number),
+        ],
+        |_, args| {
+            let (x, min, max) = (number(&args, 0), number(&args, 1), number(&args, 2));
+            Ok(Value::Number(clamp(x, min, max)))
+        },
+    ),
+    NativeFunction::new("cos", X, |_, args| Ok(Value::Number(cos(number(&args, 0))))),
+    NativeFunction::new("exp", X, |_, args| Ok(Value::Number(exp(number(&args, 0))))),
+    NativeFunction::new("floor", X, |_, args| {
+        Ok(Value::Number(floor(number(&args, 0))))
+    }),
+    NativeFunction::new("ln", X, |_, args| Ok(Value::Number(ln(number(&args, 0))))),
+    NativeFunction::new(
+        "log",
+        &[
+            Param::new("x", ValueKind::Number),
+            Param::new("base", ValueKind::Number),
+        ],
+        |_, args| Ok(Value::Number(log(number(&args, 0), number(&args, 1)))),
+    ),
+    NativeFunction::new(
+        "max",
+        &[
+            Param::new("a", ValueKind::Number),
+            Param::new("b", ValueKind::Number),
+        ],
+        |_, args| Ok(Value::Number(max(number(&args, 0), number(&args, 1)))),
+    ),
+    NativeFunction::new(
+        "min",
+        &[
+            Param::new("a", ValueKind::Number),
+            Param::new("b", ValueKind::Number),
+        ],
+        |_, args| Ok(Value::Number(min(number(&args, 0), number(&args, 1)))),
+    ),
+    NativeFunction::new(
+        "power",
+        &[
+            Param::new("x", ValueKind::Number),
+            Param::new("power", ValueKind::Number),
+        ],
+        |_, args| Ok(Value::Number(power(number(&args, 0), number(&args, 1)))),
+    ),
+    NativeFunction::new("random", &[], |_, _| Ok(Value::Number(random()))),
+    NativeFunction::new("round", X, |_, args| {
+        Ok(Value::Number(round(number(&args, 0))))
+    }),
+    NativeFunction::new("signum", X, |_, args| {
+        Ok(Value::Number(signum(number(&args, 0))))
+    }),
+    NativeFunction::new("sin", X, |_, args| Ok(Value::Number(sin(number(&args, 0))))),
+    NativeFunction::new("sqrt", X, |_, args| {
+        Ok(Value::Number(sqrt(number(&args, 0))))
+    }),
+    NativeFunction::new("tan", X, |_, args| Ok(Value::Number(tan(number(&args, 0))))),
+    NativeFunction::new("toDegrees", X, |_, args| {
+        Ok(Value::Number(to_degrees(number(&args, 0))))
+    }),
+    NativeFunction::new("toRadians", X, |_, args| {
+        Ok(Value::Number(to_radians(number(&args, 0))))
+    }),
+    NativeFunction::new("trunc", X, |_, args| {
+        Ok(Value::Number(trunc(number(&args, 0))))
+    }),
+];
+
+pub fn cos(expr: f64) -> f64 {
     expr.cos()
 }
-pub fn sin(expr: f32) -> f32 {
+pub fn sin(expr: f64) -> f64 {
     expr.sin()
 }
-pub fn tan(expr: f32) -> f32 {
+pub fn tan(expr: f64) -> f64 {
     expr.tan()
 }
-pub fn acos(expr: f32) -> f32 {
+pub fn acos(expr: f64) -> f64 {
     expr.acos()
 }
-pub fn asin(expr: f32) -> f32 {
+pub fn asin(expr: f64) -> f64 {
     expr.asin()
 }
-pub fn atan(expr: f32) -> f32 {
+pub fn atan(expr: f64) -> f64 {
     expr.atan()
 }
 
-pub fn ceil(expr: f32) -> f32 {
+pub fn ceil(expr: f64) -> f64 {
     expr.ceil()
 }
-pub fn floor(expr: f32) -> f32 {
+pub fn floor(expr: f64) -> f64 {
     expr.floor()
 }
 
-pub fn log(expr: f32, base: f32) -> f32 {
+pub fn log(expr: f64, base: f64) -> f64 {
     expr.log(base)
 }
-pub fn ln(expr: f32) -> f32 {
+pub fn ln(expr: f64) -> f64 {
     expr.ln()
 }
 
-pub fn sqrt(expr: f32) -> f32 {
+pub fn sqrt(expr: f64) -> f64 {
     expr.sqrt()
 }
-pub fn power(expr: f32, power: f32) -> f32 {
+pub fn power(expr: f64, power: f64) -> f64 {
     expr.powf(power)
 }
 
-pub fn random() -> f32 {
+pub fn random() -> f64 {
     rand::thread_rng().gen()
 }
 
-pub fn abs(expr: f32) -> f32 {
+pub fn abs(expr: f64) -> f64 {
     expr.abs()
 }
 
-pub fn exp(expr: f32) -> f32 {
+pub fn exp(expr: f64) -> f64 {
     expr.exp()
 }
 
-pub fn max(a: f32, b: f32) -> f32 {
+pub fn max(a: f64, b: f64) -> f64 {
     a.max(b)
 }
 
-pub fn min(a: f32, b: f32) -> f32 {
+pub fn min(a: f64, b: f64) -> f64 {
     a.min(b)
 }
 
-pub fn round(expr: f32) -> f32 {
+pub fn round(expr: f64) -> f64 {
     expr.round()
 }
 
-pub fn trunc(expr: f32) -> f32 {
+pub fn trunc(expr: f64) -> f64 {
     expr.trunc()
 }
 
-pub fn clamp(expr: f32, min: f32, max: f32) -> f32 {
+pub fn clamp(expr: f64, min: f64, max: f64) -> f64 {
     expr.max(min).min(max)
 }
 
-pub fn signum(expr: f32) -> f32 {
+pub fn signum(expr: f64) -> f64 {
     expr.signum()
 }
 
-pub fn to_radians(degrees: f32) -> f32 {
+pub fn to_radians(degrees: f64) -> f64 {
     degrees.to_radians()
 }
 
-pub fn to_degrees(radians: f32) -> f32 {
+pub fn to_degrees(radians: f64) -> f64 {
     radians.to_degrees()
 }
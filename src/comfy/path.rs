@@ -0,0 +1,175 @@
+// joining, normalizing and splitting paths
+
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR_STR};
+
+use super::{string, Constant, NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    errors::RuntimeError,
+    value::{Value, ValueKind},
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "path",
+    functions: FUNCTIONS,
+    constants: &[("SEPARATOR", Constant::Str(MAIN_SEPARATOR_STR))],
+};
+
+const PATH: &[Param] = &[Param::new("path", ValueKind::Str)];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new("basename", PATH, |_, args| {
+        let path = string(&args, 0);
+        Ok(optional_str(Path::new(&*path).file_name()))
+    }),
+    NativeFunction::new("canonicalize", PATH, |_, args| {
+        let path = string(&args, 0);
+
+        let canonical = Path::new(&*path)
+            .canonicalize()
+            .map_err(|e| RuntimeError::io(&e, &path))?;
+
+        Ok(path_value(&canonical))
+    }),
+    NativeFunction::new("dirname", PATH, |_, args| {
+        let path = string(&args, 0);
+        let parent = Path::new(&*path).parent().unwrap_or_else(|| Path::new(""));
+
+        Ok(path_value(parent))
+    }),
+    NativeFunction::new("extension", PATH, |_, args| {
+        let path = string(&args, 0);
+        Ok(optional_str(Path::new(&*path).extension()))
+    }),
+    NativeFunction::new("isAbsolute", PATH, |_, args| {
+        Ok(Value::Bool(Path::new(&*string(&args, 0)).is_absolute()))
+    }),
+    NativeFunction::new(
+        "join",
+        &[Param::variadic("parts", ValueKind::Str)],
+        |_, args| {
+            let mut path = PathBuf::new();
+            for i in 0..args.len() {
+                // an absolute part replaces everything before it
+                path.push(&*string(&args, i));
+            }
+
+            Ok(path_value(&path))
+        },
+    ),
+    NativeFunction::new("normalize", PATH, |_, args| {
+        Ok(path_value(&normalize(Path::new(&*string(&args, 0)))))
+    }),
+    NativeFunction::new(
+        "relativeTo",
+        &[
+            Param::new("path", ValueKind::Str),
+            Param::new("base", ValueKind::Str),
+        ],
+        |_, args| {
+            let (path, base) = (string(&args, 0), string(&args, 1));
+
+            match relative_to(Path::new(&*path), Path::new(&*base)) {
+                Some(relative) => Ok(path_value(&relative)),
+                None => Err(RuntimeError::invalid_argument(
+                    "relativeTo",
+                    format!("`{path}` and `{base}` must both be absolute or both be relative"),
+                )),
+            }
+        },
+    ),
+    NativeFunction::new("split", PATH, |_, args| {
+        let path = string(&args, 0);
+
+        let components = Path::new(&*path)
+            .components()
+            .map(|component| Value::str(&component.as_os_str().to_string_lossy()))
+            .collect();
+
+        Ok(Value::array(components))
+    }),
+    NativeFunction::new("stem", PATH, |_, args| {
+        let path = string(&args, 0);
+        Ok(optional_str(Path::new(&*path).file_stem()))
+    }),
+    NativeFunction::new(
+        "withExtension",
+        &[
+            Param::new("path", ValueKind::Str),
+            Param::new("extension", ValueKind::Str),
+        ],
+        |_, args| {
+            let path = string(&args, 0);
+            let extension = string(&args, 1);
+
+            Ok(path_value(
+                &Path::new(&*path).with_extension(extension.trim_start_matches('.')),
+            ))
+        },
+    ),
+];
+
+fn path_value(path: &Path) -> Value {
+    Value::str(&path.to_string_lossy())
+}
+
+fn optional_str(part: Option<&std::ffi::OsStr>) -> Value {
+    part.map_or(Value::Nil, |part| Value::str(&part.to_string_lossy()))
+}
+
+/// resolves `.` and `..` lexically, without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = Vec::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => match normalized.last() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // `/..` is `/`
+                Some(Component::RootDir | Component::Prefix(_)) => (),
+                _ => normalized.push(component),
+            },
+            component => normalized.push(component),
+        }
+    }
+
+    if normalized.is_empty() {
+        return PathBuf::from(".");
+    }
+
+    normalized.iter().collect()
+}
+
+fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    if path.is_absolute() != base.is_absolute() {
+        return None;
+    }
+
+    let path = normalize(path);
+    let base = normalize(base);
+
+    let path_components = path.components().collect::<Vec<_>>();
+    let base_components = base.components().collect::<Vec<_>>();
+
+    let common = path_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push("..");
+    }
+    for component in &path_components[common..] {
+        relative.push(component);
+    }
+
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+
+    Some(relative)
+}
@@ -0,0 +1,155 @@
+// combining promises and reacting to them without `await`, callbacks running from the
+// event loop once the promise settled
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use super::{NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    errors::RuntimeResult,
+    event_loop::{Promise, Settled},
+    iteration::collect_values,
+    value::{Value, ValueKind},
+    Interpreter,
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "promise",
+    functions: FUNCTIONS,
+    constants: &[],
+};
+
+const PROMISES: &[Param] = &[Param::new("promises", ValueKind::Any)];
+const CALLBACK: &[Param] = &[Param::new("function", ValueKind::Function)];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    // the results in order once every promise resolved, or the first rejection
+    NativeFunction::new("all", PROMISES, |interpreter, args| {
+        let values = collect_values(interpreter, &args[0])?;
+        let all = Promise::pending();
+
+        let results = Rc::new(RefCell::new(vec![Value::Nil; values.len()]));
+        let remaining = Rc::new(Cell::new(values.len()));
+
+        if values.is_empty() {
+            interpreter.resolve(&all, Ok(Value::array(vec![])));
+        }
+
+        for (index, value) in values.into_iter().enumerate() {
+            let (all, results, remaining) =
+                (Rc::clone(&all), Rc::clone(&results), Rc::clone(&remaining));
+
+            settle(interpreter, value, move |interpreter, settled| {
+                match settled {
+                    Ok(value) => {
+                        results.borrow_mut()[index] = value;
+                        remaining.set(remaining.get() - 1);
+
+                        if remaining.get() == 0 {
+                            let results = results.take();
+                            interpreter.resolve(&all, Ok(Value::array(results)));
+                        }
+                    }
+                    Err(err) => interpreter.resolve(&all, Err(err)),
+                }
+                Ok(())
+            });
+        }
+
+        Ok(Value::Promise(all))
+    }),
+    // settles like the first of the promises to settle
+    NativeFunction::new("race", PROMISES, |interpreter, args| {
+        let values = collect_values(interpreter, &args[0])?;
+        let race = Promise::pending();
+
+        for value in values {
+            let race = Rc::clone(&race);
+
+            settle(interpreter, value, move |interpreter, settled| {
+                interpreter.resolve(&race, settled);
+                Ok(())
+            });
+        }
+
+        Ok(Value::Promise(race))
+    }),
+    // a promise resolved with the value, promises being returned as they are
+    NativeFunction::new(
+        "resolve",
+        &[Param::new("value", ValueKind::Any)],
+        |interpreter, args| {
+            if let Value::Promise(_) = &args[0] {
+                return Ok(args[0].clone());
+            }
+
+            let promise = Promise::pending();
+            interpreter.resolve(&promise, Ok(args[0].clone()));
+
+            Ok(Value::Promise(promise))
+        },
+    ),
+];
+
+pub static PROMISE_METHODS: &[NativeFunction] = &[
+    // a promise resolved with what the function returns for the error, a success
+    // being passed through
+    NativeFunction::new("catch", CALLBACK, |interpreter, args| {
+        let function = args[1].clone();
+
+        Ok(chain(
+            interpreter,
+            &args,
+            move |interpreter, settled| match settled {
+                Ok(value) => Ok(value),
+                Err(err) => interpreter.call(&function, vec![err.to_value()]),
+            },
+        ))
+    }),
+    // a promise resolved with what the function returns for the value, an error
+    // being passed through
+    NativeFunction::new("then", CALLBACK, |interpreter, args| {
+        let function = args[1].clone();
+
+        Ok(chain(interpreter, &args, move |interpreter, settled| {
+            interpreter.call(&function, vec![settled?])
+        }))
+    }),
+];
+
+/// calls `then` once the value settles, right away for values that are not promises
+fn settle(
+    interpreter: &mut Interpreter,
+    value: Value,
+    then: impl FnOnce(&mut Interpreter, Settled) -> RuntimeResult<()> + 'static,
+) {
+    match value {
+        Value::Promise(promise) => interpreter.subscribe(&promise, then),
+        value => interpreter.schedule(move |interpreter| then(interpreter, Ok(value))),
+    }
+}
+
+/// a promise settled with what `react` makes of the receiver's result
+fn chain(
+    interpreter: &mut Interpreter,
+    args: &[Value],
+    react: impl FnOnce(&mut Interpreter, Settled) -> Settled + 'static,
+) -> Value {
+    let chained = Promise::pending();
+    let resolved = Rc::clone(&chained);
+
+    settle(interpreter, args[0].clone(), move |interpreter, settled| {
+        match react(interpreter, settled) {
+            // exiting ends the whole script rather than the promise
+            Err(err) if err.exit_code.is_some() => Err(err),
+            settled => {
+                interpreter.resolve(&resolved, settled);
+                Ok(())
+            }
+        }
+    });
+
+    Value::Promise(chained)
+}
@@ -0,0 +1,75 @@
+// methods of ranges, answered from their bounds and step without going through them
+
+use super::{NativeFunction, Param};
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    value::{format_number, Range, Value, ValueKind},
+};
+
+pub static RANGE_METHODS: &[NativeFunction] = &[
+    NativeFunction::new(
+        "contains",
+        &[Param::new("value", ValueKind::Any)],
+        |_, args| {
+            let contains = match &args[1] {
+                Value::Number(n) => range(&args).contains(*n),
+                _ => false,
+            };
+
+            Ok(Value::Bool(contains))
+        },
+    ),
+    NativeFunction::new("len", &[], |_, args| {
+        Ok(Value::Number(len(range(&args))? as f64))
+    }),
+    NativeFunction::new("toArray", &[], |_, args| {
+        let range = range(&args);
+        let len = len(range)?;
+
+        if len > MAX_VALUES {
+            return Err(RuntimeError::invalid_range(
+                &range.to_string(),
+                format!("the array would have more than {MAX_VALUES} values"),
+            ));
+        }
+
+        let values = (0..len)
+            .filter_map(|index| range.nth(index))
+            .map(Value::Number)
+            .collect();
+
+        Ok(Value::array(values))
+    }),
+];
+
+// the most values an array made from a range holds, so that a mistaken range fails rather
+// than taking the whole memory
+const MAX_VALUES: usize = 1 << 25;
+
+// the largest integer of which every number below is exactly represented
+const MAX_LEN: f64 = 9_007_199_254_740_991.0;
+
+/// the number of values of a range, which must have both ends and a length that is a number
+/// exactly
+fn len(range: &Range) -> RuntimeResult<usize> {
+    let invalid = |reason: String| RuntimeError::invalid_range(&range.to_string(), reason);
+
+    match range.len() {
+        Some(len) if len <= MAX_LEN => Ok(len as usize),
+        Some(_) => Err(invalid(format!(
+            "the range has more than {} values",
+            format_number(MAX_LEN)
+        ))),
+        None => {
+            let missing = if range.from.is_none() { "start" } else { "end" };
+            Err(invalid(format!("the range has no {missing}")))
+        }
+    }
+}
+
+fn range(args: &[Value]) -> &Range {
+    match &args[0] {
+        Value::Range(range) => range,
+        _ => unreachable!(),
+    }
+}
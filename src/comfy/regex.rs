@@ -0,0 +1,237 @@
+// regular expressions, from `/pattern/flags` literals or compiled at runtime; positions
+// count characters like the string methods do
+
+use std::{cell::RefCell, rc::Rc};
+
+use hashbrown::HashMap;
+
+use super::{count, number, optional, string, NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    value::{Regex, Value, ValueKind},
+    Interpreter,
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "regex",
+    functions: FUNCTIONS,
+    constants: &[],
+};
+
+const TEXT: &[Param] = &[Param::new("text", ValueKind::Str)];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new(
+        "compile",
+        &[
+            Param::new("pattern", ValueKind::Str),
+            Param::optional("flags", ValueKind::Str),
+        ],
+        |_, args| {
+            // patterns built at runtime are not cached, there may be any number of them
+            let flags = optional(&args, 1).map_or("".into(), |_| string(&args, 1));
+            Ok(Value::Regex(Rc::new(Regex::new(
+                &string(&args, 0),
+                &flags,
+            )?)))
+        },
+    ),
+    // the text with every character that has a meaning in patterns escaped
+    NativeFunction::new("escape", TEXT, |_, args| {
+        Ok(Value::str(&::regex::escape(&string(&args, 0))))
+    }),
+];
+
+pub static REGEX_METHODS: &[NativeFunction] = &[
+    // the first match with its index and groups, nil if there is none
+    NativeFunction::new("captures", TEXT, |_, args| {
+        let (regex, text) = (regex(&args), string(&args, 1));
+
+        Ok(regex.regex.captures(&text).map_or(Value::Nil, |captures| {
+            captures_value(&regex, &text, &captures)
+        }))
+    }),
+    // the first matched text, nil if there is none
+    NativeFunction::new("match", TEXT, |_, args| {
+        let text = string(&args, 1);
+
+        Ok(regex(&args)
+            .regex
+            .find(&text)
+            .map_or(Value::Nil, |found| Value::str(found.as_str())))
+    }),
+    // every match with its index and groups
+    NativeFunction::new("matchAll", TEXT, |_, args| {
+        let (regex, text) = (regex(&args), string(&args, 1));
+
+        let matches = regex
+            .regex
+            .captures_iter(&text)
+            .map(|captures| captures_value(&regex, &text, &captures))
+            .collect();
+
+        Ok(Value::array(matches))
+    }),
+    NativeFunction::new(
+        "replace",
+        &[
+            Param::new("text", ValueKind::Str),
+            Param::new("replacement", ValueKind::Any),
+            Param::optional("count", ValueKind::Number),
+        ],
+        |interpreter, args| {
+            let limit = match optional(&args, 3) {
+                Some(_) => count("replace", "count", number(&args, 3))?,
+                None => usize::MAX,
+            };
+
+            replace(
+                interpreter,
+                &regex(&args),
+                &string(&args, 1),
+                &args[2],
+                limit,
+            )
+        },
+    ),
+    NativeFunction::new(
+        "split",
+        &[
+            Param::new("text", ValueKind::Str),
+            Param::optional("limit", ValueKind::Number),
+        ],
+        |_, args| {
+            let limit = match optional(&args, 2) {
+                Some(_) => count("split", "limit", number(&args, 2))?,
+                None => usize::MAX,
+            };
+
+            split(&regex(&args), &string(&args, 1), limit)
+        },
+    ),
+    NativeFunction::new("test", TEXT, |_, args| {
+        Ok(Value::Bool(regex(&args).regex.is_match(&string(&args, 1))))
+    }),
+];
+
+thread_local! {
+    // literals are compiled once however many times they are evaluated
+    static COMPILED: RefCell<HashMap<(String, String), Rc<Regex>>> = RefCell::new(HashMap::new());
+}
+
+/// the regex of a literal, an invalid pattern being an error
+pub fn literal(pattern: &str, flags: &str) -> RuntimeResult<Value> {
+    let key = (pattern.to_owned(), flags.to_owned());
+
+    if let Some(regex) = COMPILED.with(|compiled| compiled.borrow().get(&key).cloned()) {
+        return Ok(Value::Regex(regex));
+    }
+
+    let regex = Rc::new(Regex::new(pattern, flags)?);
+    COMPILED.with(|compiled| compiled.borrow_mut().insert(key, Rc::clone(&regex)));
+
+    Ok(Value::Regex(regex))
+}
+
+/// replaces up to `limit` matches, with a string where `$1` or `$name` stand for groups,
+/// or with what a function returns for the captures of each match
+pub fn replace(
+    interpreter: &mut Interpreter,
+    regex: &Regex,
+    text: &str,
+    replacement: &Value,
+    limit: usize,
+) -> RuntimeResult<Value> {
+    let replaced = match replacement {
+        Value::Str(replacement) => regex
+            .regex
+            .replacen(text, limit, replacement.as_ref())
+            .into_owned(),
+        Value::Function(_) | Value::Native(_) | Value::Method(_) => {
+            let mut replaced = String::with_capacity(text.len());
+            let mut last = 0;
+
+            for captures in regex.regex.captures_iter(text).take(limit) {
+                let found = captures.get(0).unwrap();
+                let value = captures_value(regex, text, &captures);
+
+                replaced.push_str(&text[last..found.start()]);
+                replaced.push_str(&interpreter.call(replacement, vec![value])?.to_string());
+                last = found.end();
+            }
+
+            replaced.push_str(&text[last..]);
+            replaced
+        }
+        value => {
+            return Err(RuntimeError::argument_type(
+                "replace",
+                "replacement",
+                ValueKind::Str,
+                value.kind(),
+            ))
+        }
+    };
+
+    Ok(Value::str(&replaced))
+}
+
+/// the text around up to `limit - 1` matches
+pub fn split(regex: &Regex, text: &str, limit: usize) -> RuntimeResult<Value> {
+    if limit == 0 {
+        return Err(RuntimeError::invalid_argument(
+            "split",
+            "`limit` must be at least 1".to_owned(),
+        ));
+    }
+
+    Ok(Value::array(
+        regex.regex.splitn(text, limit).map(Value::str).collect(),
+    ))
+}
+
+/// the character index of the first match starting at or after byte `start`
+pub fn find(regex: &Regex, text: &str, start: usize) -> Option<usize> {
+    let found = regex.regex.find_at(text, start)?;
+    Some(char_index(text, found.start()))
+}
+
+/// `{ match, index, groups, named }`, groups that did not take part in the match being nil
+fn captures_value(regex: &Regex, text: &str, captures: &::regex::Captures) -> Value {
+    let group = |index: usize| {
+        captures
+            .get(index)
+            .map_or(Value::Nil, |group| Value::str(group.as_str()))
+    };
+    let found = captures.get(0).unwrap();
+
+    let groups = (1..captures.len()).map(group).collect();
+    let named = regex
+        .regex
+        .capture_names()
+        .enumerate()
+        .filter_map(|(index, name)| Some((name?.to_owned(), group(index))))
+        .collect();
+
+    let mut object = HashMap::new();
+    object.insert("match".to_owned(), Value::str(found.as_str()));
+    object.insert(
+        "index".to_owned(),
+        Value::Number(char_index(text, found.start()) as f64),
+    );
+    object.insert("groups".to_owned(), Value::array(groups));
+    object.insert("named".to_owned(), Value::object(named));
+
+    Value::object(object)
+}
+
+fn char_index(text: &str, byte: usize) -> usize {
+    text[..byte].chars().count()
+}
+
+fn regex(args: &[Value]) -> Rc<Regex> {
+    match &args[0] {
+        Value::Regex(regex) => Rc::clone(regex),
+        _ => unreachable!(),
+    }
+}
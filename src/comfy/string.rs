@@ -0,0 +1,312 @@
+// methods of strings, lengths and positions counting characters rather than bytes
+
+use std::rc::Rc;
+
+use super::{count, number, optional, regex, string, NativeFunction, Param};
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    iteration::collect_values,
+    value::{Regex, Value, ValueKind},
+};
+
+const PATTERN: &[Param] = &[Param::new("pattern", ValueKind::Str)];
+// a string or a regex
+const SEARCHED: &[Param] = &[Param::new("pattern", ValueKind::Any)];
+
+enum Pattern {
+    Str(Rc<str>),
+    Regex(Rc<Regex>),
+}
+// the longest string in bytes that `repeat` and the paddings build, rather than running
+// out of memory
+const MAX_LENGTH: usize = 1 << 30;
+
+const PAD: &[Param] = &[
+    Param::new("width", ValueKind::Number),
+    Param::optional("fill", ValueKind::Str),
+];
+
+pub static STRING_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("chars", &[], |_, args| {
+        let chars = string(&args, 0)
+            .chars()
+            .map(|c| Value::str(c.encode_utf8(&mut [0; 4])))
+            .collect();
+
+        Ok(Value::array(chars))
+    }),
+    NativeFunction::new("contains", SEARCHED, |_, args| {
+        let s = string(&args, 0);
+
+        Ok(Value::Bool(
+            match pattern("contains", "pattern", &args[1])? {
+                Pattern::Str(pattern) => s.contains(&*pattern),
+                Pattern::Regex(regex) => regex.regex.is_match(&s),
+            },
+        ))
+    }),
+    NativeFunction::new("endsWith", PATTERN, |_, args| {
+        Ok(Value::Bool(string(&args, 0).ends_with(&*string(&args, 1))))
+    }),
+    NativeFunction::new(
+        "find",
+        &[
+            Param::new("pattern", ValueKind::Any),
+            Param::optional("from", ValueKind::Number),
+        ],
+        |_, args| {
+            let s = string(&args, 0);
+            let from = match optional(&args, 2) {
+                Some(_) => count("find", "from", number(&args, 2))?,
+                None => 0,
+            };
+
+            // the byte offset of the `from`th character, searching nothing past the end
+            let Some(start) = char_offset(&s, from) else {
+                return Ok(Value::Nil);
+            };
+
+            let found = match pattern("find", "pattern", &args[1])? {
+                Pattern::Str(pattern) => s[start..]
+                    .find(&*pattern)
+                    .map(|offset| from + s[start..start + offset].chars().count()),
+                Pattern::Regex(regex) => regex::find(&regex, &s, start),
+            };
+
+            Ok(found.map_or(Value::Nil, |index| Value::Number(index as f64)))
+        },
+    ),
+    NativeFunction::new(
+        "join",
+        &[Param::new("values", ValueKind::Any)],
+        |interpreter, args| {
+            let parts = collect_values(interpreter, &args[1])?
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>();
+
+            Ok(Value::str(&parts.join(&string(&args, 0))))
+        },
+    ),
+    NativeFunction::new("len", &[], |_, args| {
+        Ok(Value::Number(string(&args, 0).chars().count() as f64))
+    }),
+    NativeFunction::new("lines", &[], |_, args| {
+        Ok(Value::array(
+            string(&args, 0).lines().map(Value::str).collect(),
+        ))
+    }),
+    NativeFunction::new("lower", &[], |_, args| {
+        Ok(Value::str(&string(&args, 0).to_lowercase()))
+    }),
+    NativeFunction::new("padEnd", PAD, |_, args| {
+        let (s, padding) = padding("padEnd", &args)?;
+        Ok(Value::str(&(s.to_string() + &padding)))
+    }),
+    NativeFunction::new("padStart", PAD, |_, args| {
+        let (s, padding) = padding("padStart", &args)?;
+        Ok(Value::str(&(padding + &s)))
+    }),
+    NativeFunction::new(
+        "repeat",
+        &[Param::new("count", ValueKind::Number)],
+        |_, args| {
+            let s = string(&args, 0);
+            let times = count("repeat", "count", number(&args, 1))?;
+            check_length("repeat", s.len().checked_mul(times))?;
+
+            Ok(Value::str(&s.repeat(times)))
+        },
+    ),
+    NativeFunction::new(
+        "replace",
+        &[
+            Param::new("pattern", ValueKind::Any),
+            Param::new("replacement", ValueKind::Any),
+            Param::optional("count", ValueKind::Number),
+        ],
+        |interpreter, args| {
+            let s = string(&args, 0);
+            let times = match optional(&args, 3) {
+                Some(_) => Some(count("replace", "count", number(&args, 3))?),
+                None => None,
+            };
+
+            // with a regex, the replacement can refer to groups or be a function
+            let pattern = match pattern("replace", "pattern", &args[1])? {
+                Pattern::Str(pattern) => pattern,
+                Pattern::Regex(regex) => {
+                    let limit = times.unwrap_or(usize::MAX);
+                    return regex::replace(interpreter, &regex, &s, &args[2], limit);
+                }
+            };
+
+            let Value::Str(replacement) = &args[2] else {
+                return Err(RuntimeError::argument_type(
+                    "replace",
+                    "replacement",
+                    ValueKind::Str,
+                    args[2].kind(),
+                ));
+            };
+
+            let replaced = match times {
+                Some(times) => s.replacen(&*pattern, replacement, times),
+                None => s.replace(&*pattern, replacement),
+            };
+
+            Ok(Value::str(&replaced))
+        },
+    ),
+    NativeFunction::new(
+        "split",
+        &[
+            Param::optional("separator", ValueKind::Any),
+            Param::optional("limit", ValueKind::Number),
+        ],
+        |_, args| {
+            let s = string(&args, 0);
+            let limit = match optional(&args, 2) {
+                Some(_) => count("split", "limit", number(&args, 2))?,
+                None => usize::MAX,
+            };
+
+            if limit == 0 {
+                return Err(RuntimeError::invalid_argument(
+                    "split",
+                    "`limit` must be at least 1".to_owned(),
+                ));
+            }
+
+            let separator = match optional(&args, 1) {
+                Some(separator) => Some(pattern("split", "separator", separator)?),
+                None => None,
+            };
+
+            let parts = match separator {
+                // on runs of whitespace, leading and trailing whitespace being ignored
+                None => split_whitespace(&s, limit),
+                Some(Pattern::Str(separator)) if separator.is_empty() => split_chars(&s, limit),
+                Some(Pattern::Str(separator)) => {
+                    s.splitn(limit, &*separator).map(Value::str).collect()
+                }
+                Some(Pattern::Regex(regex)) => return regex::split(&regex, &s, limit),
+            };
+
+            Ok(Value::array(parts))
+        },
+    ),
+    NativeFunction::new("startsWith", PATTERN, |_, args| {
+        Ok(Value::Bool(
+            string(&args, 0).starts_with(&*string(&args, 1)),
+        ))
+    }),
+    NativeFunction::new("toNumber", &[], |_, args| {
+        let s = string(&args, 0);
+        let s = s.trim();
+
+        // only plain decimal notation, `inf` or `nan` spelled out being rejected
+        let is_decimal = s
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'));
+
+        Ok(match s.parse::<f64>() {
+            Ok(n) if is_decimal => Value::Number(n),
+            _ => Value::Nil,
+        })
+    }),
+    NativeFunction::new("trim", &[], |_, args| {
+        Ok(Value::str(string(&args, 0).trim()))
+    }),
+    NativeFunction::new("upper", &[], |_, args| {
+        Ok(Value::str(&string(&args, 0).to_uppercase()))
+    }),
+];
+
+/// a string or regex argument
+fn pattern(function: &str, param: &str, value: &Value) -> RuntimeResult<Pattern> {
+    match value {
+        Value::Str(s) => Ok(Pattern::Str(Rc::clone(s))),
+        Value::Regex(regex) => Ok(Pattern::Regex(Rc::clone(regex))),
+        value => Err(RuntimeError::argument_type(
+            function,
+            param,
+            ValueKind::Str,
+            value.kind(),
+        )),
+    }
+}
+
+/// the byte offset of the character at `index`, the length counting as the last offset
+fn char_offset(s: &str, index: usize) -> Option<usize> {
+    s.char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(s.len()))
+        .nth(index)
+}
+
+/// the receiver and the fill repeated up to the requested width, in characters
+fn padding(function: &str, args: &[Value]) -> RuntimeResult<(Rc<str>, String)> {
+    let s = string(args, 0);
+    let width = count(function, "width", number(args, 1))?;
+    let fill = match optional(args, 2) {
+        Some(_) => string(args, 2),
+        None => Rc::from(" "),
+    };
+
+    if fill.is_empty() {
+        return Err(RuntimeError::invalid_argument(
+            function,
+            "`fill` cannot be empty".to_owned(),
+        ));
+    }
+
+    let missing = width.saturating_sub(s.chars().count());
+    // every character of the padding is at most as long as the whole fill
+    check_length(function, missing.checked_mul(fill.len()))?;
+    let padding = fill.chars().cycle().take(missing).collect();
+
+    Ok((s, padding))
+}
+
+fn check_length(function: &str, length: Option<usize>) -> RuntimeResult<()> {
+    match length {
+        Some(length) if length <= MAX_LENGTH => Ok(()),
+        _ => Err(RuntimeError::invalid_argument(
+            function,
+            format!("the string would be longer than {MAX_LENGTH} bytes"),
+        )),
+    }
+}
+
+fn split_whitespace(s: &str, limit: usize) -> Vec<Value> {
+    let mut parts = Vec::new();
+    let mut rest = s.trim_start();
+
+    while !rest.is_empty() {
+        if parts.len() + 1 == limit {
+            parts.push(Value::str(rest));
+            break;
+        }
+
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        parts.push(Value::str(&rest[..end]));
+        rest = rest[end..].trim_start();
+    }
+
+    parts
+}
+
+fn split_chars(s: &str, limit: usize) -> Vec<Value> {
+    let mut parts = Vec::new();
+
+    for (offset, c) in s.char_indices() {
+        if parts.len() + 1 == limit {
+            parts.push(Value::str(&s[offset..]));
+            break;
+        }
+        parts.push(Value::str(c.encode_utf8(&mut [0; 4])));
+    }
+
+    parts
+}
@@ -1 +1,289 @@
-// working with threads and parrallelism
+// running functions on other threads, sharing values through channels, mutexes and atomics,
+// see `interpreter::sendable` for which values can cross threads
+
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+};
+
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+
+use super::{number, optional, NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    sendable::{lock, Channel, Sendable, Shared, ThreadHandle},
+    value::{Value, ValueKind},
+    Interpreter,
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "thread",
+    functions: FUNCTIONS,
+    constants: &[],
+};
+
+// the largest integers an atomic holds, so that they stay exact as numbers
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+const VALUE: &[Param] = &[Param::new("value", ValueKind::Any)];
+const AMOUNT: &[Param] = &[Param::new("amount", ValueKind::Number)];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new(
+        "Atomic",
+        &[Param::optional("value", ValueKind::Number)],
+        |_, args| {
+            let value = match optional(&args, 0) {
+                Some(_) => integer("Atomic", number(&args, 0))?,
+                None => 0,
+            };
+
+            Ok(Value::Shared(Shared::Atomic(Arc::new(AtomicI64::new(
+                value,
+            )))))
+        },
+    ),
+    NativeFunction::new("Channel", &[], |_, _| {
+        Ok(Value::Shared(Shared::Channel(Arc::default())))
+    }),
+    NativeFunction::new("Mutex", VALUE, |_, args| {
+        let value = Sendable::new(&args)?;
+        Ok(Value::Shared(Shared::Mutex(Arc::new(Mutex::new(value)))))
+    }),
+    NativeFunction::new(
+        "parallelMap",
+        &[
+            Param::new("values", ValueKind::Array),
+            Param::new("function", ValueKind::Function),
+        ],
+        |_, args| {
+            let Value::Array(array) = &args[0] else {
+                unreachable!()
+            };
+
+            let function = Sendable::new(&args[1..])?;
+            let values = array
+                .borrow()
+                .iter()
+                .map(|value| Sendable::new(std::slice::from_ref(value)))
+                .collect::<RuntimeResult<Vec<_>>>()?;
+
+            let results = values
+                .par_iter()
+                .map(|value| {
+                    let mut interpreter = Interpreter::new();
+                    let result =
+                        interpreter.call(&function.unpack_one(), vec![value.unpack_one()])?;
+                    let result = interpreter.settle(result)?;
+                    interpreter.run_event_loop()?;
+
+                    Sendable::new(&[result])
+                })
+                .collect::<RuntimeResult<Vec<_>>>()?;
+
+            Ok(Value::array(
+                results.iter().map(Sendable::unpack_one).collect(),
+            ))
+        },
+    ),
+    NativeFunction::new(
+        "spawn",
+        &[
+            Param::new("function", ValueKind::Function),
+            Param::variadic("args", ValueKind::Any),
+        ],
+        |_, args| {
+            let package = Sendable::new(&args)?;
+
+            let handle = thread::spawn(move || {
+                let mut interpreter = Interpreter::new();
+                let mut values = package.unpack();
+                let function = values.remove(0);
+
+                // an async function sends what its promise resolves to
+                let result = interpreter.call(&function, values)?;
+                let result = interpreter.settle(result)?;
+                interpreter.run_event_loop()?;
+
+                Sendable::new(&[result])
+            });
+
+            Ok(Value::Shared(Shared::Thread(Arc::new(ThreadHandle::new(
+                handle,
+            )))))
+        },
+    ),
+];
+
+pub static THREAD_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("isFinished", &[], |_, args| {
+        let Value::Shared(Shared::Thread(handle)) = &args[0] else {
+            unreachable!()
+        };
+
+        Ok(Value::Bool(handle.is_finished()))
+    }),
+    NativeFunction::new("join", &[], |_, args| {
+        let Value::Shared(Shared::Thread(handle)) = &args[0] else {
+            unreachable!()
+        };
+
+        let result = handle.join().map_err(|mut err| {
+            err.notes.push("raised by a joined thread".to_owned());
+            err
+        })?;
+
+        Ok(result.unpack_one())
+    }),
+];
+
+pub static CHANNEL_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("close", &[], |_, args| {
+        channel(&args).close();
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("isClosed", &[], |_, args| {
+        Ok(Value::Bool(channel(&args).is_closed()))
+    }),
+    NativeFunction::new("recv", &[], |interpreter, args| {
+        // a thread waiting for others should not hold back its own output
+        interpreter.flush_stdout()?;
+
+        let value = channel(&args).recv();
+        Ok(value.map_or(Value::Nil, |value| value.unpack_one()))
+    }),
+    NativeFunction::new("send", VALUE, |_, args| {
+        let value = Sendable::new(&args[1..])?;
+
+        if !channel(&args).send(value) {
+            return Err(RuntimeError::channel_closed());
+        }
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("tryRecv", &[], |_, args| {
+        let value = channel(&args).try_recv();
+        Ok(value.map_or(Value::Nil, |value| value.unpack_one()))
+    }),
+];
+
+pub static MUTEX_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("get", &[], |_, args| Ok(lock_mutex(&args)?.unpack_one())),
+    NativeFunction::new("set", VALUE, |_, args| {
+        let value = Sendable::new(&args[1..])?;
+        *lock_mutex(&args)? = value;
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new(
+        "update",
+        &[Param::new("function", ValueKind::Function)],
+        |interpreter, args| {
+            // held while the function runs, so that no other thread sees a stale value
+            let mut value = lock_mutex(&args)?;
+
+            let pointer: *const Mutex<Sendable> = mutex(&args);
+            UPDATING.with(|updating| updating.borrow_mut().push(pointer));
+            let result = interpreter.call(&args[1], vec![value.unpack_one()]);
+            UPDATING.with(|updating| updating.borrow_mut().pop());
+
+            let result = result?;
+            *value = Sendable::new(std::slice::from_ref(&result))?;
+
+            Ok(result)
+        },
+    ),
+];
+
+pub static ATOMIC_METHODS: &[NativeFunction] = &[
+    NativeFunction::new("add", AMOUNT, |_, args| {
+        let amount = integer("add", number(&args, 1))?;
+        let previous = atomic(&args).fetch_add(amount, Ordering::SeqCst);
+
+        Ok(Value::Number((previous + amount) as f64))
+    }),
+    NativeFunction::new(
+        "compareAndSwap",
+        &[
+            Param::new("current", ValueKind::Number),
+            Param::new("new", ValueKind::Number),
+        ],
+        |_, args| {
+            let current = integer("compareAndSwap", number(&args, 1))?;
+            let new = integer("compareAndSwap", number(&args, 2))?;
+
+            let swapped = atomic(&args)
+                .compare_exchange(current, new, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok();
+
+            Ok(Value::Bool(swapped))
+        },
+    ),
+    NativeFunction::new("get", &[], |_, args| {
+        Ok(Value::Number(atomic(&args).load(Ordering::SeqCst) as f64))
+    }),
+    NativeFunction::new("set", AMOUNT, |_, args| {
+        let value = integer("set", number(&args, 1))?;
+        atomic(&args).store(value, Ordering::SeqCst);
+
+        Ok(Value::Nil)
+    }),
+    NativeFunction::new("sub", AMOUNT, |_, args| {
+        let amount = integer("sub", number(&args, 1))?;
+        let previous = atomic(&args).fetch_sub(amount, Ordering::SeqCst);
+
+        Ok(Value::Number((previous - amount) as f64))
+    }),
+];
+
+fn integer(function: &str, n: f64) -> RuntimeResult<i64> {
+    if n.fract() != 0.0 || n.abs() > MAX_SAFE_INTEGER {
+        return Err(RuntimeError::invalid_argument(
+            function,
+            format!("atomics hold integers up to 2^53, found `{n}`"),
+        ));
+    }
+
+    Ok(n as i64)
+}
+
+// receiver accessors, the receiver type being guaranteed by the method lookup
+
+fn channel(args: &[Value]) -> &Channel {
+    match &args[0] {
+        Value::Shared(Shared::Channel(channel)) => channel,
+        _ => unreachable!(),
+    }
+}
+
+thread_local! {
+    // the mutexes whose `update` runs on this thread, which locking again would deadlock
+    static UPDATING: RefCell<Vec<*const Mutex<Sendable>>> = const { RefCell::new(Vec::new()) };
+}
+
+fn lock_mutex(args: &[Value]) -> RuntimeResult<MutexGuard<'_, Sendable>> {
+    let pointer: *const Mutex<Sendable> = mutex(args);
+    if UPDATING.with(|updating| updating.borrow().contains(&pointer)) {
+        return Err(RuntimeError::mutex_updating());
+    }
+
+    Ok(lock(mutex(args)))
+}
+
+fn mutex(args: &[Value]) -> &Mutex<Sendable> {
+    match &args[0] {
+        Value::Shared(Shared::Mutex(mutex)) => mutex,
+        _ => unreachable!(),
+    }
+}
+
+fn atomic(args: &[Value]) -> &AtomicI64 {
+    match &args[0] {
+        Value::Shared(Shared::Atomic(atomic)) => atomic,
+        _ => unreachable!(),
+    }
+}
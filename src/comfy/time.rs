@@ -1 +1,672 @@
-// working with time
+// clocks, durations, sleeping and formatting dates with strftime-style patterns,
+// timestamps being milliseconds since the Unix epoch and dates always in UTC
+
+use std::{
+    rc::Rc,
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use hashbrown::HashMap;
+
+use super::{number, optional, string, Constant, NativeFunction, NativeModule, Param};
+use crate::interpreter::{
+    errors::{RuntimeError, RuntimeResult},
+    event_loop::Promise,
+    value::{Value, ValueKind},
+    Interpreter,
+};
+
+pub static MODULE: NativeModule = NativeModule {
+    name: "time",
+    functions: FUNCTIONS,
+    constants: &[("ISO_8601", Constant::Str(ISO_8601))],
+};
+
+const ISO_8601: &str = "%Y-%m-%dT%H:%M:%S.%LZ";
+
+// the same as the JavaScript Date range, keeping every computation exact
+const MAX_TIMESTAMP: f64 = 8.64e15;
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const AMOUNT: &[Param] = &[Param::new("amount", ValueKind::Number)];
+const DURATION: &[Param] = &[Param::new("duration", ValueKind::Any)];
+const TIMER: &[Param] = &[Param::new("id", ValueKind::Number)];
+const CALLBACK_DELAY: &[Param] = &[
+    Param::new("function", ValueKind::Function),
+    Param::new("delay", ValueKind::Any),
+    Param::variadic("args", ValueKind::Any),
+];
+
+static FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction::new("clearInterval", TIMER, clear_timer),
+    NativeFunction::new("clearTimeout", TIMER, clear_timer),
+    NativeFunction::new(
+        "date",
+        &[Param::optional("timestamp", ValueKind::Number)],
+        |_, args| {
+            let date = match optional(&args, 0) {
+                Some(_) => DateTime::from_timestamp("date", number(&args, 0))?,
+                None => DateTime::from_timestamp("date", now())?,
+            };
+
+            Ok(date.to_value())
+        },
+    ),
+    NativeFunction::new("days", AMOUNT, |_, args| {
+        Ok(Value::Duration(number(&args, 0) * MILLIS_PER_DAY as f64))
+    }),
+    // a promise resolving to nil once the duration elapsed, without blocking
+    NativeFunction::new("delay", DURATION, |interpreter, args| {
+        let delay = duration("delay", &args[0])?;
+        let promise = Promise::pending();
+
+        let resolved = Rc::clone(&promise);
+        interpreter
+            .set_timer(
+                Rc::new(move |interpreter| {
+                    interpreter.resolve(&resolved, Ok(Value::Nil));
+                    Ok(())
+                }),
+                delay,
+                false,
+            )
+            .ok_or_else(|| cannot_wait("delay", &args[0]))?;
+
+        Ok(Value::Promise(promise))
+    }),
+    NativeFunction::new(
+        "elapsed",
+        &[Param::new("start", ValueKind::Duration)],
+        |_, args| match &args[0] {
+            Value::Duration(start) => Ok(Value::Duration(instant() - start)),
+            _ => unreachable!(),
+        },
+    ),
+    NativeFunction::new(
+        "format",
+        &[
+            Param::new("timestamp", ValueKind::Number),
+            Param::optional("pattern", ValueKind::Str),
+        ],
+        |_, args| {
+            let date = DateTime::from_timestamp("format", number(&args, 0))?;
+            let pattern = optional(&args, 1).map_or(ISO_8601.into(), |_| string(&args, 1));
+
+            let text = format(&date, &pattern)
+                .map_err(|reason| RuntimeError::invalid_argument("format", reason))?;
+
+            Ok(Value::str(&text))
+        },
+    ),
+    NativeFunction::new("hours", AMOUNT, |_, args| {
+        Ok(Value::Duration(number(&args, 0) * 3_600_000.0))
+    }),
+    NativeFunction::new("instant", &[], |_, _| Ok(Value::Duration(instant()))),
+    NativeFunction::new("milliseconds", AMOUNT, |_, args| {
+        Ok(Value::Duration(number(&args, 0)))
+    }),
+    NativeFunction::new("minutes", AMOUNT, |_, args| {
+        Ok(Value::Duration(number(&args, 0) * 60_000.0))
+    }),
+    NativeFunction::new("now", &[], |_, _| Ok(Value::Number(now()))),
+    NativeFunction::new(
+        "parse",
+        &[
+            Param::new("text", ValueKind::Str),
+            Param::optional("pattern", ValueKind::Str),
+        ],
+        |_, args| {
+            let text = string(&args, 0);
+            let pattern = optional(&args, 1).map_or(ISO_8601.into(), |_| string(&args, 1));
+
+            let timestamp = parse(&text, &pattern)
+                .map_err(|reason| RuntimeError::time_parse(&text, &pattern, reason))?;
+
+            Ok(Value::Number(timestamp))
+        },
+    ),
+    NativeFunction::new("seconds", AMOUNT, |_, args| {
+        Ok(Value::Duration(number(&args, 0) * 1000.0))
+    }),
+    // calls the function every `delay` until the interval is cleared
+    NativeFunction::new("setInterval", CALLBACK_DELAY, |interpreter, args| {
+        set_timer("setInterval", interpreter, args, true)
+    }),
+    // calls the function once after `delay`, from the event loop
+    NativeFunction::new("setTimeout", CALLBACK_DELAY, |interpreter, args| {
+        set_timer("setTimeout", interpreter, args, false)
+    }),
+    NativeFunction::new("sleep", DURATION, |interpreter, args| {
+        let delay = duration("sleep", &args[0])?;
+
+        // output written before sleeping is expected to show up right away
+        interpreter.flush_stdout()?;
+        thread::sleep(delay);
+
+        Ok(Value::Nil)
+    }),
+];
+
+/// a duration or milliseconds that can be waited for
+fn duration(function: &str, value: &Value) -> RuntimeResult<Duration> {
+    let ms = match value {
+        Value::Number(ms) | Value::Duration(ms) => *ms,
+        value => {
+            return Err(RuntimeError::argument_type(
+                function,
+                "duration",
+                ValueKind::Duration,
+                value.kind(),
+            ))
+        }
+    };
+
+    // negative, not a number or too long to be represented
+    Duration::try_from_secs_f64(ms / 1000.0).map_err(|_| cannot_wait(function, value))
+}
+
+fn cannot_wait(function: &str, value: &Value) -> RuntimeError {
+    RuntimeError::invalid_argument(function, format!("cannot wait for `{value}`"))
+}
+
+/// the id of the new timer, calling the function with the extra arguments
+fn set_timer(
+    function: &str,
+    interpreter: &mut Interpreter,
+    mut args: Vec<Value>,
+    repeat: bool,
+) -> RuntimeResult<Value> {
+    let delay = duration(function, &args[1])?;
+    let callback = args.remove(0);
+    let delay_value = args.remove(0);
+
+    let id = interpreter
+        .set_timer(
+            Rc::new(move |interpreter| interpreter.call(&callback, args.clone()).map(|_| ())),
+            delay,
+            repeat,
+        )
+        .ok_or_else(|| cannot_wait(function, &delay_value))?;
+
+    Ok(Value::Number(id as f64))
+}
+
+/// whether the timer was still scheduled, clearing it twice being harmless
+fn clear_timer(interpreter: &mut Interpreter, args: Vec<Value>) -> RuntimeResult<Value> {
+    let id = number(&args, 0);
+    let cleared = id >= 0.0 && id.fract() == 0.0 && interpreter.clear_timer(id as u64);
+
+    Ok(Value::Bool(cleared))
+}
+
+/// milliseconds since the Unix epoch
+fn now() -> f64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis() as f64,
+        Err(e) => -(e.duration().as_millis() as f64),
+    }
+}
+
+/// monotonic milliseconds since an arbitrary point fixed for the whole process
+fn instant() -> f64 {
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+    ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    millisecond: u32,
+    // days since the Unix epoch
+    days: i64,
+}
+
+impl DateTime {
+    fn from_timestamp(function: &str, timestamp: f64) -> RuntimeResult<Self> {
+        if !timestamp.is_finite() || timestamp.abs() > MAX_TIMESTAMP {
+            return Err(RuntimeError::invalid_argument(
+                function,
+                format!("`{timestamp}` is not a valid timestamp"),
+            ));
+        }
+
+        let timestamp = timestamp.floor() as i64;
+        let days = timestamp.div_euclid(MILLIS_PER_DAY);
+        let time = timestamp.rem_euclid(MILLIS_PER_DAY) as u32;
+        let (year, month, day) = civil_from_days(days);
+
+        Ok(DateTime {
+            year,
+            month,
+            day,
+            hour: time / 3_600_000,
+            minute: time / 60_000 % 60,
+            second: time / 1000 % 60,
+            millisecond: time % 1000,
+            days,
+        })
+    }
+
+    // 0 being Sunday
+    fn weekday(&self) -> usize {
+        (self.days + 4).rem_euclid(7) as usize
+    }
+
+    fn year_day(&self) -> i64 {
+        self.days - days_from_civil(self.year, 1, 1) + 1
+    }
+
+    fn to_value(self) -> Value {
+        let fields = [
+            ("year", self.year as f64),
+            ("month", self.month as f64),
+            ("day", self.day as f64),
+            ("hour", self.hour as f64),
+            ("minute", self.minute as f64),
+            ("second", self.second as f64),
+            ("millisecond", self.millisecond as f64),
+            ("weekday", self.weekday() as f64),
+            ("yearDay", self.year_day() as f64),
+        ];
+
+        let object: HashMap<_, _> = fields
+            .into_iter()
+            .map(|(name, n)| (name.to_owned(), Value::Number(n)))
+            .collect();
+
+        Value::object(object)
+    }
+}
+
+// conversions between days since the epoch and proleptic Gregorian dates,
+// from http://howardhinnant.github.io/date_algorithms.html
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn format(date: &DateTime, pattern: &str) -> Result<String, String> {
+    let mut result = String::new();
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+
+        let specifier = chars.next().ok_or("the pattern ends with a lone `%`")?;
+        let hour12 = match date.hour % 12 {
+            0 => 12,
+            hour => hour,
+        };
+
+        let text = match specifier {
+            'Y' if date.year < 0 => format!("-{:04}", -date.year),
+            'Y' => format!("{:04}", date.year),
+            'y' => format!("{:02}", date.year.rem_euclid(100)),
+            'm' => format!("{:02}", date.month),
+            'd' => format!("{:02}", date.day),
+            'e' => format!("{:>2}", date.day),
+            'H' => format!("{:02}", date.hour),
+            'I' => format!("{hour12:02}"),
+            'M' => format!("{:02}", date.minute),
+            'S' => format!("{:02}", date.second),
+            'L' => format!("{:03}", date.millisecond),
+            'p' if date.hour < 12 => "AM".to_owned(),
+            'p' => "PM".to_owned(),
+            'j' => format!("{:03}", date.year_day()),
+            'a' => WEEKDAYS[date.weekday()][..3].to_owned(),
+            'A' => WEEKDAYS[date.weekday()].to_owned(),
+            'b' => MONTHS[date.month as usize - 1][..3].to_owned(),
+            'B' => MONTHS[date.month as usize - 1].to_owned(),
+            's' => {
+                let seconds =
+                    date.days * 86_400 + (date.hour * 3600 + date.minute * 60 + date.second) as i64;
+                seconds.to_string()
+            }
+            'z' => "+0000".to_owned(),
+            'Z' => "UTC".to_owned(),
+            'F' => format(date, "%Y-%m-%d")?,
+            'T' => format(date, "%H:%M:%S")?,
+            '%' => "%".to_owned(),
+            c => return Err(format!("unknown format specifier `%{c}`")),
+        };
+
+        result.push_str(&text);
+    }
+
+    Ok(result)
+}
+
+/// parses a date written with the given pattern, returning its timestamp
+fn parse(text: &str, pattern: &str) -> Result<f64, String> {
+    let mut fields = Fields {
+        year: 1970,
+        month: 1,
+        day: 1,
+        ..Fields::default()
+    };
+    let mut scanner = Scanner { text, position: 0 };
+
+    scanner.pattern(pattern, &mut fields)?;
+
+    if scanner.position != text.len() {
+        return Err(format!(
+            "unexpected `{}` after the date",
+            &text[scanner.position..]
+        ));
+    }
+
+    fields.timestamp()
+}
+
+#[derive(Debug, Default)]
+struct Fields {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    millisecond: u32,
+    // set by `%I` and `%p`
+    hour12: Option<u32>,
+    pm: Option<bool>,
+    year_day: Option<u32>,
+    // offset from UTC in minutes
+    offset: i64,
+    // set by `%s`, overriding every other field
+    epoch_seconds: Option<i64>,
+}
+
+impl Fields {
+    fn timestamp(&self) -> Result<f64, String> {
+        if let Some(seconds) = self.epoch_seconds {
+            return match seconds.checked_mul(1000) {
+                Some(timestamp) if timestamp.unsigned_abs() as f64 <= MAX_TIMESTAMP => {
+                    Ok(timestamp as f64)
+                }
+                _ => Err("the date is out of range".to_owned()),
+            };
+        }
+
+        let hour = match (self.hour12, self.pm) {
+            (Some(hour), _) if !(1..=12).contains(&hour) => {
+                return Err(format!("{hour} is not a valid hour for `%I`"));
+            }
+            (Some(hour), pm) => hour % 12 + if pm == Some(true) { 12 } else { 0 },
+            (None, Some(true)) if self.hour < 12 => self.hour + 12,
+            (None, _) => self.hour,
+        };
+
+        if !(1..=12).contains(&self.month) {
+            return Err(format!("{} is not a valid month", self.month));
+        }
+        if self.day < 1 || self.day > days_in_month(self.year, self.month) {
+            return Err(format!(
+                "{}-{:02} has no day {}",
+                self.year, self.month, self.day
+            ));
+        }
+        if hour > 23 || self.minute > 59 || self.second > 59 {
+            return Err(format!(
+                "{hour:02}:{:02}:{:02} is not a valid time",
+                self.minute, self.second
+            ));
+        }
+
+        let days = match self.year_day {
+            Some(year_day) => {
+                let length = if is_leap_year(self.year) { 366 } else { 365 };
+                if year_day < 1 || year_day > length {
+                    return Err(format!("{} has no day {year_day}", self.year));
+                }
+                days_from_civil(self.year, 1, 1) + year_day as i64 - 1
+            }
+            None => days_from_civil(self.year, self.month, self.day),
+        };
+
+        let time = (hour * 3_600_000 + self.minute * 60_000 + self.second * 1000 + self.millisecond)
+            as i64;
+        let timestamp = days * MILLIS_PER_DAY + time - self.offset * 60_000;
+
+        if timestamp.unsigned_abs() as f64 > MAX_TIMESTAMP {
+            return Err("the date is out of range".to_owned());
+        }
+
+        Ok(timestamp as f64)
+    }
+}
+
+struct Scanner<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Scanner<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.position..]
+    }
+
+    fn pattern(&mut self, pattern: &str, fields: &mut Fields) -> Result<(), String> {
+        let mut chars = pattern.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                self.literal(c)?;
+                continue;
+            }
+
+            match chars.next().ok_or("the pattern ends with a lone `%`")? {
+                'Y' => {
+                    let negative = self.eat('-');
+                    let year = self.number(4, 'Y')?;
+                    fields.year = if negative { -year } else { year };
+                }
+                'y' => {
+                    // the POSIX convention, 69 to 99 being the 20th century
+                    let year = self.number(2, 'y')?;
+                    fields.year = if year < 69 { 2000 + year } else { 1900 + year };
+                }
+                'm' => fields.month = self.number(2, 'm')? as u32,
+                'd' => fields.day = self.number(2, 'd')? as u32,
+                'e' => {
+                    self.eat(' ');
+                    fields.day = self.number(2, 'e')? as u32;
+                }
+                'H' => fields.hour = self.number(2, 'H')? as u32,
+                'I' => fields.hour12 = Some(self.number(2, 'I')? as u32),
+                'M' => fields.minute = self.number(2, 'M')? as u32,
+                'S' => fields.second = self.number(2, 'S')? as u32,
+                'L' => fields.millisecond = self.number(3, 'L')? as u32,
+                'j' => fields.year_day = Some(self.number(3, 'j')? as u32),
+                'p' => {
+                    let pm = self.name(&["AM", "PM"]).ok_or("expected `AM` or `PM`")?;
+                    fields.pm = Some(pm == 1);
+                }
+                'a' | 'A' => {
+                    self.name(&WEEKDAYS)
+                        .ok_or("expected the name of a weekday")?;
+                }
+                'b' | 'B' => {
+                    let month = self.name(&MONTHS).ok_or("expected the name of a month")?;
+                    fields.month = month as u32 + 1;
+                }
+                's' => {
+                    let negative = self.eat('-');
+                    let seconds = self.number(19, 's')?;
+                    fields.epoch_seconds = Some(if negative { -seconds } else { seconds });
+                }
+                'z' => fields.offset = self.offset()?,
+                'Z' => {
+                    self.name(&["UTC", "GMT"])
+                        .ok_or("expected `UTC` or `GMT`")?;
+                }
+                'F' => self.pattern("%Y-%m-%d", fields)?,
+                'T' => self.pattern("%H:%M:%S", fields)?,
+                '%' => self.literal('%')?,
+                c => return Err(format!("unknown format specifier `%{c}`")),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.rest().starts_with(c) {
+            self.position += c.len_utf8();
+            return true;
+        }
+
+        false
+    }
+
+    fn literal(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            return Ok(());
+        }
+
+        match self.rest().chars().next() {
+            Some(found) => Err(format!("expected `{c}`, found `{found}`")),
+            None => Err(format!("expected `{c}`, found the end of the text")),
+        }
+    }
+
+    // at most `max_digits` digits, required by the specifier `%{specifier}`
+    fn number(&mut self, max_digits: usize, specifier: char) -> Result<i64, String> {
+        let digits = self
+            .rest()
+            .bytes()
+            .take(max_digits)
+            .take_while(u8::is_ascii_digit)
+            .count();
+
+        if digits == 0 {
+            return Err(format!("expected a number for `%{specifier}`"));
+        }
+
+        let number = self.rest()[..digits]
+            .parse()
+            .map_err(|_| format!("the number for `%{specifier}` is too large"))?;
+        self.position += digits;
+
+        Ok(number)
+    }
+
+    // the index of the matched name, full names being tried before their first 3 letters
+    fn name(&mut self, names: &[&str]) -> Option<usize> {
+        let rest = self.rest().to_ascii_lowercase();
+
+        for length in [usize::MAX, 3] {
+            for (i, name) in names.iter().enumerate() {
+                let name = &name[..length.min(name.len())];
+
+                if rest.starts_with(&name.to_ascii_lowercase()) {
+                    self.position += name.len();
+                    return Some(i);
+                }
+            }
+        }
+
+        None
+    }
+
+    // `Z` or `+HHMM`, `+HH:MM`, `-HHMM` and `-HH:MM`
+    fn offset(&mut self) -> Result<i64, String> {
+        if self.eat('Z') {
+            return Ok(0);
+        }
+
+        let sign = if self.eat('+') {
+            1
+        } else if self.eat('-') {
+            -1
+        } else {
+            return Err("expected a UTC offset like `+0200` or `Z`".to_owned());
+        };
+
+        let hours = self.number(2, 'z')?;
+        self.eat(':');
+        let minutes = self.number(2, 'z')?;
+
+        if hours > 23 || minutes > 59 {
+            return Err(format!("{hours:02}:{minutes:02} is not a valid UTC offset"));
+        }
+
+        Ok(sign * (hours * 60 + minutes))
+    }
+}
@@ -1,11 +1,21 @@
 use std::{env::args, path::PathBuf};
 
+use crate::{format::FormatOptions, minify::MinifyOptions};
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Command {
-    RunFile(PathBuf),
-    MinifyFile(PathBuf),
+    // the script path and the arguments following it
+    RunFile(PathBuf, Vec<String>),
+    MinifyFile(PathBuf, MinifyOptions),
+    FormatFile(PathBuf, FormatOptions),
+    ListTokens(PathBuf),
+    ListStdlib(Option<String>),
     NotFound,
     MissingFileName,
+    UnknownOption(String),
+    MissingOptionValue(String),
+    // the option and the value given to it
+    InvalidOptionValue(String, String),
 }
 
 pub fn get_command() -> Command {
@@ -17,17 +27,79 @@ pub fn get_command() -> Command {
     if let Some(name) = command_name {
         match name.as_str() {
             "minify" => {
-                if let Some(file_name) = args.next() {
-                    return Command::MinifyFile(file_name.into());
-                } else {
-                    return Command::MissingFileName;
+                let mut options = MinifyOptions::default();
+                let mut file_name = None;
+
+                // options may come before or after the file or folder name
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--verify" => options.verify = true,
+                        "--aggressive" => options.aggressive = true,
+                        "--source-map" => options.source_map = true,
+                        "--stdout" => options.stdout = true,
+                        "--check" => options.check = true,
+                        "--out" => match args.next() {
+                            Some(out) => options.out = Some(out.into()),
+                            None => return Command::MissingOptionValue(arg),
+                        },
+                        option if option.starts_with("--") => {
+                            return Command::UnknownOption(arg);
+                        }
+                        _ => file_name = Some(arg),
+                    }
+                }
+
+                match file_name {
+                    Some(file_name) => return Command::MinifyFile(file_name.into(), options),
+                    None => return Command::MissingFileName,
                 }
             }
+            "fmt" => {
+                let mut options = FormatOptions::default();
+                let mut file_name = None;
+
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--check" => options.check = true,
+                        "--stdout" => options.stdout = true,
+                        "--indent" | "--width" => {
+                            let Some(value) = args.next() else {
+                                return Command::MissingOptionValue(arg);
+                            };
+                            let value = match value.parse::<usize>() {
+                                Ok(number) if number > 0 || arg == "--indent" => number,
+                                _ => return Command::InvalidOptionValue(arg, value),
+                            };
+
+                            match arg.as_str() {
+                                "--indent" => options.indent = value,
+                                _ => options.width = value,
+                            }
+                        }
+                        option if option.starts_with("--") => {
+                            return Command::UnknownOption(arg);
+                        }
+                        _ => file_name = Some(arg),
+                    }
+                }
+
+                match file_name {
+                    Some(file_name) => return Command::FormatFile(file_name.into(), options),
+                    None => return Command::MissingFileName,
+                }
+            }
+            "tokens" => match args.next() {
+                Some(file_name) => return Command::ListTokens(file_name.into()),
+                None => return Command::MissingFileName,
+            },
+            "stdlib" => {
+                return Command::ListStdlib(args.next());
+            }
             file_name => {
-                return Command::RunFile(file_name.into());
+                return Command::RunFile(file_name.into(), args.collect());
             }
         }
     }
 
-    return Command::NotFound;
+    Command::NotFound
 }
@@ -1 +1,47 @@
-// future setup of all errors
+// every error a comfy script can end with
+use std::{error::Error, fmt::Display};
+
+use codespan_reporting::{
+    diagnostic::Label,
+    files::SimpleFile,
+    term::{
+        self,
+        termcolor::{ColorChoice, StandardStream},
+    },
+};
+
+use crate::{interpreter::errors::RuntimeError, parser::errors::SyntaxError};
+
+#[derive(Debug)]
+pub enum ComfyError {
+    Syntax(SyntaxError<()>),
+    Runtime(RuntimeError),
+    // the script called `env.exit` with this status code
+    Exit(i32),
+}
+
+impl ComfyError {
+    pub fn print<Name: Display + AsRef<str> + Clone, Content: AsRef<str>>(
+        self,
+        file: SimpleFile<Name, Content>,
+    ) -> Result<(), Box<dyn Error>> {
+        let diagnostic = match self {
+            ComfyError::Syntax(err) => err.generate_diagnostic(),
+            ComfyError::Runtime(err) => match err.span.clone() {
+                Some(span) => err
+                    .generate_diagnostic()
+                    .with_labels(vec![Label::primary((), span)]),
+                // raised outside of any statement, as from the event loop
+                None => err
+                    .generate_diagnostic()
+                    .with_notes(vec![format!("in {}", file.name())]),
+            },
+            ComfyError::Exit(_) => return Ok(()),
+        };
+        let writer = StandardStream::stderr(ColorChoice::Always);
+        let config = codespan_reporting::term::Config::default();
+
+        term::emit(&mut writer.lock(), &config, &file, &diagnostic)?;
+        Ok(())
+    }
+}
@@ -7,7 +7,7 @@ use std::{
 
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 
-use crate::{get_file_content, script::ComfyScript};
+use crate::{errors::ComfyError, get_file_content, minify::source_map, script::ComfyScript};
 
 pub fn execute_folder(folder_path: &Path) -> Result<(), Box<dyn Error>> {
     let files = fs::read_dir(folder_path)
@@ -34,14 +34,16 @@ pub fn execute_folder(folder_path: &Path) -> Result<(), Box<dyn Error>> {
         };
 
         let script = ComfyScript::new(file_path.to_string_lossy(), content);
-        if let Err(err_data) = script.execute() {
-            let mut errors_list = errors.lock().unwrap();
-            errors_list.push(err_data);
-        } else {
-            println!(
+        match script.execute() {
+            Ok(()) | Err((ComfyError::Exit(0), _)) => println!(
                 "\x1b[33m{}\x1b[32m successfully executed!\x1b[0m",
                 file_path.display()
-            )
+            ),
+            Err((err, file)) => {
+                let err_data = source_map::remap(file_path, err, file);
+                let mut errors_list = errors.lock().unwrap();
+                errors_list.push(err_data);
+            }
         }
     });
 
@@ -49,6 +51,13 @@ pub fn execute_folder(folder_path: &Path) -> Result<(), Box<dyn Error>> {
     let errors_list = errors.into_inner().unwrap();
 
     for (err_data, file) in errors_list {
+        if let ComfyError::Exit(code) = err_data {
+            eprintln!(
+                "\x1b[33m{}\x1b[31m exited with status {code}\x1b[0m",
+                file.name()
+            );
+            continue;
+        }
         err_data.print(file).unwrap();
     }
     Ok(())
@@ -0,0 +1,666 @@
+// the formatter of `comfy fmt`, writing a script back with one statement per line, spaces
+// around operators and lists broken over several lines when they do not fit in the width.
+// Comments are kept where they were written, as are single blank lines between statements,
+// statements holding comments inside their expressions being left as they were written
+
+mod doc;
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use hashbrown::HashSet;
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+
+use self::doc::{group, indent, join, list, text, verbatim, Doc};
+use crate::{
+    collect_scripts, get_file_content,
+    minify::{
+        verify,
+        visit::{walk, Visitor},
+    },
+    parser::{
+        assignment::initial::VariableKeyword,
+        ast::{identifier::Identifier, quote, ASTNode, Expression, Span, Statement},
+        cst::parse_tree,
+    },
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct FormatOptions {
+    // the number of spaces each block is indented with
+    pub indent: usize,
+    // the width lines are kept within whenever they can be broken
+    pub width: usize,
+    // only check that the scripts are formatted
+    pub check: bool,
+    // print the formatted script rather than writing it back
+    pub stdout: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: 4,
+            width: 100,
+            check: false,
+            stdout: false,
+        }
+    }
+}
+
+/// formats a script, or every script of a folder, in place
+pub fn format_path(path: &Path, options: &FormatOptions) -> Result<(), Box<dyn Error>> {
+    if options.stdout && (path.is_dir() || options.check) {
+        return Err("`--stdout` only prints a single script, without `--check`!".into());
+    }
+
+    if options.stdout {
+        print!("{}", format_script(&get_file_content(path)?, options)?);
+        return Ok(());
+    }
+
+    let mut files = vec![];
+    if path.is_dir() {
+        collect_scripts(path, None, &mut files)?;
+        files.sort();
+    } else {
+        files.push(path.to_owned());
+    }
+
+    let changed = Mutex::new(vec![]);
+    let errors = Mutex::new(vec![]);
+
+    files
+        .par_iter()
+        .for_each(|file_path| match format_file(file_path, options) {
+            Ok(true) => changed.lock().unwrap().push(file_path.to_owned()),
+            Ok(false) => {}
+            Err(e) => errors
+                .lock()
+                .unwrap()
+                .push(format!("{}: {e}", file_path.display())),
+        });
+
+    let mut changed = changed.into_inner().unwrap();
+    changed.sort();
+    for path in &changed {
+        match options.check {
+            true => eprintln!("{}: not formatted", path.display()),
+            false => eprintln!("{}: formatted", path.display()),
+        }
+    }
+
+    let errors = errors.into_inner().unwrap();
+    for err in &errors {
+        eprintln!("\x1b[31mError\x1b[0m: {err}");
+    }
+
+    if !errors.is_empty() {
+        return Err(format!("{} script(s) could not be formatted!", errors.len()).into());
+    }
+    if options.check && !changed.is_empty() {
+        return Err(format!("{} script(s) are not formatted!", changed.len()).into());
+    }
+
+    Ok(())
+}
+
+/// formats a script in place, only comparing it with its formatting with `--check`,
+/// telling whether it changed
+fn format_file(path: &PathBuf, options: &FormatOptions) -> Result<bool, Box<dyn Error>> {
+    let content = get_file_content(path)?;
+    let formatted = format_script(&content, options)?;
+
+    if formatted == content {
+        return Ok(false);
+    }
+    if !options.check {
+        fs::write(path, formatted)?;
+    }
+
+    Ok(true)
+}
+
+pub fn format_script(content: &str, options: &FormatOptions) -> Result<String, Box<dyn Error>> {
+    let tree = parse_tree(content).map_err(|_| "Failed to parse script! Contains an error!")?;
+    let program = tree.ast();
+    let comments: Vec<Span> = tree.comments().iter().map(|c| c.span.clone()).collect();
+
+    let ASTNode::Program { body } = &program else {
+        unreachable!()
+    };
+    if body.is_empty() {
+        return Ok(String::new());
+    }
+
+    let formatter = Formatter::new(content, &program, &comments);
+    let formatted = doc::print(&formatter.program(body), options.indent, options.width);
+
+    // the formatted script must be the same program, with the same comments
+    verify(&program, &formatted, "formatted")?;
+    let written_comments = parse_tree(&formatted)
+        .map(|tree| tree.comments().len())
+        .unwrap_or_default();
+    if written_comments != comments.len() {
+        return Err("The formatted script lost a comment, please report this as a bug!".into());
+    }
+
+    Ok(formatted)
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    // the starts of the statements holding comments which are not statements of their own
+    verbatim: HashSet<usize>,
+}
+
+// a statement along with the comments written after it on the same line
+struct Line<'a> {
+    statement: &'a Statement,
+    trailing: Vec<&'a Statement>,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(source: &'a str, program: &ASTNode, comments: &[Span]) -> Self {
+        struct Spans(Vec<(Span, bool)>);
+
+        impl Visitor for Spans {
+            fn statements(&mut self, body: &mut Vec<Statement>) {
+                let spans = body
+                    .iter()
+                    .map(|statement| (statement.span.clone(), statement.node.is_comment()));
+                self.0.extend(spans);
+            }
+        }
+
+        let mut spans = Spans(Vec::new());
+        walk(&mut spans, &mut program.clone());
+
+        // the innermost statement holding each comment, unless the comment is one
+        let verbatim = comments
+            .iter()
+            .filter_map(|comment| {
+                spans
+                    .0
+                    .iter()
+                    .filter(|(span, _)| span.start <= comment.start && comment.end <= span.end)
+                    .max_by_key(|(span, _)| span.start)
+                    .filter(|(span, is_comment)| !(*is_comment && span.start == comment.start))
+                    .map(|(span, _)| span.start)
+            })
+            .collect();
+
+        Formatter { source, verbatim }
+    }
+
+    fn program(&self, body: &[Statement]) -> Doc {
+        let (leading, lines) = self.lines(body);
+        let mut docs: Vec<Doc> = leading.into_iter().map(|c| self.comment(c)).collect();
+        docs.extend(self.statements(&lines));
+
+        list(docs)
+    }
+
+    fn block(&self, node: &ASTNode) -> Doc {
+        let ASTNode::BlockStatement { body } = node else {
+            return self.node(node);
+        };
+        if body.is_empty() {
+            return text("{}");
+        }
+
+        // comments following the opening brace stay on its line
+        let (leading, lines) = self.lines(body);
+        let mut docs = vec![text("{")];
+        for comment in leading {
+            docs.extend([text(" "), self.comment(comment)]);
+        }
+
+        if !lines.is_empty() {
+            let mut statements = vec![Doc::HardLine];
+            statements.extend(self.statements(&lines));
+            docs.push(indent(statements));
+        }
+        docs.extend([Doc::HardLine, text("}")]);
+
+        list(docs)
+    }
+
+    /// the statements of a body with the comments that follow them on their line, and the
+    /// comments found first on the line of what precedes the body
+    fn lines(&self, body: &'a [Statement]) -> (Vec<&'a Statement>, Vec<Line<'a>>) {
+        let mut leading = vec![];
+        let mut lines: Vec<Line> = vec![];
+
+        for statement in body {
+            let line_start = self.source[..statement.span.start]
+                .rfind('\n')
+                .map_or(0, |index| index + 1);
+            let follows_code = !self.source[line_start..statement.span.start]
+                .trim()
+                .is_empty();
+
+            match lines.last_mut() {
+                _ if !(statement.node.is_comment() && follows_code) => lines.push(Line {
+                    statement,
+                    trailing: vec![],
+                }),
+                Some(line) => line.trailing.push(statement),
+                None => leading.push(statement),
+            }
+        }
+
+        (leading, lines)
+    }
+
+    fn statements(&self, lines: &[Line]) -> Vec<Doc> {
+        let mut docs = vec![];
+
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                let previous = &lines[i - 1];
+                let end = previous
+                    .trailing
+                    .last()
+                    .unwrap_or(&previous.statement)
+                    .span
+                    .end;
+
+                // groups of statements stay apart, by a single blank line
+                docs.push(Doc::HardLine);
+                if self.source[end..line.statement.span.start]
+                    .matches('\n')
+                    .count()
+                    > 1
+                {
+                    docs.push(Doc::HardLine);
+                }
+            }
+
+            docs.push(self.statement(line.statement));
+
+            if let Some(next) = lines.get(i + 1) {
+                if needs_separator(&line.statement.node, &next.statement.node) {
+                    docs.push(text(";"));
+                }
+            }
+            for comment in &line.trailing {
+                docs.extend([text(" "), self.comment(comment)]);
+            }
+        }
+
+        docs
+    }
+
+    fn statement(&self, statement: &Statement) -> Doc {
+        if self.verbatim.contains(&statement.span.start) {
+            return verbatim(&self.source[statement.span.clone()]);
+        }
+
+        self.node(&statement.node)
+    }
+
+    fn comment(&self, statement: &Statement) -> Doc {
+        verbatim(self.source[statement.span.clone()].trim_end())
+    }
+
+    fn node(&self, node: &ASTNode) -> Doc {
+        match node {
+            ASTNode::Program { body } => self.program(body),
+            ASTNode::ImportDeclaration { specifiers, source } => {
+                let specifiers = match specifiers.as_slice() {
+                    [specifier] if specifier.local.name == "*" => "*".to_owned(),
+                    [specifier] if specifier.imported.name == "*" => {
+                        format!("* as {}", specifier.local)
+                    }
+                    specifiers => specifiers
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                };
+
+                text(format!("import {specifiers} from {source}"))
+            }
+            ASTNode::VariableDeclaration { declarations, kind } => {
+                let declarations = declarations
+                    .iter()
+                    .map(|declaration| {
+                        list(vec![
+                            text(format!("{} = ", declaration.id)),
+                            self.expression(&declaration.init),
+                        ])
+                    })
+                    .collect();
+
+                list(vec![
+                    text(format!("{kind} ")),
+                    list(join(declarations, || vec![text(", ")])),
+                ])
+            }
+            ASTNode::ExpressionStatement { expression } => self.expression(expression),
+            ASTNode::FunctionDeclaration {
+                id,
+                params,
+                body,
+                is_async,
+                ..
+            } => {
+                let keyword = if *is_async { "async fn" } else { "fn" };
+
+                list(vec![
+                    text(format!("{keyword} {id}")),
+                    self.params(params),
+                    text(" "),
+                    self.block(body),
+                ])
+            }
+            ASTNode::ForStatement {
+                declarations,
+                kind,
+                source,
+                body,
+            } => {
+                // `let` is implied, unless the first name would be read as the keyword
+                let first = &declarations[0].name;
+                let keyword = match kind {
+                    VariableKeyword::Let
+                        if !first.starts_with("let") && !first.starts_with("var") =>
+                    {
+                        String::new()
+                    }
+                    kind => format!("{kind} "),
+                };
+                let declarations = declarations
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                list(vec![
+                    text(format!("for {keyword}{declarations} in ")),
+                    self.expression(source),
+                    text(" "),
+                    self.block(body),
+                ])
+            }
+            ASTNode::WhileStatement { test, body } => list(vec![
+                text("while "),
+                self.expression(test),
+                text(" "),
+                self.block(body),
+            ]),
+            ASTNode::IfStatement {
+                test,
+                body,
+                alternate,
+            } => {
+                let mut docs = vec![
+                    text("if "),
+                    self.expression(test),
+                    text(" "),
+                    self.block(body),
+                ];
+                if let Some(alternate) = alternate {
+                    docs.extend([text(" else "), self.block(alternate)]);
+                }
+
+                list(docs)
+            }
+            ASTNode::BlockStatement { .. } => self.block(node),
+            ASTNode::TryStatement {
+                block,
+                param,
+                handler,
+            } => {
+                let catch = match param {
+                    Some(param) => format!(" catch {param} "),
+                    None => " catch ".to_owned(),
+                };
+
+                list(vec![
+                    text("try "),
+                    self.block(block),
+                    text(catch),
+                    self.block(handler),
+                ])
+            }
+            ASTNode::ReturnStatement {
+                argument,
+                is_shortcut,
+            } => {
+                let keyword = if *is_shortcut { ">> " } else { "return " };
+                list(vec![text(keyword), self.expression(argument)])
+            }
+            ASTNode::YieldStatement { argument } => {
+                list(vec![text("yield "), self.expression(argument)])
+            }
+        }
+    }
+
+    fn expression(&self, expr: &Expression) -> Doc {
+        match expr {
+            // literals are kept as they were written
+            Expression::Literal { raw, .. } => text(raw),
+            Expression::TemplateLiteral { value, .. } => text(quote(value)),
+            Expression::Range {
+                from,
+                limits,
+                to,
+                step,
+            } => {
+                let mut docs = vec![];
+                if let Some(from) = from {
+                    docs.push(self.expression(from));
+                }
+                docs.push(text(limits.to_string()));
+                if let Some(to) = to {
+                    docs.push(self.expression(to));
+                }
+                if let Some(step) = step {
+                    docs.extend([text(" step "), self.expression(step)]);
+                }
+
+                list(docs)
+            }
+            Expression::Array { elements } => {
+                let elements = elements.iter().map(|e| self.expression(e)).collect();
+                self.bracketed("[", elements, "]", false)
+            }
+            Expression::Object { properties } => {
+                let properties = properties
+                    .iter()
+                    .map(|property| {
+                        list(vec![
+                            text(format!("{}: ", property.key)),
+                            self.expression(&property.value),
+                        ])
+                    })
+                    .collect();
+
+                self.bracketed("{", properties, "}", true)
+            }
+            Expression::BinaryExpression { operator, .. } => {
+                // a chain of operators of the same precedence is broken at once, after each
+                // operator
+                let precedence = operator.get_precedence();
+                let mut operands = vec![];
+                let mut first = expr;
+
+                while let Expression::BinaryExpression {
+                    left,
+                    operator,
+                    right,
+                } = first
+                {
+                    if operator.get_precedence() != precedence {
+                        break;
+                    }
+                    operands.push((operator, right));
+                    first = left;
+                }
+
+                let rest = operands
+                    .into_iter()
+                    .rev()
+                    .flat_map(|(operator, right)| {
+                        [
+                            text(format!(" {operator}")),
+                            Doc::Line,
+                            self.expression(right),
+                        ]
+                    })
+                    .collect();
+
+                group(vec![self.expression(first), indent(rest)])
+            }
+            Expression::MemberExpression {
+                indexed,
+                property,
+                computed,
+            } => {
+                if *computed {
+                    list(vec![
+                        self.expression(indexed),
+                        text("["),
+                        self.expression(property),
+                        text("]"),
+                    ])
+                } else {
+                    list(vec![
+                        self.expression(indexed),
+                        text("."),
+                        self.expression(property),
+                    ])
+                }
+            }
+            Expression::CallExpression { callee, args } => {
+                list(vec![self.expression(callee), self.arguments(args)])
+            }
+            Expression::AssignmentExpression {
+                operator,
+                id,
+                assigned,
+            } => list(vec![
+                self.expression(id),
+                text(format!(" {operator} ")),
+                self.expression(assigned),
+            ]),
+            Expression::IdentifierExpression(identifier) => text(&identifier.name),
+            Expression::Parenthesized(expr) => {
+                list(vec![text("("), self.expression(expr), text(")")])
+            }
+            Expression::Comment { raw_value, .. } => verbatim(raw_value.trim_end()),
+            Expression::FnExpression {
+                params,
+                body,
+                is_async,
+                ..
+            } => {
+                let params = params
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let keyword = if *is_async { "async " } else { "" };
+
+                list(vec![
+                    text(format!("{keyword}|{params}| ")),
+                    self.block(body),
+                ])
+            }
+            Expression::AwaitExpression { argument } => {
+                list(vec![text("await "), self.expression(argument)])
+            }
+        }
+    }
+
+    /// the parameters of a function declaration, which may not end with a comma
+    fn params(&self, params: &[Identifier]) -> Doc {
+        let params = params.iter().map(|param| text(&param.name)).collect();
+
+        let mut inner = vec![Doc::SoftLine];
+        inner.extend(join(params, || vec![text(","), Doc::Line]));
+
+        group(vec![text("("), indent(inner), Doc::SoftLine, text(")")])
+    }
+
+    /// the arguments of a call, a last function or a single object or array being written
+    /// from the line of the call
+    fn arguments(&self, args: &[Expression]) -> Doc {
+        let hugged = match args {
+            [.., Expression::FnExpression {
+                is_shortcut: false, ..
+            }] => true,
+            [Expression::Object { properties }] => !properties.is_empty(),
+            [Expression::Array { elements }] => !elements.is_empty(),
+            _ => false,
+        };
+
+        let args = args.iter().map(|arg| self.expression(arg)).collect();
+
+        if hugged {
+            return list(vec![
+                text("("),
+                list(join(args, || vec![text(", ")])),
+                text(")"),
+            ]);
+        }
+
+        self.bracketed("(", args, ")", false)
+    }
+
+    /// a list either on one line or with an item per line, `padded` lists having spaces
+    /// inside their brackets on one line
+    fn bracketed(&self, open: &str, items: Vec<Doc>, close: &str, padded: bool) -> Doc {
+        if items.is_empty() {
+            return text(format!("{open}{close}"));
+        }
+
+        let line = || if padded { Doc::Line } else { Doc::SoftLine };
+
+        let mut inner = vec![line()];
+        inner.extend(join(items, || vec![text(","), Doc::Line]));
+        inner.push(Doc::IfBroken(","));
+
+        group(vec![text(open), indent(inner), line(), text(close)])
+    }
+}
+
+/// whether two statements on their own lines would not be read back as they are, as a
+/// following `else…` is read as the alternate of an `if`, and a following `-1`, `..2` or
+/// regular expression as the continuation of an expression
+fn needs_separator(previous: &ASTNode, next: &ASTNode) -> bool {
+    let ASTNode::ExpressionStatement { expression } = next else {
+        return false;
+    };
+
+    let mut first = expression;
+    loop {
+        first = match first {
+            Expression::BinaryExpression { left, .. } => left,
+            Expression::MemberExpression { indexed, .. } => indexed,
+            Expression::CallExpression { callee, .. } => callee,
+            Expression::AssignmentExpression { id, .. } => id,
+            Expression::Range {
+                from: Some(from), ..
+            } => from,
+            _ => break,
+        };
+    }
+
+    let continues = match first {
+        Expression::Range { from: None, .. } => true,
+        Expression::Literal { raw, .. } => raw.starts_with(['-', '+', '/', '.']),
+        _ => false,
+    };
+    let is_else =
+        matches!(first, Expression::IdentifierExpression(id) if id.name.starts_with("else"));
+
+    (continues && !previous.ends_with_block())
+        || (is_else && matches!(previous, ASTNode::IfStatement { .. }))
+}
@@ -0,0 +1,176 @@
+// documents describing the ways code can be laid out: a group is printed on a single line
+// when it fits in the width left, its lines being broken otherwise
+
+pub enum Doc {
+    Text(String),
+    // a space in a group printed on one line, a new line otherwise
+    Line,
+    // nothing in a group printed on one line, a new line otherwise
+    SoftLine,
+    // a new line breaking every group around it
+    HardLine,
+    // a new line without indentation, for text kept as it was written
+    LiteralLine,
+    // written only when the group is broken, as trailing commas
+    IfBroken(&'static str),
+    Indent(Vec<Doc>),
+    Group { docs: Vec<Doc>, broken: bool },
+    List(Vec<Doc>),
+}
+
+pub fn text(text: impl Into<String>) -> Doc {
+    Doc::Text(text.into())
+}
+
+pub fn indent(docs: Vec<Doc>) -> Doc {
+    Doc::Indent(docs)
+}
+
+pub fn group(docs: Vec<Doc>) -> Doc {
+    let broken = docs.iter().any(Doc::has_hard_line);
+    Doc::Group { docs, broken }
+}
+
+pub fn list(docs: Vec<Doc>) -> Doc {
+    Doc::List(docs)
+}
+
+/// the documents with `separator` between them
+pub fn join(docs: Vec<Doc>, separator: impl Fn() -> Vec<Doc>) -> Vec<Doc> {
+    let mut joined = Vec::with_capacity(docs.len() * 2);
+
+    for (i, doc) in docs.into_iter().enumerate() {
+        if i > 0 {
+            joined.extend(separator());
+        }
+        joined.push(doc);
+    }
+
+    joined
+}
+
+/// text that may span several lines, the following ones keeping their indentation
+pub fn verbatim(written: &str) -> Doc {
+    let lines = written
+        .split('\n')
+        .map(|line| text(line.trim_end()))
+        .collect();
+    list(join(lines, || vec![Doc::LiteralLine]))
+}
+
+impl Doc {
+    fn has_hard_line(&self) -> bool {
+        match self {
+            Doc::HardLine | Doc::LiteralLine => true,
+            Doc::Group { broken, .. } => *broken,
+            Doc::Indent(docs) | Doc::List(docs) => docs.iter().any(Doc::has_hard_line),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+pub fn print(doc: &Doc, indent_width: usize, max_width: usize) -> String {
+    let mut output = String::new();
+    let mut column = 0;
+
+    // what is left to print, the next document being last
+    let mut stack = vec![(0, Mode::Break, doc)];
+
+    while let Some((indentation, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) => {
+                output.push_str(text);
+                column += text.chars().count();
+            }
+            Doc::Line if mode == Mode::Flat => {
+                output.push(' ');
+                column += 1;
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                new_line(&mut output);
+                output.push_str(&" ".repeat(indentation));
+                column = indentation;
+            }
+            Doc::LiteralLine => {
+                new_line(&mut output);
+                column = 0;
+            }
+            Doc::IfBroken(text) => {
+                if mode == Mode::Break {
+                    output.push_str(text);
+                    column += text.chars().count();
+                }
+            }
+            Doc::Indent(docs) => {
+                let indentation = indentation + indent_width;
+                stack.extend(docs.iter().rev().map(|doc| (indentation, mode, doc)));
+            }
+            Doc::List(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (indentation, mode, doc)));
+            }
+            Doc::Group { docs, broken } => {
+                let flat = mode == Mode::Flat
+                    || (!broken && fits(docs, &stack, max_width.saturating_sub(column)));
+                let mode = if flat { Mode::Flat } else { Mode::Break };
+
+                stack.extend(docs.iter().rev().map(|doc| (indentation, mode, doc)));
+            }
+        }
+    }
+
+    new_line(&mut output);
+    output
+}
+
+/// ends the line, without the spaces that would trail it
+fn new_line(output: &mut String) {
+    output.truncate(output.trim_end_matches(' ').len());
+    output.push('\n');
+}
+
+/// whether the group fits on one line along with what follows it up to the next line
+/// that may be broken
+fn fits(docs: &[Doc], rest: &[(usize, Mode, &Doc)], width: usize) -> bool {
+    let mut width = width as isize;
+    let mut pending: Vec<(Mode, &Doc)> = docs.iter().rev().map(|doc| (Mode::Flat, doc)).collect();
+    let mut rest = rest.iter().rev();
+
+    loop {
+        let Some((mode, doc)) = pending
+            .pop()
+            .or_else(|| rest.next().map(|&(_, mode, doc)| (mode, doc)))
+        else {
+            return true;
+        };
+
+        match doc {
+            Doc::Text(text) => width -= text.chars().count() as isize,
+            Doc::Line if mode == Mode::Flat => width -= 1,
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine | Doc::LiteralLine => return true,
+            Doc::IfBroken(text) => {
+                if mode == Mode::Break {
+                    width -= text.chars().count() as isize;
+                }
+            }
+            Doc::Indent(docs) | Doc::List(docs) => {
+                pending.extend(docs.iter().rev().map(|doc| (mode, doc)));
+            }
+            Doc::Group { docs, broken } => {
+                let mode = if *broken { Mode::Break } else { Mode::Flat };
+                pending.extend(docs.iter().rev().map(|doc| (mode, doc)));
+            }
+        }
+
+        if width < 0 {
+            return false;
+        }
+    }
+}
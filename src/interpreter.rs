@@ -0,0 +1,739 @@
+// tree-walking interpreter executing the AST produced by the parser
+
+pub mod collections;
+pub mod environment;
+pub mod errors;
+pub mod event_loop;
+pub mod generator;
+pub mod iteration;
+pub mod operations;
+pub mod sendable;
+pub mod value;
+
+use std::{
+    io::{self, BufWriter, Stdout, Write},
+    rc::Rc,
+    sync::Arc,
+};
+
+use self::{
+    collections::Key,
+    environment::{Env, Environment},
+    errors::{RuntimeError, RuntimeResult},
+    event_loop::{EventLoop, Task},
+    generator::{contains_yield, Generator},
+    iteration::{collect_values, ValueIter},
+    operations::{assignment_operation, binary_operation},
+    value::{Function, Method, Range, Value},
+};
+use crate::{
+    comfy,
+    parser::{
+        assignment::initial::VariableKeyword,
+        ast::{
+            identifier::Identifier,
+            import::{ImportSource, ImportSpecifier},
+            literal_value::LiteralValue,
+            range::RangeType,
+            ASTNode, Expression, Statement,
+        },
+        operations::{assignment::AssignmentOperator, binary::BinaryOperator},
+    },
+};
+
+pub struct Interpreter {
+    env: Env,
+    // buffered so that scripts writing many lines stay fast in pipelines,
+    // flushed before reading stdin, writing to stderr and when dropped
+    stdout: BufWriter<Stdout>,
+    event_loop: EventLoop,
+}
+
+// how the execution of a statement ended
+pub enum Flow {
+    Normal,
+    Return(Value),
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self {
+            env: Self::global_env(),
+            stdout: BufWriter::new(io::stdout()),
+            event_loop: EventLoop::new(),
+        }
+    }
+
+    /// a global scope with only the native globals declared
+    pub fn global_env() -> Env {
+        let globals = Environment::new_global();
+
+        for function in comfy::GLOBALS {
+            globals
+                .borrow_mut()
+                .declare(function.name, Value::Native(function), false);
+        }
+
+        globals
+    }
+
+    pub fn write_stdout(&mut self, text: &str) -> RuntimeResult<()> {
+        self.stdout
+            .write_all(text.as_bytes())
+            .map_err(|e| RuntimeError::io(&e, "<stdout>"))
+    }
+
+    pub fn flush_stdout(&mut self) -> RuntimeResult<()> {
+        self.stdout
+            .flush()
+            .map_err(|e| RuntimeError::io(&e, "<stdout>"))
+    }
+
+    pub fn run(&mut self, program: &[Statement]) -> RuntimeResult<()> {
+        for statement in program {
+            if let Flow::Return(_) = self.execute_statement(statement)? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// executes a statement of a program or a block, errors recording where it was written
+    pub fn execute_statement(&mut self, statement: &Statement) -> RuntimeResult<Flow> {
+        self.execute(&statement.node)
+            .map_err(|err| err.at(&statement.span))
+    }
+
+    fn execute(&mut self, node: &ASTNode) -> RuntimeResult<Flow> {
+        match node {
+            ASTNode::Program { body } => {
+                self.run(body)?;
+            }
+            ASTNode::ImportDeclaration { specifiers, source } => {
+                self.import(specifiers, source)?;
+            }
+            ASTNode::VariableDeclaration { declarations, kind } => {
+                for declaration in declarations {
+                    let value = self.evaluate(&declaration.init)?;
+
+                    self.env.borrow_mut().declare(
+                        &declaration.id.name,
+                        value,
+                        *kind == VariableKeyword::Var,
+                    );
+                }
+            }
+            ASTNode::ExpressionStatement { expression } => {
+                self.evaluate(expression)?;
+            }
+            ASTNode::FunctionDeclaration {
+                id,
+                params,
+                body,
+                is_async,
+                ..
+            } => {
+                let function = Function {
+                    name: Some(id.name.to_owned()),
+                    params: params.to_owned(),
+                    body: Arc::clone(body),
+                    closure: Rc::clone(&self.env),
+                    is_generator: contains_yield(body) && !is_async,
+                    is_async: *is_async,
+                };
+
+                self.env
+                    .borrow_mut()
+                    .declare(&id.name, Value::Function(Rc::new(function)), false);
+            }
+            ASTNode::ForStatement {
+                declarations,
+                kind,
+                source,
+                body,
+            } => {
+                let source = self.evaluate(source)?;
+                let mut iter = ValueIter::new(&source)?;
+                let binds_key = iter.binds_key();
+                let mutable = *kind == VariableKeyword::Var;
+
+                while let Some(entry) = iter.next_entry(self)? {
+                    let env = loop_env(&self.env, declarations, mutable, binds_key, entry);
+
+                    if let Flow::Return(value) = self.execute_in(body, env)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            ASTNode::WhileStatement { test, body } => {
+                while self.evaluate(test)?.is_truthy() {
+                    if let Flow::Return(value) = self.execute(body)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            ASTNode::IfStatement {
+                test,
+                body,
+                alternate,
+            } => {
+                if self.evaluate(test)?.is_truthy() {
+                    return self.execute(body);
+                } else if let Some(alternate) = alternate {
+                    return self.execute(alternate);
+                }
+            }
+            ASTNode::BlockStatement { .. } => {
+                let env = Environment::new_child(&self.env);
+
+                return self.execute_in(node, env);
+            }
+            ASTNode::TryStatement {
+                block,
+                param,
+                handler,
+            } => {
+                let env = Environment::new_child(&self.env);
+
+                match self.execute_in(block, env) {
+                    Ok(flow) => return Ok(flow),
+                    Err(err) if err.exit_code.is_some() => return Err(err),
+                    Err(err) => {
+                        let env = Environment::new_child(&self.env);
+                        if let Some(param) = param {
+                            env.borrow_mut().declare(&param.name, err.to_value(), false);
+                        }
+
+                        return self.execute_in(handler, env);
+                    }
+                }
+            }
+            ASTNode::ReturnStatement { argument, .. } => {
+                return Ok(Flow::Return(self.evaluate(argument)?));
+            }
+            // in functions, statements containing `yield` are run by their generator
+            ASTNode::YieldStatement { .. } => return Err(RuntimeError::yield_outside_generator()),
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    /// executes a block statement in the given environment
+    fn execute_in(&mut self, block: &ASTNode, env: Env) -> RuntimeResult<Flow> {
+        let body = match block {
+            ASTNode::BlockStatement { body } => body,
+            node => return self.with_env(env, |interpreter| interpreter.execute(node)),
+        };
+
+        self.with_env(env, |interpreter| {
+            for statement in body {
+                if let Flow::Return(value) = interpreter.execute_statement(statement)? {
+                    return Ok(Flow::Return(value));
+                }
+            }
+
+            Ok(Flow::Normal)
+        })
+    }
+
+    fn with_env<T>(&mut self, env: Env, f: impl FnOnce(&mut Self) -> T) -> T {
+        let previous = std::mem::replace(&mut self.env, env);
+        let result = f(self);
+        self.env = previous;
+
+        result
+    }
+
+    fn import(
+        &mut self,
+        specifiers: &[ImportSpecifier],
+        source: &ImportSource,
+    ) -> RuntimeResult<()> {
+        let module = comfy::get_module(&source.value)
+            .ok_or_else(|| RuntimeError::module_not_found(&source.value))?;
+
+        for specifier in specifiers {
+            if specifier.imported.name == "*" {
+                if specifier.local.name == "*" {
+                    // `import * from "module"` binds every member
+                    for (name, value) in module.members() {
+                        self.env.borrow_mut().declare(&name, value, false);
+                    }
+                } else {
+                    let value = module.to_value();
+                    self.env
+                        .borrow_mut()
+                        .declare(&specifier.local.name, value, false);
+                }
+
+                continue;
+            }
+
+            let value = module.member(&specifier.imported.name).ok_or_else(|| {
+                RuntimeError::missing_export(module.name, &specifier.imported.name)
+            })?;
+
+            self.env
+                .borrow_mut()
+                .declare(&specifier.local.name, value, false);
+        }
+
+        Ok(())
+    }
+
+    pub fn evaluate(&mut self, expr: &Expression) -> RuntimeResult<Value> {
+        let value = match expr {
+            Expression::Literal { value, .. } => match value {
+                LiteralValue::Number(n) => Value::Number(*n),
+                LiteralValue::Str(s) => Value::str(s),
+                LiteralValue::Boolean(b) => Value::Bool(*b),
+                LiteralValue::Nil => Value::Nil,
+                LiteralValue::Regex { pattern, flags } => comfy::regex::literal(pattern, flags)?,
+            },
+            Expression::TemplateLiteral { value, .. } => Value::str(value),
+            Expression::Range {
+                from,
+                limits,
+                to,
+                step,
+            } => {
+                // an omitted end evaluates to nil
+                let from = match from {
+                    Some(from) => self.evaluate(from)?,
+                    None => Value::Nil,
+                };
+                let to = match to {
+                    Some(to) => self.evaluate(to)?,
+                    None => Value::Nil,
+                };
+
+                let bound = |value: &Value| match value {
+                    Value::Nil => Some(None),
+                    Value::Number(n) => Some(Some(*n)),
+                    _ => None,
+                };
+
+                let range = match (bound(&from), bound(&to)) {
+                    (Some(start), Some(end)) => Range {
+                        from: start,
+                        to: end,
+                        inclusive: matches!(limits, RangeType::DotEqual),
+                        step: None,
+                    },
+                    _ => {
+                        return Err(RuntimeError::invalid_operation(
+                            &limits.to_string(),
+                            from.kind(),
+                            to.kind(),
+                        ))
+                    }
+                };
+
+                match step {
+                    Some(step) => {
+                        let step = self.evaluate(step)?;
+                        Value::Range(range_step(range, &step)?)
+                    }
+                    None => Value::Range(range),
+                }
+            }
+            Expression::Array { elements } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.evaluate(element))
+                    .collect::<RuntimeResult<Vec<_>>>()?;
+
+                Value::array(elements)
+            }
+            Expression::Object { properties } => {
+                let mut object = hashbrown::HashMap::with_capacity(properties.len());
+
+                for property in properties {
+                    let value = self.evaluate(&property.value)?;
+                    object.insert(property.key.name.to_owned(), value);
+                }
+
+                Value::object(object)
+            }
+            Expression::BinaryExpression {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.evaluate(left)?;
+
+                match operator {
+                    BinaryOperator::And if !left.is_truthy() => left,
+                    BinaryOperator::Or if left.is_truthy() => left,
+                    BinaryOperator::And | BinaryOperator::Or => self.evaluate(right)?,
+                    operator => {
+                        let right = self.evaluate(right)?;
+                        binary_operation(&left, *operator, &right)?
+                    }
+                }
+            }
+            Expression::MemberExpression {
+                indexed,
+                property,
+                computed,
+            } => {
+                let indexed = self.evaluate(indexed)?;
+
+                if *computed {
+                    let property = self.evaluate(property)?;
+                    self.get_index(&indexed, &property)?
+                } else {
+                    let name = property_name(property);
+                    self.get_member(&indexed, name)?
+                }
+            }
+            Expression::CallExpression { callee, args } => {
+                let callee = self.evaluate(callee)?;
+                let args = args
+                    .iter()
+                    .map(|arg| self.evaluate(arg))
+                    .collect::<RuntimeResult<Vec<_>>>()?;
+
+                self.call(&callee, args)?
+            }
+            Expression::AssignmentExpression {
+                operator,
+                id,
+                assigned,
+            } => {
+                let assigned = self.evaluate(assigned)?;
+
+                self.assign(id, *operator, assigned)?
+            }
+            Expression::IdentifierExpression(identifier) => {
+                self.env.borrow().get(&identifier.name)?
+            }
+            Expression::Parenthesized(expr) => self.evaluate(expr)?,
+            Expression::Comment { .. } => Value::Nil,
+            Expression::FnExpression {
+                params,
+                body,
+                is_async,
+                ..
+            } => Value::Function(Rc::new(Function {
+                name: None,
+                params: params.to_owned(),
+                body: Arc::clone(body),
+                closure: Rc::clone(&self.env),
+                is_generator: contains_yield(body) && !is_async,
+                is_async: *is_async,
+            })),
+            // an `await` the body cannot suspend at waits for the promise in place
+            Expression::AwaitExpression { argument } => {
+                let value = self.evaluate(argument)?;
+                self.settle(value)?
+            }
+        };
+
+        Ok(value)
+    }
+
+    pub fn call(&mut self, callee: &Value, args: Vec<Value>) -> RuntimeResult<Value> {
+        match callee {
+            Value::Function(function) => {
+                let env = Environment::new_child(&function.closure);
+
+                // missing arguments are nil and extra ones are ignored
+                let mut args = args.into_iter();
+                for param in &function.params {
+                    let value = args.next().unwrap_or(Value::Nil);
+                    env.borrow_mut().declare(&param.name, value, false);
+                }
+
+                if function.is_async {
+                    let generator = Generator::new(&function.body, env, true);
+                    return self.start_task(Task::new(generator));
+                }
+
+                if function.is_generator {
+                    let mut generator = Generator::new(&function.body, env, false);
+                    return Ok(Value::iterator_from_fn(move |interpreter| {
+                        interpreter.resume(&mut generator)
+                    }));
+                }
+
+                match self.execute_in(&function.body, env)? {
+                    Flow::Return(value) => Ok(value),
+                    Flow::Normal => Ok(Value::Nil),
+                }
+            }
+            Value::Native(function) => {
+                function.check_args(&args)?;
+
+                (function.func)(self, args)
+            }
+            Value::Method(method) => {
+                method.function.check_args(&args)?;
+
+                let mut receiver_and_args = vec![method.receiver.clone()];
+                receiver_and_args.extend(args);
+
+                (method.function.func)(self, receiver_and_args)
+            }
+            value => Err(RuntimeError::not_callable(value.kind())),
+        }
+    }
+
+    fn get_member(&mut self, value: &Value, name: &str) -> RuntimeResult<Value> {
+        match value {
+            Value::Object(object) => Ok(object.borrow().get(name).cloned().unwrap_or(Value::Nil)),
+            Value::Duration(ms) => {
+                let unit = match name {
+                    "milliseconds" => 1.0,
+                    "seconds" => 1000.0,
+                    "minutes" => 60_000.0,
+                    "hours" => 3_600_000.0,
+                    "days" => 86_400_000.0,
+                    _ => return Err(RuntimeError::no_member(value.kind(), name)),
+                };
+
+                Ok(Value::Number(ms / unit))
+            }
+            value => match comfy::method(value, name) {
+                Some(function) => Ok(Value::Method(Rc::new(Method {
+                    receiver: value.clone(),
+                    function,
+                }))),
+                None => Err(RuntimeError::no_member(value.kind(), name)),
+            },
+        }
+    }
+
+    fn get_index(&mut self, value: &Value, index: &Value) -> RuntimeResult<Value> {
+        match (value, index) {
+            (Value::Array(array), Value::Number(n)) => {
+                let array = array.borrow();
+                let i = array_index(*n, array.len())?;
+
+                Ok(array[i].clone())
+            }
+            (Value::Array(array), Value::Range(range)) => {
+                let array = array.borrow();
+                let (start, end) = slice_bounds(range, array.len())?;
+
+                Ok(Value::array(array[start..end].to_vec()))
+            }
+            (Value::Bytes(bytes), Value::Number(n)) => {
+                let i = array_index(*n, bytes.len())?;
+
+                Ok(Value::Number(bytes[i] as f64))
+            }
+            (Value::Bytes(bytes), Value::Range(range)) => {
+                let (start, end) = slice_bounds(range, bytes.len())?;
+
+                Ok(Value::Bytes(bytes[start..end].into()))
+            }
+            // strings are indexed by characters, not bytes
+            (Value::Str(s), Value::Number(n)) => {
+                let i = array_index(*n, s.chars().count())?;
+                let c = s.chars().nth(i).unwrap_or_default();
+
+                Ok(Value::str(c.encode_utf8(&mut [0; 4])))
+            }
+            (Value::Str(s), Value::Range(range)) => {
+                let (start, end) = slice_bounds(range, s.chars().count())?;
+                let slice = s.chars().skip(start).take(end - start).collect::<String>();
+
+                Ok(Value::str(&slice))
+            }
+            (Value::Object(object), Value::Str(key)) => {
+                Ok(object.borrow().get(&**key).cloned().unwrap_or(Value::Nil))
+            }
+            (Value::Map(map), key) => {
+                let key = Key::new(key)?;
+                Ok(map.borrow().get(&key).cloned().unwrap_or(Value::Nil))
+            }
+            (value, index) => Err(RuntimeError::invalid_index(value.kind(), index.kind())),
+        }
+    }
+
+    fn assign(
+        &mut self,
+        target: &Expression,
+        operator: AssignmentOperator,
+        assigned: Value,
+    ) -> RuntimeResult<Value> {
+        match target {
+            Expression::IdentifierExpression(identifier) => {
+                let value = if operator == AssignmentOperator::Equal {
+                    assigned
+                } else {
+                    let current = self.env.borrow().get(&identifier.name)?;
+                    assignment_operation(&current, operator, assigned)?
+                };
+
+                self.env
+                    .borrow_mut()
+                    .assign(&identifier.name, value.clone())?;
+
+                Ok(value)
+            }
+            Expression::MemberExpression {
+                indexed,
+                property,
+                computed,
+            } => {
+                let indexed = self.evaluate(indexed)?;
+                let key = if *computed {
+                    self.evaluate(property)?
+                } else {
+                    Value::str(property_name(property))
+                };
+
+                let current = if operator == AssignmentOperator::Equal {
+                    Value::Nil
+                } else {
+                    self.get_index(&indexed, &key)?
+                };
+                let value = assignment_operation(&current, operator, assigned)?;
+
+                match (&indexed, &key) {
+                    (Value::Array(array), Value::Number(n)) => {
+                        let mut array = array.borrow_mut();
+                        let i = array_index(*n, array.len())?;
+                        array[i] = value.clone();
+                    }
+                    (Value::Array(array), Value::Range(range)) => {
+                        let values = collect_values(self, &value)?;
+
+                        let mut array = array.borrow_mut();
+                        let (start, end) = slice_bounds(range, array.len())?;
+                        array.splice(start..end, values);
+                    }
+                    (Value::Object(object), Value::Str(key)) => {
+                        object.borrow_mut().insert(key.to_string(), value.clone());
+                    }
+                    (Value::Map(map), key) if *computed => {
+                        map.borrow_mut().insert(Key::new(key)?, value.clone());
+                    }
+                    (indexed, key) => {
+                        return Err(RuntimeError::invalid_index(indexed.kind(), key.kind()))
+                    }
+                }
+
+                Ok(value)
+            }
+            _ => Err(RuntimeError::invalid_assignment()),
+        }
+    }
+}
+
+fn property_name(property: &Expression) -> &str {
+    match property {
+        Expression::IdentifierExpression(identifier) => &identifier.name,
+        // the parser only accepts identifiers after a dot
+        _ => unreachable!(),
+    }
+}
+
+/// the scope of one iteration of a `for ... in` loop, with its loop variables declared
+fn loop_env(
+    parent: &Env,
+    declarations: &[Identifier],
+    mutable: bool,
+    binds_key: bool,
+    (key, value): (Value, Value),
+) -> Env {
+    let env = Environment::new_child(parent);
+
+    match declarations {
+        [item] => {
+            let item_value = if binds_key { key } else { value };
+            env.borrow_mut().declare(&item.name, item_value, mutable);
+        }
+        [key_id, value_id, ..] => {
+            env.borrow_mut().declare(&key_id.name, key, mutable);
+            env.borrow_mut().declare(&value_id.name, value, mutable);
+        }
+        [] => unreachable!(),
+    }
+
+    env
+}
+
+/// the position of an element, negative indices counting from the end
+pub fn array_index(index: f64, len: usize) -> RuntimeResult<usize> {
+    let position = if index < 0.0 {
+        index + len as f64
+    } else {
+        index
+    };
+
+    if position < 0.0 || position.fract() != 0.0 || position >= len as f64 {
+        return Err(RuntimeError::index_out_of_bounds(index, len));
+    }
+
+    Ok(position as usize)
+}
+
+/// the range with a step, which must be a positive number, the direction coming from the bounds
+fn range_step(range: Range, step: &Value) -> RuntimeResult<Range> {
+    let error = |reason: String| {
+        let written = format!("{range} step {step}");
+        Err(RuntimeError::invalid_range(&written, reason))
+    };
+
+    let step = match step {
+        Value::Number(n) if *n > 0.0 && n.is_finite() => *n,
+        Value::Number(n) if *n < 0.0 => {
+            let example = format!("10..0 step {}", value::format_number(-n));
+            return error(format!(
+                "the step must be positive, ranges ending before their start go down as in `{example}`"
+            ));
+        }
+        step => {
+            return error(format!(
+                "the step must be a positive number, found `{step}`"
+            ))
+        }
+    };
+
+    if range.from.is_none() {
+        return error("a range with a step needs a start".to_owned());
+    }
+
+    Ok(Range {
+        step: Some(step),
+        ..range
+    })
+}
+
+/// the start and end positions of a slice, negative bounds counting from the end
+fn slice_bounds(range: &Range, len: usize) -> RuntimeResult<(usize, usize)> {
+    let error = |reason: String| RuntimeError::invalid_slice(&range.to_string(), reason);
+    if range.step.is_some() {
+        return Err(error("slices cannot have a step".to_owned()));
+    }
+    let position = |bound: f64| {
+        if bound.fract() != 0.0 {
+            return Err(error(format!("`{bound}` is not an integer")));
+        }
+
+        Ok(if bound < 0.0 {
+            bound + len as f64
+        } else {
+            bound
+        })
+    };
+
+    let start = range.from.map_or(Ok(0.0), position)?;
+    let mut end = range.to.map_or(Ok(len as f64), position)?;
+    if range.inclusive {
+        end += 1.0;
+    }
+
+    if start < 0.0 || end > len as f64 {
+        return Err(error(format!("the length is {len}")));
+    }
+    if start > end {
+        return Err(error("the range starts after its end".to_owned()));
+    }
+
+    Ok((start as usize, end as usize))
+}
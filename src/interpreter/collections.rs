@@ -0,0 +1,240 @@
+// the native collection types, hashed collections being keyed by immutable values
+
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+    fmt,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use hashbrown::{HashMap, HashSet};
+
+use super::{
+    errors::{RuntimeError, RuntimeResult},
+    value::{format_number, Value},
+};
+
+pub type Set = Rc<RefCell<HashSet<Key>>>;
+pub type Map = Rc<RefCell<HashMap<Key, Value>>>;
+pub type Deque = Rc<RefCell<VecDeque<Value>>>;
+pub type Stack = Rc<RefCell<Vec<Value>>>;
+pub type PriorityQueue = Rc<RefCell<Heap>>;
+
+/// a value usable as an element of a set or a key of a map,
+/// mutable values such as arrays being rejected
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Key {
+    Nil,
+    Bool(bool),
+    Number(Number),
+    Str(Rc<str>),
+    Bytes(Rc<[u8]>),
+    Duration(Number),
+}
+
+/// a totally ordered number, `-0` being the same key as `0`
+#[derive(Debug, Clone, Copy)]
+pub struct Number(f64);
+
+/// a min-heap on priorities, values of equal priority coming out in insertion order
+#[derive(Debug, Default)]
+pub struct Heap {
+    entries: BinaryHeap<HeapEntry>,
+    pushed: u64,
+}
+
+#[derive(Debug)]
+struct HeapEntry {
+    priority: f64,
+    order: u64,
+    value: Value,
+}
+
+impl Key {
+    pub fn new(value: &Value) -> RuntimeResult<Self> {
+        let key = match value {
+            Value::Nil => Key::Nil,
+            Value::Bool(b) => Key::Bool(*b),
+            Value::Number(n) => Key::Number(Number::new(*n)),
+            Value::Str(s) => Key::Str(Rc::clone(s)),
+            Value::Bytes(bytes) => Key::Bytes(Rc::clone(bytes)),
+            Value::Duration(ms) => Key::Duration(Number::new(*ms)),
+            value => return Err(RuntimeError::unhashable(value.kind())),
+        };
+
+        Ok(key)
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Key::Nil => Value::Nil,
+            Key::Bool(b) => Value::Bool(*b),
+            Key::Number(n) => Value::Number(n.0),
+            Key::Str(s) => Value::Str(Rc::clone(s)),
+            Key::Bytes(bytes) => Value::Bytes(Rc::clone(bytes)),
+            Key::Duration(ms) => Value::Duration(ms.0),
+        }
+    }
+}
+
+impl Number {
+    fn new(n: f64) -> Self {
+        Number(if n == 0.0 { 0.0 } else { n })
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Number {}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl Heap {
+    pub fn push(&mut self, value: Value, priority: f64) {
+        self.entries.push(HeapEntry {
+            priority,
+            order: self.pushed,
+            value,
+        });
+        self.pushed += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<Value> {
+        self.entries.pop().map(|entry| entry.value)
+    }
+
+    pub fn peek(&self) -> Option<&Value> {
+        self.entries.peek().map(|entry| &entry.value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// the (priority, value) pairs in the order they would be popped
+    pub fn sorted(&self) -> Vec<(f64, Value)> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.cmp(a));
+
+        entries
+            .into_iter()
+            .map(|entry| (entry.priority, entry.value.clone()))
+            .collect()
+    }
+}
+
+impl PartialEq for Heap {
+    fn eq(&self, other: &Self) -> bool {
+        self.sorted() == other.sorted()
+    }
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    // reversed, the std heap being a max-heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then(other.order.cmp(&self.order))
+    }
+}
+
+/// the keys of a set or a map in a deterministic order
+pub fn sorted_keys<'a>(keys: impl Iterator<Item = &'a Key>) -> Vec<&'a Key> {
+    let mut keys = keys.collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+pub fn fmt_set(f: &mut fmt::Formatter<'_>, set: &Set) -> fmt::Result {
+    let set = set.borrow();
+    let elements = sorted_keys(set.iter())
+        .into_iter()
+        .map(|key| key.to_value().repr());
+
+    fmt_elements(f, "Set {", elements, "}")
+}
+
+pub fn fmt_map(f: &mut fmt::Formatter<'_>, map: &Map) -> fmt::Result {
+    let map = map.borrow();
+    let elements = sorted_keys(map.keys())
+        .into_iter()
+        .map(|key| format!("{}: {}", key.to_value().repr(), map[key].repr()));
+
+    fmt_elements(f, "Map {", elements, "}")
+}
+
+pub fn fmt_sequence<'a>(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    values: impl Iterator<Item = &'a Value>,
+) -> fmt::Result {
+    let elements = values.map(Value::repr);
+    fmt_elements(f, &format!("{name} ["), elements, "]")
+}
+
+pub fn fmt_heap(f: &mut fmt::Formatter<'_>, heap: &PriorityQueue) -> fmt::Result {
+    let elements = heap
+        .borrow()
+        .sorted()
+        .into_iter()
+        .map(|(priority, value)| format!("{}: {}", format_number(priority), value.repr()));
+
+    fmt_elements(f, "PriorityQueue {", elements, "}")
+}
+
+fn fmt_elements(
+    f: &mut fmt::Formatter<'_>,
+    open: &str,
+    elements: impl Iterator<Item = String>,
+    close: &str,
+) -> fmt::Result {
+    write!(f, "{open}")?;
+    for (i, element) in elements.enumerate() {
+        if i != 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{element}")?;
+    }
+    write!(f, "{close}")
+}
@@ -0,0 +1,72 @@
+use std::{cell::RefCell, rc::Rc};
+
+use hashbrown::HashMap;
+
+use super::{
+    errors::{RuntimeError, RuntimeResult},
+    value::Value,
+};
+
+pub type Env = Rc<RefCell<Environment>>;
+
+#[derive(Debug, Default)]
+pub struct Environment {
+    variables: HashMap<String, Variable>,
+    parent: Option<Env>,
+}
+
+#[derive(Debug)]
+struct Variable {
+    value: Value,
+    mutable: bool,
+}
+
+impl Environment {
+    pub fn new_global() -> Env {
+        Rc::new(RefCell::new(Environment::default()))
+    }
+    pub fn new_child(parent: &Env) -> Env {
+        Rc::new(RefCell::new(Environment {
+            variables: HashMap::new(),
+            parent: Some(Rc::clone(parent)),
+        }))
+    }
+
+    /// declares a variable, shadowing any previous one with the same name
+    pub fn declare(&mut self, name: &str, value: Value, mutable: bool) {
+        self.variables
+            .insert(name.to_owned(), Variable { value, mutable });
+    }
+
+    pub fn get(&self, name: &str) -> RuntimeResult<Value> {
+        match self.variables.get(name) {
+            Some(variable) => Ok(variable.value.clone()),
+            None => match &self.parent {
+                Some(parent) => parent.borrow().get(name),
+                None => Err(RuntimeError::undefined_variable(name)),
+            },
+        }
+    }
+
+    /// the value of a variable and whether it is mutable, if it is declared
+    pub fn lookup(&self, name: &str) -> Option<(Value, bool)> {
+        match self.variables.get(name) {
+            Some(variable) => Some((variable.value.clone(), variable.mutable)),
+            None => self.parent.as_ref()?.borrow().lookup(name),
+        }
+    }
+
+    pub fn assign(&mut self, name: &str, value: Value) -> RuntimeResult<()> {
+        match self.variables.get_mut(name) {
+            Some(variable) if variable.mutable => {
+                variable.value = value;
+                Ok(())
+            }
+            Some(_) => Err(RuntimeError::constant_assignment(name)),
+            None => match &self.parent {
+                Some(parent) => parent.borrow_mut().assign(name, value),
+                None => Err(RuntimeError::undefined_variable(name)),
+            },
+        }
+    }
+}
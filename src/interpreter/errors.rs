@@ -0,0 +1,333 @@
+use codespan_reporting::diagnostic::Diagnostic;
+use hashbrown::HashMap;
+
+use crate::parser::{ast::Span, errors::ErrorCode};
+
+use super::value::{Value, ValueKind};
+
+pub type RuntimeResult<T> = Result<T, RuntimeError>;
+
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    /// the error message
+    pub message: String,
+    /// the error status code
+    pub code: ErrorCode,
+    /// additional notes
+    pub notes: Vec<String>,
+    /// additional properties of the error object bound by `catch`
+    pub fields: Vec<(&'static str, ErrorField)>,
+    /// set when the script ends through `env.exit`, such errors are never caught
+    pub exit_code: Option<i32>,
+    /// where the innermost statement raising the error was written
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ErrorField {
+    Number(f64),
+    Str(String),
+}
+
+impl RuntimeError {
+    pub fn new(message: String, code: usize, notes: Vec<String>) -> Self {
+        RuntimeError {
+            message,
+            code: code.into(),
+            notes,
+            fields: Vec::new(),
+            exit_code: None,
+            span: None,
+        }
+    }
+    /// records the statement the error was raised from, unless a nested one already was
+    pub fn at(mut self, span: &Span) -> Self {
+        self.span.get_or_insert_with(|| span.clone());
+        self
+    }
+    pub fn with_field(mut self, name: &'static str, field: ErrorField) -> Self {
+        self.fields.push((name, field));
+        self
+    }
+
+    /// the error object bound by `catch`
+    pub fn to_value(&self) -> Value {
+        let mut object = HashMap::new();
+        object.insert("message".to_owned(), Value::str(&self.message));
+        object.insert("code".to_owned(), Value::str(&self.code.to_string()));
+
+        for (name, field) in &self.fields {
+            let value = match field {
+                ErrorField::Number(n) => Value::Number(*n),
+                ErrorField::Str(s) => Value::str(s),
+            };
+            object.insert(name.to_string(), value);
+        }
+
+        Value::object(object)
+    }
+    pub fn generate_diagnostic<FileId>(self) -> Diagnostic<FileId> {
+        Diagnostic::error()
+            .with_message(self.message)
+            .with_code(self.code.to_string())
+            .with_notes(self.notes)
+    }
+
+    pub fn undefined_variable(name: &str) -> Self {
+        RuntimeError::new(
+            format!("cannot find value `{name}` in this scope"),
+            100,
+            vec![format!(
+                "`{name}` must be declared with `let` or `var` before being used"
+            )],
+        )
+    }
+    pub fn constant_assignment(name: &str) -> Self {
+        RuntimeError::new(
+            format!("cannot assign twice to immutable variable `{name}`"),
+            101,
+            vec![format!("declare `{name}` with `var` to make it mutable")],
+        )
+    }
+    pub fn invalid_assignment() -> Self {
+        RuntimeError::new(
+            "invalid left-hand side of assignment".to_owned(),
+            102,
+            Vec::new(),
+        )
+    }
+    pub fn not_callable(kind: ValueKind) -> Self {
+        RuntimeError::new(
+            format!("a value of type `{kind}` is not callable"),
+            103,
+            vec!["only functions can be called".to_owned()],
+        )
+    }
+    pub fn arity(function: &str, min: usize, max: Option<usize>, found: usize) -> Self {
+        let expected = match max {
+            Some(max) if max == min => format!("{min}"),
+            Some(max) => format!("{min} to {max}"),
+            None => format!("at least {min}"),
+        };
+
+        RuntimeError::new(
+            format!("wrong number of arguments passed to `{function}`"),
+            104,
+            vec![format!(
+                "expected {expected} argument(s)
+    found {found}"
+            )],
+        )
+    }
+    pub fn argument_type(
+        function: &str,
+        param: &str,
+        expected: ValueKind,
+        found: ValueKind,
+    ) -> Self {
+        RuntimeError::new(
+            format!("mismatched type for argument `{param}` of `{function}`"),
+            105,
+            vec![format!(
+                "expected `{expected}`
+    found `{found}`"
+            )],
+        )
+    }
+    pub fn invalid_operation(operator: &str, left: ValueKind, right: ValueKind) -> Self {
+        RuntimeError::new(
+            format!("cannot apply `{operator}` to `{left}` and `{right}`"),
+            106,
+            Vec::new(),
+        )
+    }
+    pub fn division_by_zero() -> Self {
+        RuntimeError::new("attempt to divide by zero".to_owned(), 107, Vec::new())
+    }
+    pub fn no_member(kind: ValueKind, member: &str) -> Self {
+        RuntimeError::new(
+            format!("no member `{member}` on a value of type `{kind}`"),
+            108,
+            Vec::new(),
+        )
+    }
+    pub fn invalid_index(kind: ValueKind, index: ValueKind) -> Self {
+        RuntimeError::new(
+            format!("cannot index a value of type `{kind}` with `{index}`"),
+            109,
+            Vec::new(),
+        )
+    }
+    pub fn index_out_of_bounds(index: f64, len: usize) -> Self {
+        RuntimeError::new(
+            "index out of bounds".to_owned(),
+            110,
+            vec![format!("the length is {len} but the index is {index}")],
+        )
+    }
+    pub fn not_iterable(kind: ValueKind) -> Self {
+        RuntimeError::new(
+            format!("a value of type `{kind}` is not iterable"),
+            111,
+            Vec::new(),
+        )
+    }
+    pub fn module_not_found(name: &str) -> Self {
+        RuntimeError::new(
+            format!("cannot find module `{name}`"),
+            112,
+            vec!["run `comfy stdlib` to list the available modules".to_owned()],
+        )
+    }
+    pub fn missing_export(module: &str, name: &str) -> Self {
+        RuntimeError::new(
+            format!("module `{module}` has no member `{name}`"),
+            113,
+            vec!["run `comfy stdlib` to list the members of every module".to_owned()],
+        )
+    }
+    pub fn assertion_failed(message: Option<String>) -> Self {
+        let message = match message {
+            Some(message) => format!("assertion failed: {message}"),
+            None => "assertion failed".to_owned(),
+        };
+
+        RuntimeError::new(message, 114, vec![])
+    }
+    pub fn json_syntax(message: &str, line: usize, column: usize) -> Self {
+        RuntimeError::new(
+            format!("invalid JSON: {message}"),
+            115,
+            vec![format!("at line {line}, column {column}")],
+        )
+        .with_field("line", ErrorField::Number(line as f64))
+        .with_field("column", ErrorField::Number(column as f64))
+    }
+    pub fn json_unrepresentable(kind: ValueKind) -> Self {
+        RuntimeError::new(
+            format!("a value of type `{kind}` cannot be represented in JSON"),
+            116,
+            Vec::new(),
+        )
+    }
+    pub fn json_cycle() -> Self {
+        RuntimeError::new(
+            "cannot convert a cyclic structure to JSON".to_owned(),
+            117,
+            vec!["an array or an object contains itself".to_owned()],
+        )
+    }
+    pub fn io(err: &std::io::Error, path: &str) -> Self {
+        RuntimeError::new(
+            err.to_string(),
+            118,
+            vec![format!("while accessing `{path}`")],
+        )
+        .with_field("kind", ErrorField::Str(format!("{:?}", err.kind())))
+        .with_field("path", ErrorField::Str(path.to_owned()))
+    }
+    pub fn invalid_glob(pattern: &str, reason: &str) -> Self {
+        RuntimeError::new(
+            format!("invalid glob pattern `{pattern}`"),
+            119,
+            vec![reason.to_owned()],
+        )
+    }
+    pub fn invalid_argument(function: &str, reason: String) -> Self {
+        RuntimeError::new(
+            format!("invalid argument passed to `{function}`"),
+            120,
+            vec![reason],
+        )
+    }
+    pub fn exit(code: i32) -> Self {
+        RuntimeError {
+            exit_code: Some(code),
+            ..RuntimeError::new(format!("exited with status {code}"), 121, vec![])
+        }
+    }
+    pub fn time_parse(text: &str, pattern: &str, reason: String) -> Self {
+        RuntimeError::new(
+            format!("cannot parse `{text}` with the pattern `{pattern}`"),
+            122,
+            vec![reason],
+        )
+    }
+    pub fn unhashable(kind: ValueKind) -> Self {
+        RuntimeError::new(
+            format!("a value of type `{kind}` cannot be a set element or a map key"),
+            123,
+            vec![
+                "only nil, booleans, numbers, strings, bytes and durations are hashable".to_owned(),
+            ],
+        )
+    }
+    pub fn not_sendable(kind: ValueKind) -> Self {
+        RuntimeError::new(
+            format!("a value of type `{kind}` cannot be sent to another thread"),
+            124,
+            vec![],
+        )
+    }
+    pub fn thread_panicked() -> Self {
+        RuntimeError::new("the thread panicked".to_owned(), 125, vec![])
+    }
+    pub fn channel_closed() -> Self {
+        RuntimeError::new(
+            "cannot send a value through a closed channel".to_owned(),
+            126,
+            vec![],
+        )
+    }
+    pub fn http(method: &str, url: &str, kind: String, reason: String) -> Self {
+        RuntimeError::new(
+            format!("the request `{method} {url}` failed"),
+            127,
+            vec![reason],
+        )
+        .with_field("kind", ErrorField::Str(kind))
+        .with_field("url", ErrorField::Str(url.to_owned()))
+    }
+    pub fn invalid_slice(range: &str, reason: String) -> Self {
+        RuntimeError::new(format!("cannot slice with `{range}`"), 128, vec![reason])
+    }
+    pub fn invalid_range(range: &str, reason: String) -> Self {
+        RuntimeError::new(format!("invalid range `{range}`"), 129, vec![reason])
+    }
+    pub fn yield_outside_generator() -> Self {
+        RuntimeError::new(
+            "`yield` can only be used in a function".to_owned(),
+            130,
+            vec![],
+        )
+    }
+    pub fn iterator_running() -> Self {
+        RuntimeError::new(
+            "the iterator is already running".to_owned(),
+            131,
+            vec!["an iterator cannot be advanced from within itself".to_owned()],
+        )
+    }
+
+    pub fn promise_never_settles() -> Self {
+        RuntimeError::new(
+            "the awaited promise never settles".to_owned(),
+            132,
+            vec!["nothing left to run could resolve it".to_owned()],
+        )
+    }
+    pub fn invalid_regex(pattern: &str, flags: &str, reason: String) -> Self {
+        RuntimeError::new(
+            format!("invalid regex `/{pattern}/{flags}`"),
+            133,
+            vec![reason],
+        )
+    }
+    pub fn mutex_updating() -> Self {
+        RuntimeError::new(
+            "the mutex is being updated".to_owned(),
+            134,
+            vec!["a mutex cannot be read or written from within its own `update`".to_owned()],
+        )
+    }
+}
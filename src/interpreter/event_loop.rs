@@ -0,0 +1,373 @@
+// the single-threaded event loop running async functions, promise callbacks and timers,
+// driven by the interpreter once the script ends or while an `await` waits; blocking work
+// such as HTTP requests runs on background threads and only its result comes back here
+
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    fmt,
+    rc::Rc,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use hashbrown::HashMap;
+
+use super::{
+    errors::{RuntimeError, RuntimeResult},
+    generator::{Generator, Suspension},
+    value::Value,
+    Interpreter,
+};
+
+/// how a promise settled, the error being raised again by each `await`
+pub type Settled = RuntimeResult<Value>;
+
+// work run on the loop's thread, a background result turning into a value there
+type Job = Box<dyn FnOnce(&mut Interpreter) -> RuntimeResult<()>>;
+type Waiter = Box<dyn FnOnce(&mut Interpreter, Settled) -> RuntimeResult<()>>;
+pub type Completion = Box<dyn FnOnce() -> Settled + Send>;
+pub type TimerCallback = Rc<dyn Fn(&mut Interpreter) -> RuntimeResult<()>>;
+
+pub struct Promise {
+    state: RefCell<PromiseState>,
+    // set once anything awaits the promise, unhandled rejections ending the script
+    handled: Cell<bool>,
+}
+
+enum PromiseState {
+    Pending(Vec<Waiter>),
+    Settled(Settled),
+}
+
+// the body of an async function, run until it awaits a promise that is still pending
+pub struct Task {
+    generator: Generator,
+    promise: Rc<Promise>,
+}
+
+struct Timer {
+    callback: TimerCallback,
+    interval: Option<Duration>,
+}
+
+pub struct EventLoop {
+    jobs: VecDeque<Job>,
+    // ordered by due time then by id, timers due together firing in creation order
+    schedule: BinaryHeap<Reverse<(Instant, u64)>>,
+    timers: HashMap<u64, Timer>,
+    next_id: u64,
+    // promises waiting for background threads, by id
+    background: HashMap<u64, Rc<Promise>>,
+    sender: Sender<(u64, Completion)>,
+    receiver: Receiver<(u64, Completion)>,
+    rejected: Vec<Rc<Promise>>,
+}
+
+impl Promise {
+    pub fn pending() -> Rc<Self> {
+        Rc::new(Promise {
+            state: RefCell::new(PromiseState::Pending(Vec::new())),
+            handled: Cell::new(false),
+        })
+    }
+
+    /// the result, if the promise settled
+    pub fn settled(&self) -> Option<Settled> {
+        match &*self.state.borrow() {
+            PromiseState::Pending(_) => None,
+            PromiseState::Settled(settled) => Some(settled.clone()),
+        }
+    }
+}
+
+impl fmt::Debug for Promise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Promise")
+    }
+}
+
+impl Task {
+    pub fn new(generator: Generator) -> Self {
+        Task {
+            generator,
+            promise: Promise::pending(),
+        }
+    }
+}
+
+impl EventLoop {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        EventLoop {
+            jobs: VecDeque::new(),
+            schedule: BinaryHeap::new(),
+            timers: HashMap::new(),
+            next_id: 0,
+            background: HashMap::new(),
+            sender,
+            receiver,
+            rejected: Vec::new(),
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.jobs.is_empty() && self.timers.is_empty() && self.background.is_empty()
+    }
+}
+
+impl Interpreter {
+    /// runs everything scheduled until nothing is left, a rejection nobody awaited
+    /// ending the script like an error
+    pub fn run_event_loop(&mut self) -> RuntimeResult<()> {
+        while self.turn()? {}
+
+        let rejected = std::mem::take(&mut self.event_loop.rejected);
+        for promise in rejected {
+            if let (false, Some(Err(err))) = (promise.handled.get(), promise.settled()) {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// waits for a promise, running other tasks and timers in the meantime
+    pub fn wait(&mut self, promise: &Rc<Promise>) -> Settled {
+        promise.handled.set(true);
+
+        loop {
+            if let Some(settled) = promise.settled() {
+                return settled;
+            }
+            if !self.turn()? {
+                return Err(RuntimeError::promise_never_settles());
+            }
+        }
+    }
+
+    /// what a promise resolves to, waiting for it, any other value being returned as is
+    pub fn settle(&mut self, value: Value) -> Settled {
+        match value {
+            Value::Promise(promise) => self.wait(&promise),
+            value => Ok(value),
+        }
+    }
+
+    /// starts an async function body, which runs until its first `await`
+    pub fn start_task(&mut self, task: Task) -> RuntimeResult<Value> {
+        let promise = Rc::clone(&task.promise);
+        self.run_task(Rc::new(RefCell::new(task)), None)?;
+
+        Ok(Value::Promise(promise))
+    }
+
+    fn run_task(&mut self, task: Rc<RefCell<Task>>, input: Option<Settled>) -> RuntimeResult<()> {
+        let suspension = self.drive(&mut task.borrow_mut().generator, input);
+        let promise = Rc::clone(&task.borrow().promise);
+
+        match suspension {
+            Ok(Suspension::Await(Value::Promise(awaited))) => {
+                self.subscribe(&awaited, move |interpreter, settled| {
+                    interpreter.run_task(task, Some(settled))
+                });
+            }
+            // awaiting any other value resumes right away, after what is already scheduled
+            Ok(Suspension::Await(value)) => {
+                self.schedule(move |interpreter| interpreter.run_task(task, Some(Ok(value))));
+            }
+            Ok(Suspension::Done(value)) => self.resolve(&promise, Ok(value)),
+            Ok(Suspension::Yield(_)) => unreachable!(),
+            // exiting ends the whole script rather than the task
+            Err(err) if err.exit_code.is_some() => return Err(err),
+            Err(err) => self.resolve(&promise, Err(err)),
+        }
+
+        Ok(())
+    }
+
+    /// settles a promise, one resolved with another promise following it
+    pub fn resolve(&mut self, promise: &Rc<Promise>, settled: Settled) {
+        if let Ok(Value::Promise(inner)) = &settled {
+            let promise = Rc::clone(promise);
+            self.subscribe(&Rc::clone(inner), move |interpreter, settled| {
+                interpreter.resolve(&promise, settled);
+                Ok(())
+            });
+            return;
+        }
+
+        let previous = promise
+            .state
+            .replace(PromiseState::Settled(settled.clone()));
+
+        let PromiseState::Pending(waiters) = previous else {
+            // settling twice keeps the first result
+            promise.state.replace(previous);
+            return;
+        };
+
+        if settled.is_err() && waiters.is_empty() {
+            self.event_loop.rejected.push(Rc::clone(promise));
+        }
+
+        for waiter in waiters {
+            let settled = settled.clone();
+            self.schedule(move |interpreter| waiter(interpreter, settled));
+        }
+    }
+
+    /// calls `waiter` once the promise settles, from the event loop
+    pub fn subscribe(
+        &mut self,
+        promise: &Rc<Promise>,
+        waiter: impl FnOnce(&mut Interpreter, Settled) -> RuntimeResult<()> + 'static,
+    ) {
+        promise.handled.set(true);
+
+        match &mut *promise.state.borrow_mut() {
+            PromiseState::Pending(waiters) => waiters.push(Box::new(waiter)),
+            PromiseState::Settled(settled) => {
+                let settled = settled.clone();
+                self.schedule(move |interpreter| waiter(interpreter, settled));
+            }
+        }
+    }
+
+    pub fn schedule(&mut self, job: impl FnOnce(&mut Interpreter) -> RuntimeResult<()> + 'static) {
+        self.event_loop.jobs.push_back(Box::new(job));
+    }
+
+    /// runs `callback` after `delay`, then every `interval` if any, returning the timer id,
+    /// or `None` when the delay ends too far away to be scheduled
+    pub fn set_timer(
+        &mut self,
+        callback: TimerCallback,
+        delay: Duration,
+        repeat: bool,
+    ) -> Option<u64> {
+        let due = Instant::now().checked_add(delay)?;
+        let id = self.event_loop.next_id;
+        self.event_loop.next_id += 1;
+
+        let timer = Timer {
+            callback,
+            interval: repeat.then_some(delay),
+        };
+        self.event_loop.timers.insert(id, timer);
+        self.event_loop.schedule.push(Reverse((due, id)));
+
+        Some(id)
+    }
+
+    /// whether the timer was still scheduled
+    pub fn clear_timer(&mut self, id: u64) -> bool {
+        self.event_loop.timers.remove(&id).is_some()
+    }
+
+    /// runs blocking work on another thread, the promise settling once it is done
+    pub fn run_in_background(
+        &mut self,
+        work: impl FnOnce() -> Completion + Send + 'static,
+    ) -> Value {
+        let id = self.event_loop.next_id;
+        self.event_loop.next_id += 1;
+
+        let promise = Promise::pending();
+        self.event_loop.background.insert(id, Rc::clone(&promise));
+
+        let sender = self.event_loop.sender.clone();
+        thread::spawn(move || {
+            // the loop only goes away with the interpreter, nobody waits for the result then
+            let _ = sender.send((id, work()));
+        });
+
+        Value::Promise(promise)
+    }
+
+    /// runs the next job, timer or background result, waiting for one if needed;
+    /// false once there is nothing left to run
+    fn turn(&mut self) -> RuntimeResult<bool> {
+        if let Some(job) = self.event_loop.jobs.pop_front() {
+            job(self)?;
+            return Ok(true);
+        }
+
+        if let Ok((id, completion)) = self.event_loop.receiver.try_recv() {
+            self.complete(id, completion);
+            return Ok(true);
+        }
+
+        // timers cleared in the meantime are dropped from the schedule
+        while let Some(Reverse((_, id))) = self.event_loop.schedule.peek() {
+            if self.event_loop.timers.contains_key(id) {
+                break;
+            }
+            self.event_loop.schedule.pop();
+        }
+
+        let next_timer = self.event_loop.schedule.peek().map(|Reverse(entry)| *entry);
+
+        if let Some((due, id)) = next_timer {
+            if due <= Instant::now() {
+                self.event_loop.schedule.pop();
+                self.fire(id, due)?;
+                return Ok(true);
+            }
+        }
+
+        if self.event_loop.is_idle() {
+            return Ok(false);
+        }
+
+        // output written so far shows up while waiting
+        self.flush_stdout()?;
+
+        let received = match next_timer {
+            Some((due, _)) => {
+                let timeout = due.saturating_duration_since(Instant::now());
+                self.event_loop.receiver.recv_timeout(timeout)
+            }
+            None => self
+                .event_loop
+                .receiver
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        if let Ok((id, completion)) = received {
+            self.complete(id, completion);
+        }
+
+        Ok(true)
+    }
+
+    fn fire(&mut self, id: u64, due: Instant) -> RuntimeResult<()> {
+        let Some(timer) = self.event_loop.timers.get(&id) else {
+            return Ok(());
+        };
+        let callback = Rc::clone(&timer.callback);
+
+        // timers that do not repeat, or whose next run is too far away, are done
+        match timer
+            .interval
+            .and_then(|interval| due.checked_add(interval))
+        {
+            Some(next) => self.event_loop.schedule.push(Reverse((next, id))),
+            None => {
+                self.event_loop.timers.remove(&id);
+            }
+        }
+
+        callback(self)
+    }
+
+    fn complete(&mut self, id: u64, completion: Completion) {
+        if let Some(promise) = self.event_loop.background.remove(&id) {
+            self.resolve(&promise, completion());
+        }
+    }
+}
@@ -0,0 +1,425 @@
+// generators, functions containing `yield` whose calls return an iterator running the body
+// up to the next `yield` each time a value is needed; statements containing a `yield` are
+// tracked on an explicit stack so that they can be left and resumed, the others being
+// executed as usual. Async function bodies are run the same way, suspending at statements
+// awaiting a promise: `await promise`, `let x = await promise`, `x = await promise` and
+// `return await promise`, an `await` anywhere else waiting in place
+
+use std::{rc::Rc, sync::Arc};
+
+use super::{
+    environment::{Env, Environment},
+    errors::RuntimeResult,
+    event_loop::Settled,
+    iteration::ValueIter,
+    value::Value,
+    Flow, Interpreter,
+};
+use crate::parser::{
+    assignment::initial::VariableKeyword,
+    ast::{identifier::Identifier, ASTNode, Expression, Span},
+    operations::assignment::AssignmentOperator,
+};
+
+pub struct Generator {
+    // the innermost statement is last, no frame being left once the generator finished
+    frames: Vec<Frame>,
+    // async bodies suspend at `await` rather than `yield`
+    is_async: bool,
+    // what the statement that suspended does with the awaited value
+    awaiting: Option<(Continuation, Env)>,
+}
+
+enum Frame {
+    // the statements of a block, or a single statement, and the next one to execute
+    Block {
+        node: Arc<ASTNode>,
+        index: usize,
+        env: Env,
+    },
+    While {
+        test: Expression,
+        body: Arc<ASTNode>,
+        env: Env,
+    },
+    For {
+        iter: ValueIter,
+        declarations: Vec<Identifier>,
+        mutable: bool,
+        body: Arc<ASTNode>,
+        env: Env,
+    },
+    // errors raised by the frames above are handled by `handler`
+    Try {
+        param: Option<Identifier>,
+        handler: Arc<ASTNode>,
+        env: Env,
+    },
+}
+
+enum Continuation {
+    Discard,
+    Declare(String, bool),
+    Assign(Expression, AssignmentOperator),
+    Return,
+}
+
+// why the body stopped running
+pub enum Suspension {
+    Yield(Value),
+    Await(Value),
+    // the body ended, with the returned value if any
+    Done(Value),
+}
+
+enum Step {
+    Continue,
+    Suspend(Suspension),
+}
+
+impl Generator {
+    /// a generator about to run a function body, its parameters being declared in `env`
+    pub fn new(body: &Arc<ASTNode>, env: Env, is_async: bool) -> Self {
+        let frame = Frame::Block {
+            node: Arc::clone(body),
+            index: 0,
+            env,
+        };
+
+        Generator {
+            frames: vec![frame],
+            is_async,
+            awaiting: None,
+        }
+    }
+}
+
+impl Interpreter {
+    /// runs a generator up to its next `yield`, `None` meaning that its body ended
+    pub fn resume(&mut self, generator: &mut Generator) -> RuntimeResult<Option<Value>> {
+        match self.drive(generator, None)? {
+            Suspension::Yield(value) => Ok(Some(value)),
+            Suspension::Done(_) => Ok(None),
+            Suspension::Await(_) => unreachable!(),
+        }
+    }
+
+    /// runs the body up to where it suspends, an async body being handed the result
+    /// of the promise it awaited
+    pub fn drive(
+        &mut self,
+        generator: &mut Generator,
+        mut awaited: Option<Settled>,
+    ) -> RuntimeResult<Suspension> {
+        loop {
+            let step = match awaited.take() {
+                Some(settled) => self.continue_with(generator, settled),
+                None => self.step(generator),
+            };
+
+            match step {
+                Ok(Step::Continue) => {}
+                Ok(Step::Suspend(Suspension::Done(value))) => {
+                    generator.frames.clear();
+                    return Ok(Suspension::Done(value));
+                }
+                Ok(Step::Suspend(suspension)) => return Ok(suspension),
+                Err(err) => {
+                    let handler = generator
+                        .frames
+                        .iter()
+                        .rposition(|frame| matches!(frame, Frame::Try { .. }));
+
+                    let Some(position) = handler.filter(|_| err.exit_code.is_none()) else {
+                        generator.frames.clear();
+                        return Err(err);
+                    };
+
+                    generator.frames.truncate(position + 1);
+                    let Some(Frame::Try {
+                        param,
+                        handler,
+                        env,
+                    }) = generator.frames.pop()
+                    else {
+                        unreachable!()
+                    };
+
+                    let env = Environment::new_child(&env);
+                    if let Some(param) = param {
+                        env.borrow_mut().declare(&param.name, err.to_value(), false);
+                    }
+                    generator.frames.push(Frame::Block {
+                        node: handler,
+                        index: 0,
+                        env,
+                    });
+                }
+            }
+        }
+    }
+
+    /// finishes the statement that awaited, an error being raised from it
+    fn continue_with(
+        &mut self,
+        generator: &mut Generator,
+        settled: Settled,
+    ) -> RuntimeResult<Step> {
+        let Some((continuation, env)) = generator.awaiting.take() else {
+            unreachable!()
+        };
+        let value = settled?;
+
+        match continuation {
+            Continuation::Discard => {}
+            Continuation::Declare(name, mutable) => env.borrow_mut().declare(&name, value, mutable),
+            Continuation::Assign(target, operator) => {
+                self.with_env(env, |interpreter| {
+                    interpreter.assign(&target, operator, value)
+                })?;
+            }
+            Continuation::Return => return Ok(Step::Suspend(Suspension::Done(value))),
+        }
+
+        Ok(Step::Continue)
+    }
+
+    fn step(&mut self, generator: &mut Generator) -> RuntimeResult<Step> {
+        let is_async = generator.is_async;
+        let Some(frame) = generator.frames.last_mut() else {
+            return Ok(Step::Suspend(Suspension::Done(Value::Nil)));
+        };
+
+        let entered = match frame {
+            Frame::Block { node, index, env } => {
+                let (node, env) = (Arc::clone(node), Rc::clone(env));
+                let Some((statement, span)) = statement(&node, *index) else {
+                    generator.frames.pop();
+                    return Ok(Step::Continue);
+                };
+                *index += 1;
+
+                let step = if suspends(statement, is_async) {
+                    self.enter(generator, statement, env)
+                } else {
+                    self.with_env(env, |interpreter| interpreter.execute(statement))
+                        .map(|flow| match flow {
+                            Flow::Return(value) => Step::Suspend(Suspension::Done(value)),
+                            Flow::Normal => Step::Continue,
+                        })
+                };
+
+                return match span {
+                    Some(span) => step.map_err(|err| err.at(span)),
+                    None => step,
+                };
+            }
+            Frame::While { test, body, env } => {
+                let test =
+                    self.with_env(Rc::clone(env), |interpreter| interpreter.evaluate(test))?;
+
+                test.is_truthy().then(|| Frame::Block {
+                    node: Arc::clone(body),
+                    index: 0,
+                    env: Environment::new_child(env),
+                })
+            }
+            Frame::For {
+                iter,
+                declarations,
+                mutable,
+                body,
+                env,
+            } => {
+                let binds_key = iter.binds_key();
+
+                iter.next_entry(self)?.map(|entry| Frame::Block {
+                    node: Arc::clone(body),
+                    index: 0,
+                    env: super::loop_env(env, declarations, *mutable, binds_key, entry),
+                })
+            }
+            // the block ended without any error
+            Frame::Try { .. } => None,
+        };
+
+        match entered {
+            Some(frame) => generator.frames.push(frame),
+            None => {
+                generator.frames.pop();
+            }
+        }
+
+        Ok(Step::Continue)
+    }
+
+    /// starts a statement that suspends or contains one that does
+    fn enter(
+        &mut self,
+        generator: &mut Generator,
+        statement: &ASTNode,
+        env: Env,
+    ) -> RuntimeResult<Step> {
+        if let Some((argument, continuation)) = awaits(statement).filter(|_| generator.is_async) {
+            let value = self.with_env(Rc::clone(&env), |interpreter| {
+                interpreter.evaluate(argument)
+            })?;
+            generator.awaiting = Some((continuation, env));
+
+            return Ok(Step::Suspend(Suspension::Await(value)));
+        }
+
+        let frame = match statement {
+            ASTNode::YieldStatement { argument } => {
+                let value = self.with_env(env, |interpreter| interpreter.evaluate(argument))?;
+                return Ok(Step::Suspend(Suspension::Yield(value)));
+            }
+            ASTNode::IfStatement {
+                test,
+                body,
+                alternate,
+            } => {
+                let test =
+                    self.with_env(Rc::clone(&env), |interpreter| interpreter.evaluate(test))?;
+
+                match (test.is_truthy(), alternate) {
+                    (true, _) => block(body, &env),
+                    (false, Some(alternate)) => block(alternate, &env),
+                    (false, None) => return Ok(Step::Continue),
+                }
+            }
+            ASTNode::WhileStatement { test, body } => Frame::While {
+                test: test.clone(),
+                body: Arc::clone(body),
+                env,
+            },
+            ASTNode::ForStatement {
+                declarations,
+                kind,
+                source,
+                body,
+            } => {
+                let source =
+                    self.with_env(Rc::clone(&env), |interpreter| interpreter.evaluate(source))?;
+
+                Frame::For {
+                    iter: ValueIter::new(&source)?,
+                    declarations: declarations.clone(),
+                    mutable: *kind == VariableKeyword::Var,
+                    body: Arc::clone(body),
+                    env,
+                }
+            }
+            ASTNode::TryStatement {
+                block: tried,
+                param,
+                handler,
+            } => {
+                generator.frames.push(Frame::Try {
+                    param: param.clone(),
+                    handler: Arc::clone(handler),
+                    env: Rc::clone(&env),
+                });
+
+                block(tried, &env)
+            }
+            _ => unreachable!("only statements with a nested block can contain a suspension"),
+        };
+
+        generator.frames.push(frame);
+        Ok(Step::Continue)
+    }
+}
+
+/// the frame executing a statement as `execute` would, blocks getting their own scope
+fn block(node: &Arc<ASTNode>, env: &Env) -> Frame {
+    let env = match &**node {
+        ASTNode::BlockStatement { .. } => Environment::new_child(env),
+        _ => Rc::clone(env),
+    };
+
+    Frame::Block {
+        node: Arc::clone(node),
+        index: 0,
+        env,
+    }
+}
+
+/// the statement at `index` of a block with its span, any other node being a block of
+/// its own statement
+fn statement(node: &ASTNode, index: usize) -> Option<(&ASTNode, Option<&Span>)> {
+    match node {
+        ASTNode::BlockStatement { body } => body
+            .get(index)
+            .map(|statement| (&statement.node, Some(&statement.span))),
+        node => (index == 0).then_some((node, None)),
+    }
+}
+
+/// whether a statement yields, functions declared inside being generators of their own
+pub fn contains_yield(node: &ASTNode) -> bool {
+    suspends(node, false)
+}
+
+/// whether a statement yields or, in async bodies, awaits
+fn suspends(node: &ASTNode, is_async: bool) -> bool {
+    if is_async && awaits(node).is_some() {
+        return true;
+    }
+
+    let suspends = |node: &ASTNode| suspends(node, is_async);
+
+    match node {
+        ASTNode::YieldStatement { .. } => !is_async,
+        ASTNode::Program { body } | ASTNode::BlockStatement { body } => {
+            body.iter().any(|statement| suspends(&statement.node))
+        }
+        ASTNode::ForStatement { body, .. } | ASTNode::WhileStatement { body, .. } => suspends(body),
+        ASTNode::IfStatement {
+            body, alternate, ..
+        } => suspends(body) || alternate.as_deref().is_some_and(suspends),
+        ASTNode::TryStatement { block, handler, .. } => suspends(block) || suspends(handler),
+        _ => false,
+    }
+}
+
+/// the awaited expression of a statement an async body can suspend at
+fn awaits(node: &ASTNode) -> Option<(&Expression, Continuation)> {
+    fn awaited(expression: &Expression) -> Option<&Expression> {
+        match expression {
+            Expression::AwaitExpression { argument } => Some(argument),
+            _ => None,
+        }
+    }
+
+    match node {
+        ASTNode::ExpressionStatement {
+            expression:
+                Expression::AssignmentExpression {
+                    operator,
+                    id,
+                    assigned,
+                },
+        } => {
+            let continuation = Continuation::Assign(*id.clone(), *operator);
+            awaited(assigned).map(|argument| (argument, continuation))
+        }
+        ASTNode::ExpressionStatement { expression } => {
+            awaited(expression).map(|argument| (argument, Continuation::Discard))
+        }
+        ASTNode::VariableDeclaration { declarations, kind } => match declarations.as_slice() {
+            [declaration] => {
+                let continuation = Continuation::Declare(
+                    declaration.id.name.clone(),
+                    *kind == VariableKeyword::Var,
+                );
+                awaited(&declaration.init).map(|argument| (argument, continuation))
+            }
+            _ => None,
+        },
+        ASTNode::ReturnStatement { argument, .. } => {
+            awaited(argument).map(|argument| (argument, Continuation::Return))
+        }
+        _ => None,
+    }
+}
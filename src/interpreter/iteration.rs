@@ -0,0 +1,263 @@
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use hashbrown::HashMap;
+
+use super::{
+    collections::sorted_keys,
+    errors::{RuntimeError, RuntimeResult},
+    sendable::Shared,
+    value::{Array, NativeIterator, Range, Value, ValueKind},
+    Interpreter,
+};
+
+// the state of a `for ... in` loop over a value
+pub enum ValueIter {
+    Range {
+        range: Range,
+        index: usize,
+    },
+    Array {
+        array: Array,
+        index: usize,
+    },
+    // a snapshot of the elements of a collection
+    Values {
+        values: std::vec::IntoIter<Value>,
+        index: usize,
+    },
+    Entries {
+        entries: std::vec::IntoIter<(Value, Value)>,
+    },
+    Chars {
+        chars: Vec<char>,
+        index: usize,
+    },
+    Bytes {
+        bytes: Rc<[u8]>,
+        index: usize,
+    },
+    Native {
+        iterator: Rc<RefCell<NativeIterator>>,
+        index: usize,
+    },
+    // an object following the iterator protocol, whose `next()` returns nil once exhausted
+    Protocol {
+        next: Value,
+        index: usize,
+    },
+}
+
+impl ValueIter {
+    pub fn new(value: &Value) -> RuntimeResult<Self> {
+        let iter = match value {
+            Value::Range(range) => {
+                if range.from.is_none() {
+                    let mut err = RuntimeError::not_iterable(value.kind());
+                    err.notes.push(format!("`{range}` has no start"));
+                    return Err(err);
+                }
+
+                // values are computed one at a time, `0..1e9` never being stored
+                ValueIter::Range {
+                    range: *range,
+                    index: 0,
+                }
+            }
+            Value::Array(array) => ValueIter::Array {
+                array: Rc::clone(array),
+                index: 0,
+            },
+            Value::Object(object) if is_iterator(&object.borrow()) => ValueIter::Protocol {
+                next: object.borrow()["next"].clone(),
+                index: 0,
+            },
+            Value::Object(object) => {
+                let object = object.borrow();
+                let mut keys = object.keys().collect::<Vec<_>>();
+                keys.sort();
+
+                let entries = keys
+                    .into_iter()
+                    .map(|key| (Value::str(key), object[key].clone()))
+                    .collect::<Vec<_>>();
+
+                ValueIter::Entries {
+                    entries: entries.into_iter(),
+                }
+            }
+            Value::Map(map) => {
+                let map = map.borrow();
+                let entries = sorted_keys(map.keys())
+                    .into_iter()
+                    .map(|key| (key.to_value(), map[key].clone()))
+                    .collect::<Vec<_>>();
+
+                ValueIter::Entries {
+                    entries: entries.into_iter(),
+                }
+            }
+            Value::Set(set) => {
+                let set = set.borrow();
+                let values = sorted_keys(set.iter())
+                    .into_iter()
+                    .map(|key| key.to_value());
+
+                ValueIter::values(values.collect())
+            }
+            Value::Deque(deque) => ValueIter::values(deque.borrow().iter().cloned().collect()),
+            Value::Stack(stack) => ValueIter::values(stack.borrow().clone()),
+            Value::PriorityQueue(heap) => {
+                let values = heap.borrow().sorted().into_iter().map(|(_, value)| value);
+
+                ValueIter::values(values.collect())
+            }
+            Value::Str(s) => ValueIter::Chars {
+                chars: s.chars().collect(),
+                index: 0,
+            },
+            Value::Bytes(bytes) => ValueIter::Bytes {
+                bytes: Rc::clone(bytes),
+                index: 0,
+            },
+            Value::Shared(Shared::Channel(channel)) => {
+                let channel = Arc::clone(channel);
+                let values = move |_: &mut Interpreter| Ok(channel.recv().map(|v| v.unpack_one()));
+
+                ValueIter::Native {
+                    iterator: Rc::new(RefCell::new(NativeIterator(Box::new(values)))),
+                    index: 0,
+                }
+            }
+            Value::Iterator(iterator) => ValueIter::Native {
+                iterator: Rc::clone(iterator),
+                index: 0,
+            },
+            value => return Err(RuntimeError::not_iterable(value.kind())),
+        };
+
+        Ok(iter)
+    }
+
+    fn values(values: Vec<Value>) -> Self {
+        ValueIter::Values {
+            values: values.into_iter(),
+            index: 0,
+        }
+    }
+
+    /// whether a single loop variable is bound to the key rather than the value
+    pub fn binds_key(&self) -> bool {
+        matches!(self, ValueIter::Entries { .. })
+    }
+
+    /// the value a single loop variable would be bound to
+    pub fn next_value(&mut self, interpreter: &mut Interpreter) -> RuntimeResult<Option<Value>> {
+        let binds_key = self.binds_key();
+        let entry = self.next_entry(interpreter)?;
+
+        Ok(entry.map(|(key, value)| if binds_key { key } else { value }))
+    }
+
+    /// yields the next (key, value) pair, the key being an index for sequences
+    pub fn next_entry(
+        &mut self,
+        interpreter: &mut Interpreter,
+    ) -> RuntimeResult<Option<(Value, Value)>> {
+        let (value, index) = match self {
+            ValueIter::Native { iterator, index } => (next(iterator, interpreter)?, index),
+            ValueIter::Protocol { next, index } => {
+                let value = match interpreter.call(next, vec![])? {
+                    Value::Nil => None,
+                    value => Some(value),
+                };
+                (value, index)
+            }
+            _ => return Ok(self.next_sequence_entry()),
+        };
+
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        let key = Value::Number(*index as f64);
+        *index += 1;
+
+        Ok(Some((key, value)))
+    }
+
+    fn next_sequence_entry(&mut self) -> Option<(Value, Value)> {
+        match self {
+            ValueIter::Range { range, index } => {
+                if range.len().is_some_and(|len| *index as f64 >= len) {
+                    return None;
+                }
+
+                let value = Value::Number(range.nth(*index)?);
+                let key = Value::Number(*index as f64);
+                *index += 1;
+
+                Some((key, value))
+            }
+            ValueIter::Array { array, index } => {
+                let value = array.borrow().get(*index).cloned()?;
+                let key = Value::Number(*index as f64);
+                *index += 1;
+
+                Some((key, value))
+            }
+            ValueIter::Values { values, index } => {
+                let value = values.next()?;
+                let key = Value::Number(*index as f64);
+                *index += 1;
+
+                Some((key, value))
+            }
+            ValueIter::Entries { entries } => entries.next(),
+            ValueIter::Chars { chars, index } => {
+                let c = chars.get(*index)?;
+                let key = Value::Number(*index as f64);
+                *index += 1;
+
+                Some((key, Value::str(&c.to_string())))
+            }
+            ValueIter::Bytes { bytes, index } => {
+                let byte = bytes.get(*index)?;
+                let key = Value::Number(*index as f64);
+                *index += 1;
+
+                Some((key, Value::Number(*byte as f64)))
+            }
+            ValueIter::Native { .. } | ValueIter::Protocol { .. } => unreachable!(),
+        }
+    }
+}
+
+/// every value a `for ... in` loop over the value would bind
+pub fn collect_values(interpreter: &mut Interpreter, value: &Value) -> RuntimeResult<Vec<Value>> {
+    let mut iter = ValueIter::new(value)?;
+    let mut values = vec![];
+
+    while let Some(value) = iter.next_value(interpreter)? {
+        values.push(value);
+    }
+
+    Ok(values)
+}
+
+/// the next value of a native iterator, which cannot be advanced from within itself
+pub fn next(
+    iterator: &RefCell<NativeIterator>,
+    interpreter: &mut Interpreter,
+) -> RuntimeResult<Option<Value>> {
+    let mut iterator = iterator
+        .try_borrow_mut()
+        .map_err(|_| RuntimeError::iterator_running())?;
+
+    iterator.next(interpreter)
+}
+
+/// whether an object follows the iterator protocol by having a `next` method
+fn is_iterator(object: &HashMap<String, Value>) -> bool {
+    object
+        .get("next")
+        .is_some_and(|next| next.kind() == ValueKind::Function)
+}
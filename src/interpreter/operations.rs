@@ -0,0 +1,120 @@
+// evaluation of binary and assignment operators,
+// `&&` and `||` are short-circuited by the interpreter itself
+
+use std::cmp::Ordering;
+
+use crate::parser::operations::{assignment::AssignmentOperator, binary::BinaryOperator};
+
+use super::{
+    errors::{RuntimeError, RuntimeResult},
+    value::Value,
+};
+
+pub fn binary_operation(
+    left: &Value,
+    operator: BinaryOperator,
+    right: &Value,
+) -> RuntimeResult<Value> {
+    let value = match operator {
+        BinaryOperator::Plus => match (left, right) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+            (Value::Str(a), b) => Value::str(&format!("{a}{b}")),
+            (a, Value::Str(b)) => Value::str(&format!("{a}{b}")),
+            (Value::Array(a), Value::Array(b)) => {
+                let mut elements = a.borrow().clone();
+                elements.extend(b.borrow().iter().cloned());
+                Value::array(elements)
+            }
+            (Value::Duration(a), Value::Duration(b)) => Value::Duration(a + b),
+            _ => return Err(invalid(left, operator, right)),
+        },
+        BinaryOperator::Minus => match (left, right) {
+            (Value::Duration(a), Value::Duration(b)) => Value::Duration(a - b),
+            _ => Value::Number(numbers(left, operator, right, |a, b| a - b)?),
+        },
+        BinaryOperator::Times => match (left, right) {
+            (Value::Duration(d), Value::Number(n)) | (Value::Number(n), Value::Duration(d)) => {
+                Value::Duration(d * n)
+            }
+            _ => Value::Number(numbers(left, operator, right, |a, b| a * b)?),
+        },
+        BinaryOperator::Exponential => {
+            Value::Number(numbers(left, operator, right, |a, b| a.powf(b))?)
+        }
+        BinaryOperator::Divide | BinaryOperator::Modulo => {
+            if matches!(right, Value::Number(b) | Value::Duration(b) if *b == 0.0) {
+                return Err(RuntimeError::division_by_zero());
+            }
+
+            match (operator, left, right) {
+                (BinaryOperator::Divide, Value::Duration(a), Value::Number(b)) => {
+                    Value::Duration(a / b)
+                }
+                (BinaryOperator::Divide, Value::Duration(a), Value::Duration(b)) => {
+                    Value::Number(a / b)
+                }
+                (BinaryOperator::Modulo, Value::Duration(a), Value::Duration(b)) => {
+                    Value::Duration(a % b)
+                }
+                (BinaryOperator::Divide, ..) => {
+                    Value::Number(numbers(left, operator, right, |a, b| a / b)?)
+                }
+                _ => Value::Number(numbers(left, operator, right, |a, b| a % b)?),
+            }
+        }
+        BinaryOperator::Equal => Value::Bool(left == right),
+        BinaryOperator::NotEqual => Value::Bool(left != right),
+        BinaryOperator::Greater => Value::Bool(compare(left, operator, right)?.is_gt()),
+        BinaryOperator::GreaterOrEqual => Value::Bool(compare(left, operator, right)?.is_ge()),
+        BinaryOperator::Smaller => Value::Bool(compare(left, operator, right)?.is_lt()),
+        BinaryOperator::SmallerOrEqual => Value::Bool(compare(left, operator, right)?.is_le()),
+        BinaryOperator::And => Value::Bool(left.is_truthy() && right.is_truthy()),
+        BinaryOperator::Or => Value::Bool(left.is_truthy() || right.is_truthy()),
+    };
+
+    Ok(value)
+}
+
+pub fn assignment_operation(
+    current: &Value,
+    operator: AssignmentOperator,
+    value: Value,
+) -> RuntimeResult<Value> {
+    let operator = match operator {
+        AssignmentOperator::Equal => return Ok(value),
+        AssignmentOperator::PlusEqual => BinaryOperator::Plus,
+        AssignmentOperator::MinusEqual => BinaryOperator::Minus,
+        AssignmentOperator::TimesEqual => BinaryOperator::Times,
+        AssignmentOperator::DivideEqual => BinaryOperator::Divide,
+        AssignmentOperator::ModuloEqual => BinaryOperator::Modulo,
+    };
+
+    binary_operation(current, operator, &value)
+}
+
+pub fn compare(left: &Value, operator: BinaryOperator, right: &Value) -> RuntimeResult<Ordering> {
+    let ordering = match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        (Value::Duration(a), Value::Duration(b)) => a.partial_cmp(b),
+        _ => None,
+    };
+
+    ordering.ok_or_else(|| invalid(left, operator, right))
+}
+
+fn numbers(
+    left: &Value,
+    operator: BinaryOperator,
+    right: &Value,
+    operation: fn(f64, f64) -> f64,
+) -> RuntimeResult<f64> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => Ok(operation(*a, *b)),
+        _ => Err(invalid(left, operator, right)),
+    }
+}
+
+fn invalid(left: &Value, operator: BinaryOperator, right: &Value) -> RuntimeError {
+    RuntimeError::invalid_operation(&operator.to_string(), left.kind(), right.kind())
+}
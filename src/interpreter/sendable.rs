@@ -0,0 +1,562 @@
+// values crossing threads: data is deep-copied, functions are sent along with
+// copies of the variables they capture, and thread primitives are shared
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
+    sync::{atomic::AtomicI64, Arc, Condvar, Mutex, MutexGuard},
+    thread::JoinHandle,
+};
+
+use hashbrown::{HashMap, HashSet};
+
+use super::{
+    collections::{Heap, Key},
+    environment::Environment,
+    errors::{RuntimeError, RuntimeResult},
+    value::{Function, Method, Range, Regex, Value},
+    Interpreter,
+};
+use crate::{
+    comfy::NativeFunction,
+    parser::ast::{identifier::Identifier, ASTNode, Expression},
+};
+
+/// values packed on one thread to be unpacked on another
+#[derive(Debug, Clone)]
+pub struct Sendable {
+    values: Vec<SendValue>,
+    functions: Vec<SendFunction>,
+}
+
+#[derive(Debug, Clone)]
+enum SendValue {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    Array(Vec<SendValue>),
+    Object(Vec<(String, SendValue)>),
+    Range(Range),
+    Duration(f64),
+    Regex(Regex),
+    Set(Vec<SendValue>),
+    Map(Vec<(SendValue, SendValue)>),
+    Deque(Vec<SendValue>),
+    Stack(Vec<SendValue>),
+    PriorityQueue(Vec<(f64, SendValue)>),
+    Shared(Shared),
+    Native(&'static NativeFunction),
+    Method(Box<SendValue>, &'static NativeFunction),
+    // an index in the functions of the package, functions being able to capture themselves
+    Function(usize),
+}
+
+#[derive(Debug, Clone)]
+struct SendFunction {
+    name: Option<String>,
+    params: Vec<Identifier>,
+    body: Arc<ASTNode>,
+    is_generator: bool,
+    is_async: bool,
+    // name, value and mutability of the captured variables
+    captures: Vec<(String, SendValue, bool)>,
+}
+
+/// the thread primitives, the same on every thread they are sent to
+#[derive(Debug, Clone)]
+pub enum Shared {
+    Thread(Arc<ThreadHandle>),
+    Channel(Arc<Channel>),
+    Mutex(Arc<Mutex<Sendable>>),
+    Atomic(Arc<AtomicI64>),
+}
+
+#[derive(Debug)]
+pub struct ThreadHandle {
+    state: Mutex<JoinState>,
+}
+
+#[derive(Debug)]
+enum JoinState {
+    Running(JoinHandle<RuntimeResult<Sendable>>),
+    Joining,
+    Finished(RuntimeResult<Sendable>),
+}
+
+/// an unbounded multi-producer multi-consumer queue, closed explicitly
+#[derive(Debug, Default)]
+pub struct Channel {
+    state: Mutex<ChannelState>,
+    ready: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct ChannelState {
+    queue: VecDeque<Sendable>,
+    closed: bool,
+}
+
+impl Sendable {
+    pub fn new(values: &[Value]) -> RuntimeResult<Self> {
+        let mut packer = Packer::default();
+        let values = values
+            .iter()
+            .map(|value| packer.pack(value))
+            .collect::<RuntimeResult<_>>()?;
+
+        Ok(Sendable {
+            values,
+            functions: packer.functions,
+        })
+    }
+
+    /// the values as seen by the current thread, functions getting their own copies
+    /// of the captured variables on top of a fresh global scope
+    pub fn unpack(&self) -> Vec<Value> {
+        let globals = Interpreter::global_env();
+        let functions = self
+            .functions
+            .iter()
+            .map(|function| {
+                Rc::new(Function {
+                    name: function.name.clone(),
+                    params: function.params.clone(),
+                    body: Arc::clone(&function.body),
+                    closure: Environment::new_child(&globals),
+                    is_generator: function.is_generator,
+                    is_async: function.is_async,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for (function, sent) in functions.iter().zip(&self.functions) {
+            for (name, value, mutable) in &sent.captures {
+                let value = unpack(value, &functions);
+                function.closure.borrow_mut().declare(name, value, *mutable);
+            }
+        }
+
+        self.values
+            .iter()
+            .map(|value| unpack(value, &functions))
+            .collect()
+    }
+
+    pub fn unpack_one(&self) -> Value {
+        self.unpack().swap_remove(0)
+    }
+}
+
+#[derive(Default)]
+struct Packer {
+    functions: Vec<SendFunction>,
+    packed_functions: HashMap<*const Function, usize>,
+    // the containers being packed, to reject cycles
+    ancestors: Vec<*const ()>,
+}
+
+impl Packer {
+    fn pack(&mut self, value: &Value) -> RuntimeResult<SendValue> {
+        let packed = match value {
+            Value::Nil => SendValue::Nil,
+            Value::Bool(b) => SendValue::Bool(*b),
+            Value::Number(n) => SendValue::Number(*n),
+            Value::Str(s) => SendValue::Str(s.to_string()),
+            Value::Bytes(bytes) => SendValue::Bytes(bytes.to_vec()),
+            Value::Range(range) => SendValue::Range(*range),
+            Value::Duration(ms) => SendValue::Duration(*ms),
+            Value::Regex(regex) => SendValue::Regex((**regex).clone()),
+            Value::Array(array) => {
+                self.enter(Rc::as_ptr(array).cast(), value)?;
+                let values = self.pack_all(array.borrow().iter())?;
+                self.ancestors.pop();
+
+                SendValue::Array(values)
+            }
+            Value::Object(object) => {
+                self.enter(Rc::as_ptr(object).cast(), value)?;
+                let properties = object
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| Ok((key.to_owned(), self.pack(value)?)))
+                    .collect::<RuntimeResult<_>>()?;
+                self.ancestors.pop();
+
+                SendValue::Object(properties)
+            }
+            Value::Set(set) => {
+                let values = set.borrow().iter().map(Key::to_value).collect::<Vec<_>>();
+                SendValue::Set(self.pack_all(values.iter())?)
+            }
+            Value::Map(map) => {
+                self.enter(Rc::as_ptr(map).cast(), value)?;
+                let entries = map
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| Ok((self.pack(&key.to_value())?, self.pack(value)?)))
+                    .collect::<RuntimeResult<_>>()?;
+                self.ancestors.pop();
+
+                SendValue::Map(entries)
+            }
+            Value::Deque(deque) => {
+                self.enter(Rc::as_ptr(deque).cast(), value)?;
+                let values = self.pack_all(deque.borrow().iter())?;
+                self.ancestors.pop();
+
+                SendValue::Deque(values)
+            }
+            Value::Stack(stack) => {
+                self.enter(Rc::as_ptr(stack).cast(), value)?;
+                let values = self.pack_all(stack.borrow().iter())?;
+                self.ancestors.pop();
+
+                SendValue::Stack(values)
+            }
+            Value::PriorityQueue(heap) => {
+                self.enter(Rc::as_ptr(heap).cast(), value)?;
+                let entries = heap
+                    .borrow()
+                    .sorted()
+                    .into_iter()
+                    .map(|(priority, value)| Ok((priority, self.pack(&value)?)))
+                    .collect::<RuntimeResult<_>>()?;
+                self.ancestors.pop();
+
+                SendValue::PriorityQueue(entries)
+            }
+            Value::Shared(shared) => SendValue::Shared(shared.clone()),
+            Value::Native(function) => SendValue::Native(function),
+            Value::Method(method) => {
+                SendValue::Method(Box::new(self.pack(&method.receiver)?), method.function)
+            }
+            Value::Function(function) => SendValue::Function(self.pack_function(function)?),
+            Value::Iterator(_) | Value::Promise(_) => {
+                return Err(RuntimeError::not_sendable(value.kind()))
+            }
+        };
+
+        Ok(packed)
+    }
+
+    fn pack_all<'a>(
+        &mut self,
+        values: impl Iterator<Item = &'a Value>,
+    ) -> RuntimeResult<Vec<SendValue>> {
+        values.map(|value| self.pack(value)).collect()
+    }
+
+    fn enter(&mut self, pointer: *const (), value: &Value) -> RuntimeResult<()> {
+        if self.ancestors.contains(&pointer) {
+            let mut err = RuntimeError::not_sendable(value.kind());
+            err.notes.push("the value contains itself".to_owned());
+            return Err(err);
+        }
+
+        self.ancestors.push(pointer);
+        Ok(())
+    }
+
+    fn pack_function(&mut self, function: &Rc<Function>) -> RuntimeResult<usize> {
+        if let Some(index) = self.packed_functions.get(&Rc::as_ptr(function)) {
+            return Ok(*index);
+        }
+
+        let index = self.functions.len();
+        self.packed_functions.insert(Rc::as_ptr(function), index);
+        self.functions.push(SendFunction {
+            name: function.name.clone(),
+            params: function.params.clone(),
+            body: Arc::clone(&function.body),
+            is_generator: function.is_generator,
+            is_async: function.is_async,
+            captures: vec![],
+        });
+
+        let mut names = HashSet::new();
+        referenced_names(&function.body, &mut names);
+        for param in &function.params {
+            names.remove(&param.name);
+        }
+
+        let mut names = names.into_iter().collect::<Vec<_>>();
+        names.sort();
+
+        let mut captures = vec![];
+        for name in names {
+            let Some((value, mutable)) = function.closure.borrow().lookup(&name) else {
+                continue;
+            };
+
+            let value = self.pack(&value).map_err(|mut err| {
+                err.notes
+                    .push(format!("captured by a function as `{name}`"));
+                err
+            })?;
+            captures.push((name, value, mutable));
+        }
+
+        self.functions[index].captures = captures;
+        Ok(index)
+    }
+}
+
+fn unpack(value: &SendValue, functions: &[Rc<Function>]) -> Value {
+    let unpack_all = |values: &[SendValue]| {
+        values
+            .iter()
+            .map(|value| unpack(value, functions))
+            .collect::<Vec<_>>()
+    };
+
+    match value {
+        SendValue::Nil => Value::Nil,
+        SendValue::Bool(b) => Value::Bool(*b),
+        SendValue::Number(n) => Value::Number(*n),
+        SendValue::Str(s) => Value::str(s),
+        SendValue::Bytes(bytes) => Value::Bytes(bytes.as_slice().into()),
+        SendValue::Array(values) => Value::array(unpack_all(values)),
+        SendValue::Object(properties) => Value::object(
+            properties
+                .iter()
+                .map(|(key, value)| (key.to_owned(), unpack(value, functions)))
+                .collect(),
+        ),
+        SendValue::Range(range) => Value::Range(*range),
+        SendValue::Duration(ms) => Value::Duration(*ms),
+        SendValue::Regex(regex) => Value::Regex(Rc::new(regex.clone())),
+        SendValue::Set(values) => {
+            // every element was a key when packed
+            let keys = unpack_all(values)
+                .iter()
+                .filter_map(|value| Key::new(value).ok())
+                .collect();
+            Value::Set(Rc::new(RefCell::new(keys)))
+        }
+        SendValue::Map(entries) => {
+            let entries = entries.iter().filter_map(|(key, value)| {
+                let key = Key::new(&unpack(key, functions)).ok()?;
+                Some((key, unpack(value, functions)))
+            });
+            Value::Map(Rc::new(RefCell::new(entries.collect())))
+        }
+        SendValue::Deque(values) => Value::Deque(Rc::new(RefCell::new(unpack_all(values).into()))),
+        SendValue::Stack(values) => Value::Stack(Rc::new(RefCell::new(unpack_all(values)))),
+        SendValue::PriorityQueue(entries) => {
+            let mut heap = Heap::default();
+            for (priority, value) in entries {
+                heap.push(unpack(value, functions), *priority);
+            }
+            Value::PriorityQueue(Rc::new(RefCell::new(heap)))
+        }
+        SendValue::Shared(shared) => Value::Shared(shared.clone()),
+        SendValue::Native(function) => Value::Native(function),
+        SendValue::Method(receiver, function) => Value::Method(Rc::new(Method {
+            receiver: unpack(receiver, functions),
+            function,
+        })),
+        SendValue::Function(index) => Value::Function(Rc::clone(&functions[*index])),
+    }
+}
+
+/// every identifier the node reads or assigns, a superset of the variables it captures
+fn referenced_names(node: &ASTNode, names: &mut HashSet<String>) {
+    match node {
+        ASTNode::Program { body } | ASTNode::BlockStatement { body } => {
+            body.iter()
+                .for_each(|statement| referenced_names(&statement.node, names));
+        }
+        ASTNode::ImportDeclaration { .. } => {}
+        ASTNode::VariableDeclaration { declarations, .. } => {
+            for declaration in declarations {
+                expression_names(&declaration.init, names);
+            }
+        }
+        ASTNode::ExpressionStatement { expression } => expression_names(expression, names),
+        ASTNode::FunctionDeclaration { body, .. } => referenced_names(body, names),
+        ASTNode::ForStatement { source, body, .. } => {
+            expression_names(source, names);
+            referenced_names(body, names);
+        }
+        ASTNode::WhileStatement { test, body } => {
+            expression_names(test, names);
+            referenced_names(body, names);
+        }
+        ASTNode::IfStatement {
+            test,
+            body,
+            alternate,
+        } => {
+            expression_names(test, names);
+            referenced_names(body, names);
+            if let Some(alternate) = alternate {
+                referenced_names(alternate, names);
+            }
+        }
+        ASTNode::TryStatement { block, handler, .. } => {
+            referenced_names(block, names);
+            referenced_names(handler, names);
+        }
+        ASTNode::ReturnStatement { argument, .. } | ASTNode::YieldStatement { argument } => {
+            expression_names(argument, names)
+        }
+    }
+}
+
+fn expression_names(expression: &Expression, names: &mut HashSet<String>) {
+    match expression {
+        Expression::Literal { .. } | Expression::Comment { .. } => {}
+        Expression::IdentifierExpression(identifier) => {
+            names.insert(identifier.name.to_owned());
+        }
+        Expression::TemplateLiteral { expressions, .. } => {
+            expressions.iter().for_each(|e| expression_names(e, names));
+        }
+        Expression::Array { elements } => {
+            elements.iter().for_each(|e| expression_names(e, names));
+        }
+        Expression::Object { properties } => {
+            properties
+                .iter()
+                .for_each(|property| expression_names(&property.value, names));
+        }
+        Expression::Range { from, to, step, .. } => {
+            from.iter()
+                .chain(to)
+                .chain(step)
+                .for_each(|e| expression_names(e, names));
+        }
+        Expression::BinaryExpression { left, right, .. } => {
+            expression_names(left, names);
+            expression_names(right, names);
+        }
+        Expression::MemberExpression {
+            indexed,
+            property,
+            computed,
+        } => {
+            expression_names(indexed, names);
+            if *computed {
+                expression_names(property, names);
+            }
+        }
+        Expression::CallExpression { callee, args } => {
+            expression_names(callee, names);
+            args.iter().for_each(|e| expression_names(e, names));
+        }
+        Expression::AssignmentExpression { id, assigned, .. } => {
+            expression_names(id, names);
+            expression_names(assigned, names);
+        }
+        Expression::Parenthesized(expression)
+        | Expression::AwaitExpression {
+            argument: expression,
+        } => expression_names(expression, names),
+        Expression::FnExpression { body, .. } => referenced_names(body, names),
+    }
+}
+
+impl Shared {
+    pub fn ptr_eq(&self, other: &Shared) -> bool {
+        match (self, other) {
+            (Shared::Thread(a), Shared::Thread(b)) => Arc::ptr_eq(a, b),
+            (Shared::Channel(a), Shared::Channel(b)) => Arc::ptr_eq(a, b),
+            (Shared::Mutex(a), Shared::Mutex(b)) => Arc::ptr_eq(a, b),
+            (Shared::Atomic(a), Shared::Atomic(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl ThreadHandle {
+    pub fn new(handle: JoinHandle<RuntimeResult<Sendable>>) -> Self {
+        ThreadHandle {
+            state: Mutex::new(JoinState::Running(handle)),
+        }
+    }
+
+    /// waits for the thread to end, the result being kept for later joins
+    pub fn join(&self) -> RuntimeResult<Sendable> {
+        let mut state = lock(&self.state);
+
+        if let JoinState::Running(_) = &*state {
+            let JoinState::Running(handle) = std::mem::replace(&mut *state, JoinState::Joining)
+            else {
+                unreachable!()
+            };
+
+            let result = handle
+                .join()
+                .unwrap_or_else(|_| Err(RuntimeError::thread_panicked()));
+            *state = JoinState::Finished(result);
+        }
+
+        match &*state {
+            JoinState::Finished(result) => result.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match &*lock(&self.state) {
+            JoinState::Running(handle) => handle.is_finished(),
+            _ => true,
+        }
+    }
+}
+
+impl Channel {
+    /// false when the channel is closed
+    pub fn send(&self, value: Sendable) -> bool {
+        let mut state = lock(&self.state);
+        if state.closed {
+            return false;
+        }
+
+        state.queue.push_back(value);
+        self.ready.notify_one();
+        true
+    }
+
+    /// blocks until a value is sent, None once the channel is closed and empty
+    pub fn recv(&self) -> Option<Sendable> {
+        let mut state = lock(&self.state);
+
+        loop {
+            if let Some(value) = state.queue.pop_front() {
+                return Some(value);
+            }
+            if state.closed {
+                return None;
+            }
+
+            state = self
+                .ready
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    pub fn try_recv(&self) -> Option<Sendable> {
+        lock(&self.state).queue.pop_front()
+    }
+
+    pub fn close(&self) {
+        lock(&self.state).closed = true;
+        self.ready.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        lock(&self.state).closed
+    }
+}
+
+/// locks a mutex, ignoring poisoning as scripts cannot panic while holding one
+pub fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,528 @@
+use std::{cell::RefCell, fmt, rc::Rc, sync::Arc, thread::LocalKey};
+
+use hashbrown::HashMap;
+
+use crate::{
+    comfy::NativeFunction,
+    parser::ast::{identifier::Identifier, ASTNode},
+};
+
+use super::{
+    collections::{self, Deque, Map, PriorityQueue, Set, Stack},
+    environment::Env,
+    errors::{RuntimeError, RuntimeResult},
+    event_loop::Promise,
+    sendable::Shared,
+    Interpreter,
+};
+
+pub type Array = Rc<RefCell<Vec<Value>>>;
+pub type Object = Rc<RefCell<HashMap<String, Value>>>;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    Str(Rc<str>),
+    Bytes(Rc<[u8]>),
+    Array(Array),
+    Object(Object),
+    Range(Range),
+    // a span of time in milliseconds, possibly negative
+    Duration(f64),
+    Set(Set),
+    Map(Map),
+    Deque(Deque),
+    Stack(Stack),
+    PriorityQueue(PriorityQueue),
+    Shared(Shared),
+    Iterator(Rc<RefCell<NativeIterator>>),
+    Promise(Rc<Promise>),
+    Regex(Rc<Regex>),
+    Function(Rc<Function>),
+    Native(&'static NativeFunction),
+    Method(Rc<Method>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    // open ends are `None`, a range without a start cannot be iterated
+    pub from: Option<f64>,
+    pub to: Option<f64>,
+    pub inclusive: bool,
+    // the distance between values, always positive, `None` when not written
+    pub step: Option<f64>,
+}
+
+// a compiled regular expression, along with the pattern and flags it was written with
+#[derive(Debug, Clone)]
+pub struct Regex {
+    pub regex: regex::Regex,
+    pub pattern: String,
+    pub flags: String,
+}
+
+// a lazy sequence produced by native code or a generator, consumed by `for ... in`,
+// `None` meaning that it is exhausted
+pub struct NativeIterator(pub Box<NextValue>);
+
+pub type NextValue = dyn FnMut(&mut Interpreter) -> RuntimeResult<Option<Value>>;
+
+#[derive(Debug)]
+pub struct Function {
+    // if None then anon func
+    pub name: Option<String>,
+    pub params: Vec<Identifier>,
+    pub body: Arc<ASTNode>,
+    pub closure: Env,
+    // whether the body contains `yield`, calls then returning an iterator
+    pub is_generator: bool,
+    // calls of async functions return a promise
+    pub is_async: bool,
+}
+
+// a native method bound to the value it was accessed on, like `set.add`
+#[derive(Debug)]
+pub struct Method {
+    pub receiver: Value,
+    pub function: &'static NativeFunction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Any,
+    Nil,
+    Bool,
+    Number,
+    Str,
+    Bytes,
+    Array,
+    Object,
+    Range,
+    Duration,
+    Set,
+    Map,
+    Deque,
+    Stack,
+    PriorityQueue,
+    Thread,
+    Channel,
+    Mutex,
+    Atomic,
+    Iterator,
+    Promise,
+    Regex,
+    Function,
+}
+
+impl Value {
+    pub fn str(value: &str) -> Self {
+        Value::Str(value.into())
+    }
+    pub fn array(values: Vec<Value>) -> Self {
+        Value::Array(Rc::new(RefCell::new(values)))
+    }
+    pub fn object(properties: HashMap<String, Value>) -> Self {
+        Value::Object(Rc::new(RefCell::new(properties)))
+    }
+    pub fn iterator(mut iterator: impl Iterator<Item = RuntimeResult<Value>> + 'static) -> Self {
+        Value::iterator_from_fn(move |_| iterator.next().transpose())
+    }
+    pub fn iterator_from_fn(
+        next: impl FnMut(&mut Interpreter) -> RuntimeResult<Option<Value>> + 'static,
+    ) -> Self {
+        Value::Iterator(Rc::new(RefCell::new(NativeIterator(Box::new(next)))))
+    }
+
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Value::Nil => ValueKind::Nil,
+            Value::Bool(_) => ValueKind::Bool,
+            Value::Number(_) => ValueKind::Number,
+            Value::Str(_) => ValueKind::Str,
+            Value::Bytes(_) => ValueKind::Bytes,
+            Value::Array(_) => ValueKind::Array,
+            Value::Object(_) => ValueKind::Object,
+            Value::Range(_) => ValueKind::Range,
+            Value::Duration(_) => ValueKind::Duration,
+            Value::Set(_) => ValueKind::Set,
+            Value::Map(_) => ValueKind::Map,
+            Value::Deque(_) => ValueKind::Deque,
+            Value::Stack(_) => ValueKind::Stack,
+            Value::PriorityQueue(_) => ValueKind::PriorityQueue,
+            Value::Shared(Shared::Thread(_)) => ValueKind::Thread,
+            Value::Shared(Shared::Channel(_)) => ValueKind::Channel,
+            Value::Shared(Shared::Mutex(_)) => ValueKind::Mutex,
+            Value::Shared(Shared::Atomic(_)) => ValueKind::Atomic,
+            Value::Iterator(_) => ValueKind::Iterator,
+            Value::Promise(_) => ValueKind::Promise,
+            Value::Regex(_) => ValueKind::Regex,
+            Value::Function(_) | Value::Native(_) | Value::Method(_) => ValueKind::Function,
+        }
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+
+    /// the representation of a value nested in an array or an object,
+    /// where strings are quoted
+    pub fn repr(&self) -> String {
+        match self {
+            Value::Str(s) => format!("{s:?}"),
+            value => value.to_string(),
+        }
+    }
+}
+
+thread_local! {
+    // the containers being printed, and the pairs of them being compared, in which meeting
+    // one again means that it contains itself
+    static PRINTED: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+    static COMPARED: RefCell<Vec<(*const (), *const ())>> = const { RefCell::new(Vec::new()) };
+}
+
+// removes the entry pushed by `enter` once the container is printed or compared
+struct Ancestor<T: 'static>(&'static LocalKey<RefCell<Vec<T>>>);
+
+impl<T> Drop for Ancestor<T> {
+    fn drop(&mut self) {
+        self.0.with(|ancestors| ancestors.borrow_mut().pop());
+    }
+}
+
+/// pushes the entry until the returned guard is dropped, `None` when it is pushed already
+fn enter<T: PartialEq>(
+    ancestors: &'static LocalKey<RefCell<Vec<T>>>,
+    entry: T,
+) -> Option<Ancestor<T>> {
+    ancestors.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        if stack.contains(&entry) {
+            return None;
+        }
+
+        stack.push(entry);
+        Some(Ancestor(ancestors))
+    })
+}
+
+impl Value {
+    // the address of the values that may hold other values, and so themselves
+    fn container(&self) -> Option<*const ()> {
+        match self {
+            Value::Array(array) => Some(Rc::as_ptr(array) as *const ()),
+            Value::Object(object) => Some(Rc::as_ptr(object) as *const ()),
+            Value::Map(map) => Some(Rc::as_ptr(map) as *const ()),
+            Value::Deque(deque) => Some(Rc::as_ptr(deque) as *const ()),
+            Value::Stack(stack) => Some(Rc::as_ptr(stack) as *const ()),
+            Value::PriorityQueue(heap) => Some(Rc::as_ptr(heap) as *const ()),
+            _ => None,
+        }
+    }
+
+    // what is printed for a container found again inside itself
+    fn cycle(&self) -> &'static str {
+        match self {
+            Value::Object(_) => "{...}",
+            Value::Map(_) => "Map {...}",
+            Value::Deque(_) => "Deque [...]",
+            Value::Stack(_) => "Stack [...]",
+            Value::PriorityQueue(_) => "PriorityQueue {...}",
+            _ => "[...]",
+        }
+    }
+}
+
+impl ValueKind {
+    pub fn accepts(&self, value: &Value) -> bool {
+        *self == ValueKind::Any || *self == value.kind()
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        // containers met again while comparing them are equal if the rest of them is
+        let _ancestor = match (self.container(), other.container()) {
+            (Some(a), Some(b)) if a == b => return true,
+            (Some(a), Some(b)) => match enter(&COMPARED, (a, b)) {
+                Some(ancestor) => Some(ancestor),
+                None => return true,
+            },
+            _ => None,
+        };
+
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => *a.borrow() == *b.borrow(),
+            (Value::Object(a), Value::Object(b)) => *a.borrow() == *b.borrow(),
+            (Value::Range(a), Value::Range(b)) => a == b,
+            (Value::Duration(a), Value::Duration(b)) => a == b,
+            (Value::Set(a), Value::Set(b)) => Rc::ptr_eq(a, b) || *a.borrow() == *b.borrow(),
+            (Value::Map(a), Value::Map(b)) => *a.borrow() == *b.borrow(),
+            (Value::Deque(a), Value::Deque(b)) => *a.borrow() == *b.borrow(),
+            (Value::Stack(a), Value::Stack(b)) => *a.borrow() == *b.borrow(),
+            (Value::PriorityQueue(a), Value::PriorityQueue(b)) => *a.borrow() == *b.borrow(),
+            (Value::Shared(a), Value::Shared(b)) => a.ptr_eq(b),
+            (Value::Iterator(a), Value::Iterator(b)) => Rc::ptr_eq(a, b),
+            (Value::Promise(a), Value::Promise(b)) => Rc::ptr_eq(a, b),
+            (Value::Regex(a), Value::Regex(b)) => a.pattern == b.pattern && a.flags == b.flags,
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Native(a), Value::Native(b)) => std::ptr::eq(*a, *b),
+            (Value::Method(a), Value::Method(b)) => {
+                std::ptr::eq(a.function, b.function) && a.receiver == b.receiver
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let _ancestor = match self.container() {
+            Some(pointer) => match enter(&PRINTED, pointer) {
+                Some(ancestor) => Some(ancestor),
+                None => return write!(f, "{}", self.cycle()),
+            },
+            None => None,
+        };
+
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Number(n) => write!(f, "{}", format_number(*n)),
+            Value::Str(s) => write!(f, "{s}"),
+            Value::Bytes(bytes) => write!(f, "<bytes ({})>", bytes.len()),
+            Value::Array(array) => {
+                write!(f, "[")?;
+                for (i, element) in array.borrow().iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element.repr())?;
+                }
+                write!(f, "]")
+            }
+            Value::Object(object) => {
+                let object = object.borrow();
+                // sorted so that printing is deterministic
+                let mut keys = object.keys().collect::<Vec<_>>();
+                keys.sort();
+
+                write!(f, "{{")?;
+                for (i, key) in keys.into_iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, " {}: {}", key, object[key].repr())?;
+                }
+                if !object.is_empty() {
+                    write!(f, " ")?;
+                }
+                write!(f, "}}")
+            }
+            Value::Range(range) => write!(f, "{range}"),
+            Value::Duration(ms) => write!(f, "{}", format_duration(*ms)),
+            Value::Set(set) => collections::fmt_set(f, set),
+            Value::Map(map) => collections::fmt_map(f, map),
+            Value::Deque(deque) => collections::fmt_sequence(f, "Deque", deque.borrow().iter()),
+            Value::Stack(stack) => collections::fmt_sequence(f, "Stack", stack.borrow().iter()),
+            Value::PriorityQueue(heap) => collections::fmt_heap(f, heap),
+            Value::Shared(_) => write!(f, "<{}>", self.kind()),
+            Value::Iterator(_) => write!(f, "<iterator>"),
+            Value::Promise(_) => write!(f, "<promise>"),
+            Value::Regex(regex) => write!(f, "/{}/{}", regex.pattern, regex.flags),
+            Value::Function(function) => match &function.name {
+                Some(name) => write!(f, "<fn {name}>"),
+                None => write!(f, "<fn>"),
+            },
+            Value::Native(function) => write!(f, "<native fn {}>", function.name),
+            Value::Method(method) => write!(f, "<method {}>", method.function.name),
+        }
+    }
+}
+
+impl Range {
+    // tolerance of float ranges, so that `0..=1 step 0.1` includes 1
+    const EPSILON: f64 = 1e-9;
+
+    /// the signed distance between values, a range going down when it ends before its start
+    pub fn step(&self) -> f64 {
+        let step = self.step.unwrap_or(1.0);
+
+        match (self.from, self.to) {
+            (Some(from), Some(to)) if to < from => -step,
+            _ => step,
+        }
+    }
+
+    /// the value at a position, which may be past the end
+    pub fn nth(&self, index: usize) -> Option<f64> {
+        Some(self.from? + index as f64 * self.step())
+    }
+
+    /// the number of values, computed without going through them, which may be more than
+    /// any integer holds
+    pub fn len(&self) -> Option<f64> {
+        let (from, to) = (self.from?, self.to?);
+        let span = (to - from) / self.step();
+
+        let len = if self.inclusive {
+            (span + Self::EPSILON).floor() + 1.0
+        } else {
+            (span - Self::EPSILON).ceil()
+        };
+
+        Some(len.max(0.0))
+    }
+
+    /// whether going through the range would reach the number
+    pub fn contains(&self, n: f64) -> bool {
+        let Some(from) = self.from else {
+            return match self.to {
+                Some(to) if self.inclusive => n <= to,
+                Some(to) => n < to,
+                None => true,
+            };
+        };
+
+        let offset = (n - from) / self.step();
+        if offset < -Self::EPSILON || (offset - offset.round()).abs() > Self::EPSILON {
+            return false;
+        }
+
+        match self.len() {
+            Some(len) => offset.round() < len,
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limits = if self.inclusive { "..=" } else { ".." };
+        let bound = |bound: Option<f64>| bound.map(format_number).unwrap_or_default();
+
+        write!(f, "{}{limits}{}", bound(self.from), bound(self.to))?;
+        if let Some(step) = self.step {
+            write!(f, " step {}", format_number(step))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueKind::Any => write!(f, "any"),
+            ValueKind::Nil => write!(f, "nil"),
+            ValueKind::Bool => write!(f, "boolean"),
+            ValueKind::Number => write!(f, "number"),
+            ValueKind::Str => write!(f, "string"),
+            ValueKind::Bytes => write!(f, "bytes"),
+            ValueKind::Array => write!(f, "array"),
+            ValueKind::Object => write!(f, "object"),
+            ValueKind::Range => write!(f, "range"),
+            ValueKind::Duration => write!(f, "duration"),
+            ValueKind::Set => write!(f, "set"),
+            ValueKind::Map => write!(f, "map"),
+            ValueKind::Deque => write!(f, "deque"),
+            ValueKind::Stack => write!(f, "stack"),
+            ValueKind::PriorityQueue => write!(f, "priorityQueue"),
+            ValueKind::Thread => write!(f, "thread"),
+            ValueKind::Channel => write!(f, "channel"),
+            ValueKind::Mutex => write!(f, "mutex"),
+            ValueKind::Atomic => write!(f, "atomic"),
+            ValueKind::Iterator => write!(f, "iterator"),
+            ValueKind::Promise => write!(f, "promise"),
+            ValueKind::Regex => write!(f, "regex"),
+            ValueKind::Function => write!(f, "function"),
+        }
+    }
+}
+
+pub fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e16 {
+        format!("{}", n as i64)
+    } else {
+        format!("{n}")
+    }
+}
+
+/// formats milliseconds as `250ms`, `1.5s` or `1h 2m 3s`
+pub fn format_duration(ms: f64) -> String {
+    if !ms.is_finite() || ms.abs() < 1000.0 {
+        return format!("{}ms", format_number(ms));
+    }
+
+    let sign = if ms < 0.0 { "-" } else { "" };
+    let ms = ms.abs();
+    let hours = (ms / 3_600_000.0).floor();
+    let minutes = (ms % 3_600_000.0 / 60_000.0).floor();
+    let seconds = ms % 60_000.0 / 1000.0;
+
+    let mut parts = vec![];
+    if hours != 0.0 {
+        parts.push(format!("{}h", format_number(hours)));
+    }
+    if minutes != 0.0 {
+        parts.push(format!("{}m", format_number(minutes)));
+    }
+    if seconds != 0.0 {
+        parts.push(format!("{}s", format_number(seconds)));
+    }
+
+    format!("{sign}{}", parts.join(" "))
+}
+
+impl Regex {
+    /// flags are any of `i` (ignoring case), `m` (`^` and `$` matching at line breaks),
+    /// `s` (`.` matching line breaks) and `x` (ignoring whitespace and `#` comments)
+    pub fn new(pattern: &str, flags: &str) -> RuntimeResult<Self> {
+        let mut builder = regex::RegexBuilder::new(pattern);
+
+        for flag in flags.chars() {
+            match flag {
+                'i' => builder.case_insensitive(true),
+                'm' => builder.multi_line(true),
+                's' => builder.dot_matches_new_line(true),
+                'x' => builder.ignore_whitespace(true),
+                flag => {
+                    return Err(RuntimeError::invalid_regex(
+                        pattern,
+                        flags,
+                        format!("unknown flag `{flag}`, expected i, m, s or x"),
+                    ))
+                }
+            };
+        }
+
+        let regex = builder
+            .build()
+            .map_err(|err| RuntimeError::invalid_regex(pattern, flags, err.to_string()))?;
+
+        Ok(Regex {
+            regex,
+            pattern: pattern.to_owned(),
+            flags: flags.to_owned(),
+        })
+    }
+}
+
+impl NativeIterator {
+    pub fn next(&mut self, interpreter: &mut Interpreter) -> RuntimeResult<Option<Value>> {
+        (self.0)(interpreter)
+    }
+}
+
+impl fmt::Debug for NativeIterator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NativeIterator")
+    }
+}
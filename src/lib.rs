@@ -0,0 +1,23 @@
+//! the parser of ComfyScript, for tools reading scripts without running them, the `comfy`
+//! binary adding the interpreter, the formatter and the minifier on top of it
+//!
+//! ```
+//! use comfy::{lex, TokenKind};
+//!
+//! let input = "let half = 10 / 2 // comment";
+//! let tokens: Vec<_> = lex(input)
+//!     .into_iter()
+//!     .filter(|token| token.kind != TokenKind::Whitespace)
+//!     .map(|token| (token.kind, token.text(input)))
+//!     .collect();
+//!
+//! assert_eq!(tokens[0], (TokenKind::Keyword, "let"));
+//! assert_eq!(tokens[3], (TokenKind::Number, "10"));
+//! assert_eq!(tokens[4], (TokenKind::Operator, "/"));
+//! assert_eq!(tokens[6], (TokenKind::LineComment, "// comment"));
+//! ```
+
+pub mod parser;
+pub mod reserved_keywords;
+
+pub use parser::lexer::{first_token, lex, Token, TokenKind};
@@ -2,14 +2,20 @@ mod comfy;
 mod command;
 mod errors;
 mod execute_folder;
+mod format;
+mod interpreter;
 mod minify;
-mod parser;
-mod reserved_keywords;
+mod minify_folder;
 mod script;
 
+// the parser is the library of the crate, shared with other tools
+use ::comfy::{lex, parser, reserved_keywords, TokenKind};
+
 use crate::{
     command::{get_command, Command},
-    minify::generate_minified_file,
+    errors::ComfyError,
+    format::format_path,
+    minify::{minify_path, source_map},
     script::ComfyScript,
 };
 
@@ -17,16 +23,20 @@ use std::{
     error::Error,
     fs::{self, File},
     io::{BufReader, Read},
-    path::Path,
+    path::{Path, PathBuf},
+    process,
     time::Instant,
 };
 
 fn main() -> Result<(), Box<dyn Error>> {
     let start_time = Instant::now();
     let command = get_command();
+    let mut exit_code = 0;
 
     match command {
-        Command::RunFile(path) => {
+        Command::RunFile(path, args) => {
+            comfy::set_args(args);
+
             if path.is_dir() {
                 execute_folder::execute_folder(&path)?;
             } else {
@@ -35,19 +45,47 @@ fn main() -> Result<(), Box<dyn Error>> {
 
                 match script.execute() {
                     Ok(_) => (),
+                    Err((ComfyError::Exit(code), _)) => exit_code = code,
                     Err((err, file)) => {
+                        let (err, file) = source_map::remap(&path, err, file);
                         err.print(file).unwrap();
+                        exit_code = 1;
                     }
                 };
             }
         }
-        Command::MinifyFile(path) => {
+        Command::MinifyFile(path, options) => {
+            minify_path(&path, &options)?;
+        }
+        Command::FormatFile(path, options) => {
+            format_path(&path, &options)?;
+        }
+        Command::ListTokens(path) => {
             let content = get_file_content(&path)?;
-            let script = ComfyScript::new(path.to_string_lossy(), content);
 
-            let minified_script = script.minify()?;
+            // whitespace is left out, comments being kept for highlighting
+            for token in lex(&content) {
+                if !matches!(token.kind, TokenKind::Whitespace | TokenKind::NewLine) {
+                    println!(
+                        "{}..{} {} {:?}",
+                        token.span.start,
+                        token.span.end,
+                        token.kind,
+                        token.text(&content)
+                    );
+                }
+            }
+        }
+        Command::ListStdlib(name) => {
+            let modules = match name {
+                Some(name) => vec![comfy::get_module(&name)
+                    .ok_or_else(|| format!("Module `{name}` not found!"))?],
+                None => comfy::modules(),
+            };
 
-            generate_minified_file(&path, minified_script.as_bytes())?;
+            for module in modules {
+                print!("{module}");
+            }
         }
         Command::NotFound => {
             return Err("Command not found!".into());
@@ -55,24 +93,67 @@ fn main() -> Result<(), Box<dyn Error>> {
         Command::MissingFileName => {
             return Err("Missing a valid file name!".into());
         }
+        Command::UnknownOption(option) => {
+            return Err(format!("Unknown option `{option}`!").into());
+        }
+        Command::MissingOptionValue(option) => {
+            return Err(format!("Missing a value for option `{option}`!").into());
+        }
+        Command::InvalidOptionValue(option, value) => {
+            return Err(format!("Invalid value `{value}` for option `{option}`!").into());
+        }
     }
 
     let elapsed_time = start_time.elapsed();
-    println!("Execution time: {} microseconds", elapsed_time.as_micros(),);
+    eprintln!("Execution time: {} microseconds", elapsed_time.as_micros(),);
+
+    if exit_code != 0 {
+        process::exit(exit_code);
+    }
     Ok(())
 }
 
 fn get_file_content(file_path: &Path) -> Result<String, Box<dyn Error>> {
-    let file_metadata = fs::metadata(&file_path)?;
+    let file_metadata = fs::metadata(file_path)?;
 
     if file_metadata.len() == 0 {
         return Ok(String::new());
     }
 
-    let file = File::open(&file_path)?;
+    let file = File::open(file_path)?;
     let mut reader = BufReader::new(file);
     let mut content = String::new();
     reader.read_to_string(&mut content)?;
 
     Ok(content)
 }
+
+/// the `.cfs` files of a folder and its subfolders, apart from the `skipped` one and the
+/// scripts minified next to their original
+fn collect_scripts(
+    folder_path: &Path,
+    skipped: Option<&Path>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    for entry in fs::read_dir(folder_path)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            let is_skipped = match skipped {
+                Some(skipped) => std::path::absolute(&path)? == std::path::absolute(skipped)?,
+                None => false,
+            };
+            if !is_skipped {
+                collect_scripts(&path, skipped, files)?;
+            }
+        } else if path.extension().is_some_and(|extension| extension == "cfs")
+            && !path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("minified."))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -1,17 +1,256 @@
-use std::{error::Error, fs, path::Path};
+mod dead_code;
+mod fold;
+mod mangle;
+pub mod source_map;
+pub mod visit;
 
-pub fn generate_minified_file(initial_path: &Path, content: &[u8]) -> Result<(), Box<dyn Error>> {
-    let file_name = initial_path
-        .file_name()
-        .ok_or("Invalid file path!")?
-        .to_string_lossy()
-        .to_string();
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{self, Path, PathBuf},
+};
 
-    let parent_dir = initial_path.parent().unwrap_or_else(|| Path::new(""));
+use self::{
+    dead_code::{Unreachable, Unused},
+    fold::Fold,
+    mangle::mangle,
+    visit::{walk, Visitor},
+};
+use crate::{
+    get_file_content,
+    minify_folder::minify_folder,
+    parser::{
+        ast::{ASTNode, Expression, Statement},
+        parse_input,
+    },
+    script::ComfyScript,
+};
 
-    let new_path = parent_dir.join(format!("minified.{}", file_name));
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MinifyOptions {
+    // parse the minified script back and compare it with the program that was written
+    pub verify: bool,
+    // rename locals, fold constants and remove dead code before writing the script
+    pub aggressive: bool,
+    // write a source map relating the minified script to the original one next to it
+    pub source_map: bool,
+    // where the minified script, or the tree mirroring a minified folder, is written
+    pub out: Option<PathBuf>,
+    // print the minified script rather than writing it
+    pub stdout: bool,
+    // only check that the minified scripts already written are up to date
+    pub check: bool,
+}
+
+#[derive(Debug)]
+pub struct Minified {
+    pub script: String,
+    pub source_map: Option<String>,
+}
+
+impl Minified {
+    pub fn write(&self, output: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = output
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(output, &self.script)?;
+
+        if let Some(source_map) = &self.source_map {
+            fs::write(map_path(output), source_map)?;
+        }
+
+        Ok(())
+    }
+
+    /// whether writing the script to `output` would leave it as it is
+    fn is_written(&self, output: &Path) -> bool {
+        let written = |path: &Path, content: &str| {
+            fs::read_to_string(path).is_ok_and(|written| written == content)
+        };
+
+        written(output, &self.script)
+            && self
+                .source_map
+                .as_ref()
+                .is_none_or(|source_map| written(&map_path(output), source_map))
+    }
+}
+
+/// the size of a script before and after being minified
+#[derive(Debug)]
+pub struct Report {
+    pub path: PathBuf,
+    pub before: usize,
+    pub after: usize,
+    // set by `--check` when the minified script written is missing or out of date
+    pub outdated: bool,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.outdated {
+            return write!(f, "{}: not minified or out of date", self.path.display());
+        }
+
+        // an empty script stays empty
+        let change = match self.before {
+            0 => 0.0,
+            before => 100.0 * (self.after as f64 - before as f64) / before as f64,
+        };
+
+        write!(
+            f,
+            "{}: {} -> {} bytes ({change:+.1}%)",
+            self.path.display(),
+            self.before,
+            self.after
+        )
+    }
+}
+
+/// minifies a script, or every script of a folder, as the options say
+pub fn minify_path(path: &Path, options: &MinifyOptions) -> Result<(), Box<dyn Error>> {
+    if options.stdout && (path.is_dir() || options.out.is_some() || options.check) {
+        return Err("`--stdout` only prints a single script, without `--out` or `--check`!".into());
+    }
+    if options.stdout && options.source_map {
+        return Err("`--source-map` needs the minified script to be written to a file!".into());
+    }
+
+    let output = match &options.out {
+        Some(out) => out.to_owned(),
+        None => default_output(path)?,
+    };
+
+    let reports = if path.is_dir() {
+        minify_folder(path, &output, options)?
+    } else if options.stdout {
+        let script = ComfyScript::new(path.to_string_lossy(), get_file_content(path)?);
+        let minified = script.minify(options, &output)?;
+        print!("{}", minified.script);
+
+        vec![Report {
+            path: path.to_owned(),
+            before: script.content.len(),
+            after: minified.script.len(),
+            outdated: false,
+        }]
+    } else {
+        vec![minify_file(path, &output, options)?]
+    };
+
+    // the report goes along the execution time, the minified script being printed alone
+    for report in &reports {
+        eprintln!("{report}");
+    }
 
-    fs::write(new_path, content)?;
+    match reports.iter().filter(|report| report.outdated).count() {
+        0 => Ok(()),
+        outdated => Err(format!("{outdated} script(s) must be minified again!").into()),
+    }
+}
+
+/// minifies `input` into `output`, only comparing them with `--check`
+pub fn minify_file(
+    input: &Path,
+    output: &Path,
+    options: &MinifyOptions,
+) -> Result<Report, Box<dyn Error>> {
+    let script = ComfyScript::new(input.to_string_lossy(), get_file_content(input)?);
+    let minified = script.minify(options, output)?;
+
+    let outdated = options.check && !minified.is_written(output);
+    if !options.check {
+        minified.write(output)?;
+    }
+
+    Ok(Report {
+        path: input.to_owned(),
+        before: script.content.len(),
+        after: minified.script.len(),
+        outdated,
+    })
+}
+
+/// `minified.<name>` next to the script or folder
+fn default_output(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let path = path::absolute(path)?;
+    let file_name = path.file_name().ok_or("Invalid file path!")?;
+
+    Ok(path.with_file_name(format!("minified.{}", file_name.to_string_lossy())))
+}
+
+/// the source map of a script minified into `output`, written next to it
+fn map_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".map");
+    path.into()
+}
+
+/// rewrites the program into a shorter one doing the same, leaving the names it exports
+/// and imports as they are
+pub fn optimize(program: &mut ASTNode) {
+    walk(&mut Fold, program);
+    walk(&mut Unreachable, program);
+    walk(&mut Unused, program);
+    mangle(program);
+}
+
+/// checks that the script written from the program, `kind` saying how, parses to the
+/// same program, apart from comments, parentheses and the way literals and returns are
+/// spelled
+pub fn verify(program: &ASTNode, written: &str, kind: &str) -> Result<(), Box<dyn Error>> {
+    let reparsed = parse_input(written)
+        .map_err(|_| format!("The {kind} script does not parse, please report this as a bug!"))?;
+
+    let (mut program, mut reparsed) = (program.clone(), reparsed);
+    walk(&mut Normalize, &mut program);
+    walk(&mut Normalize, &mut reparsed);
+
+    let (ASTNode::Program { body: expected }, ASTNode::Program { body: found }) =
+        (&program, &reparsed)
+    else {
+        unreachable!()
+    };
+
+    if let Some(index) =
+        (0..expected.len().max(found.len())).find(|&index| expected.get(index) != found.get(index))
+    {
+        return Err(format!(
+            "The {kind} script differs from the original at statement {}, please report this as a bug!",
+            index + 1
+        )
+        .into());
+    }
 
     Ok(())
 }
+
+struct Normalize;
+
+impl Visitor for Normalize {
+    fn statement(&mut self, node: &mut ASTNode) {
+        if let ASTNode::ReturnStatement { is_shortcut, .. } = node {
+            *is_shortcut = false;
+        }
+    }
+
+    fn statements(&mut self, body: &mut Vec<Statement>) {
+        body.retain(|statement| !statement.node.is_comment());
+    }
+
+    fn expression(&mut self, expr: &mut Expression) {
+        match expr {
+            Expression::Parenthesized(inner) => {
+                let inner = std::mem::replace(&mut **inner, Expression::Array { elements: vec![] });
+                *expr = inner;
+            }
+            Expression::Literal { raw, .. } => raw.clear(),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// removes what can never run or matter: statements following a return, and the
+// declarations of functions whose names are never read
+
+use hashbrown::HashSet;
+
+use super::visit::{walk, walk_function, Visitor};
+use crate::parser::ast::{
+    identifier::Identifier, literal_value::LiteralValue, vars::VariableDeclarator, ASTNode,
+    Expression, Statement,
+};
+
+pub struct Unreachable;
+
+impl Visitor for Unreachable {
+    fn statements(&mut self, body: &mut Vec<Statement>) {
+        if let Some(index) = body
+            .iter()
+            .position(|statement| matches!(statement.node, ASTNode::ReturnStatement { .. }))
+        {
+            body.truncate(index + 1);
+        }
+    }
+}
+
+/// drops the unused locals of every function, the top level being what a script exports
+pub struct Unused;
+
+impl Visitor for Unused {
+    fn function(&mut self, params: &mut [Identifier], body: &mut ASTNode) {
+        walk_function(self, params, body);
+
+        // a removed declaration may have been the only reader of another one
+        loop {
+            let mut reads = Reads::default();
+            walk(&mut reads, body);
+
+            let mut remove = Remove {
+                reads: reads.0,
+                removed: false,
+            };
+            walk(&mut remove, body);
+
+            if !remove.removed {
+                break;
+            }
+        }
+    }
+}
+
+/// the names read anywhere, nested functions included
+#[derive(Default)]
+struct Reads(HashSet<String>);
+
+impl Visitor for Reads {
+    fn identifier(&mut self, id: &mut Identifier, is_binding: bool) {
+        if !is_binding {
+            self.0.insert(id.name.to_owned());
+        }
+    }
+}
+
+/// removes the declarations of a function's own scope that are never read
+struct Remove {
+    reads: HashSet<String>,
+    removed: bool,
+}
+
+impl Visitor for Remove {
+    // the locals of nested functions were handled first
+    fn function(&mut self, _: &mut [Identifier], _: &mut ASTNode) {}
+
+    fn statement(&mut self, node: &mut ASTNode) {
+        if let ASTNode::TryStatement { param, .. } = node {
+            if param
+                .as_ref()
+                .is_some_and(|param| !self.reads.contains(&param.name))
+            {
+                *param = None;
+                self.removed = true;
+            }
+        }
+    }
+
+    fn statements(&mut self, body: &mut Vec<Statement>) {
+        let mut kept = Vec::with_capacity(body.len());
+
+        for Statement { node, span } in body.drain(..) {
+            match node {
+                ASTNode::FunctionDeclaration { ref id, .. } if !self.reads.contains(&id.name) => {
+                    self.removed = true;
+                }
+                ASTNode::VariableDeclaration { declarations, kind } => {
+                    // what remains of the declaration is still written where it was
+                    let mut keep = |node| {
+                        kept.push(Statement {
+                            node,
+                            span: span.clone(),
+                        })
+                    };
+                    let mut used: Vec<VariableDeclarator> = vec![];
+
+                    for declaration in declarations {
+                        if self.reads.contains(&declaration.id.name) {
+                            used.push(declaration);
+                            continue;
+                        }
+
+                        self.removed = true;
+
+                        // the value is still computed for what computing it does
+                        if !is_pure(&declaration.init) {
+                            if !used.is_empty() {
+                                keep(ASTNode::VariableDeclaration {
+                                    declarations: std::mem::take(&mut used),
+                                    kind: kind.clone(),
+                                });
+                            }
+                            keep(ASTNode::ExpressionStatement {
+                                expression: declaration.init,
+                            });
+                        }
+                    }
+
+                    if !used.is_empty() {
+                        keep(ASTNode::VariableDeclaration {
+                            declarations: used,
+                            kind,
+                        });
+                    }
+                }
+                node => kept.push(Statement { node, span }),
+            }
+        }
+
+        *body = kept;
+    }
+}
+
+/// whether evaluating the expression can neither fail nor do anything
+fn is_pure(expr: &Expression) -> bool {
+    match expr {
+        Expression::Literal { value, .. } => !matches!(value, LiteralValue::Regex { .. }),
+        Expression::FnExpression { .. } => true,
+        Expression::Array { elements } => elements.iter().all(is_pure),
+        Expression::Object { properties } => {
+            properties.iter().all(|property| is_pure(&property.value))
+        }
+        Expression::Parenthesized(inner) => is_pure(inner),
+        _ => false,
+    }
+}
@@ -0,0 +1,80 @@
+// constant folding: operators applied to literals are replaced by their result, computed
+// by the interpreter's own operations so that the value is the one the script would get,
+// unless the result is longer to write, as `10/3` is
+
+use super::visit::Visitor;
+use crate::{
+    interpreter::{operations::binary_operation, value::Value},
+    parser::{
+        ast::{literal_value::LiteralValue, quote, Expression},
+        operations::binary::BinaryOperator,
+    },
+};
+
+pub struct Fold;
+
+impl Visitor for Fold {
+    fn expression(&mut self, expr: &mut Expression) {
+        if let Some(folded) = fold(expr) {
+            if folded.to_string().len() <= expr.to_string().len() {
+                *expr = folded;
+            }
+        }
+    }
+}
+
+fn fold(expr: &Expression) -> Option<Expression> {
+    match expr {
+        Expression::Parenthesized(inner) => {
+            value(inner)?;
+            Some((**inner).clone())
+        }
+        Expression::BinaryExpression {
+            left,
+            operator,
+            right,
+        } => {
+            let left_value = value(left)?;
+
+            // `&&` and `||` give one of their operands, whatever the other one is
+            match operator {
+                BinaryOperator::And if left_value.is_truthy() => Some((**right).clone()),
+                BinaryOperator::Or if !left_value.is_truthy() => Some((**right).clone()),
+                BinaryOperator::And | BinaryOperator::Or => Some((**left).clone()),
+                operator => {
+                    let folded = binary_operation(&left_value, *operator, &value(right)?).ok()?;
+                    literal(folded)
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+/// the value of a literal, regexes being left to the runtime
+fn value(expr: &Expression) -> Option<Value> {
+    let Expression::Literal { value, .. } = expr else {
+        return None;
+    };
+
+    match value {
+        LiteralValue::Number(n) => Some(Value::Number(*n)),
+        LiteralValue::Str(s) => Some(Value::str(s)),
+        LiteralValue::Boolean(b) => Some(Value::Bool(*b)),
+        LiteralValue::Nil => Some(Value::Nil),
+        LiteralValue::Regex { .. } => None,
+    }
+}
+
+/// the literal for a value, if it can be written as one
+fn literal(value: Value) -> Option<Expression> {
+    let (value, raw) = match value {
+        Value::Number(n) if n.is_finite() => (LiteralValue::Number(n), n.to_string()),
+        Value::Str(s) => (LiteralValue::Str(s.to_string()), quote(&s)),
+        Value::Bool(b) => (LiteralValue::Boolean(b), b.to_string()),
+        Value::Nil => (LiteralValue::Nil, "nil".to_owned()),
+        _ => return None,
+    };
+
+    Some(Expression::Literal { value, raw })
+}
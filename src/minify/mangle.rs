@@ -0,0 +1,178 @@
+// renames the parameters and locals of functions to the shortest free names; top-level
+// names are what a script exports and imported names stay as the modules spell them
+
+use hashbrown::{HashMap, HashSet};
+
+use super::visit::{walk, walk_function, Visitor};
+use crate::{
+    comfy,
+    parser::ast::{identifier::Identifier, ASTNode},
+    reserved_keywords::RESERVED_KEYWORD,
+};
+
+const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+pub fn mangle(program: &mut ASTNode) {
+    let mut names = Occurrences::default();
+    walk(&mut names, program);
+
+    let mut imports = Imports::default();
+    walk(&mut imports, program);
+
+    // locals with these names may be read before they are declared
+    let mut outer: HashSet<String> = comfy::GLOBALS
+        .iter()
+        .map(|function| function.name.to_owned())
+        .chain(imports.0)
+        .collect();
+
+    let mut exported = Declared::default();
+    walk(&mut exported, program);
+    outer.extend(exported.0);
+
+    let mut taken: HashSet<String> = names.0.into_keys().collect();
+    taken.extend(outer.iter().cloned());
+    taken.extend(RESERVED_KEYWORD.iter().map(|keyword| keyword.to_string()));
+
+    let mut mangler = Mangler {
+        taken,
+        outer,
+        next: 0,
+    };
+    walk(&mut mangler, program);
+}
+
+struct Mangler {
+    // names that a new one must not be equal to
+    taken: HashSet<String>,
+    // names declared where the function being renamed can see them
+    outer: HashSet<String>,
+    next: usize,
+}
+
+impl Mangler {
+    fn fresh(&mut self) -> String {
+        loop {
+            let name = short_name(self.next);
+            self.next += 1;
+
+            if !self.taken.contains(&name) {
+                return name;
+            }
+        }
+    }
+}
+
+impl Visitor for Mangler {
+    fn function(&mut self, params: &mut [Identifier], body: &mut ASTNode) {
+        let mut declared = Declared::default();
+        for param in params.iter_mut() {
+            declared.identifier(param, true);
+        }
+        walk(&mut declared, body);
+
+        let mut occurrences = Occurrences::default();
+        walk_function(&mut occurrences, params, body);
+
+        // the most used names get the shortest replacements
+        let mut locals: Vec<String> = declared
+            .0
+            .into_iter()
+            .filter(|name| !self.outer.contains(name))
+            .collect();
+        locals.sort_by_key(|name| std::cmp::Reverse(occurrences.0[name]));
+
+        let start = self.next;
+        let renames: HashMap<String, String> = locals
+            .into_iter()
+            .map(|name| (name, self.fresh()))
+            .collect();
+
+        // renaming every occurrence the same way keeps what each one refers to, nested
+        // functions included
+        walk_function(&mut Rename(&renames), params, body);
+
+        let outer = self.outer.clone();
+        let mut declared = Declared::default();
+        for param in params.iter_mut() {
+            declared.identifier(param, true);
+        }
+        walk(&mut declared, body);
+        self.outer.extend(declared.0);
+
+        walk_function(self, params, body);
+
+        // sibling functions may reuse the same names
+        self.outer = outer;
+        self.next = start;
+    }
+}
+
+struct Rename<'a>(&'a HashMap<String, String>);
+
+impl Visitor for Rename<'_> {
+    fn identifier(&mut self, id: &mut Identifier, _: bool) {
+        if let Some(name) = self.0.get(&id.name) {
+            id.name = name.to_owned();
+        }
+    }
+}
+
+/// the names declared in a scope, in order, without those of nested functions
+#[derive(Default)]
+struct Declared(Vec<String>);
+
+impl Visitor for Declared {
+    fn function(&mut self, _: &mut [Identifier], _: &mut ASTNode) {}
+
+    fn identifier(&mut self, id: &mut Identifier, is_binding: bool) {
+        if is_binding && !self.0.contains(&id.name) {
+            self.0.push(id.name.to_owned());
+        }
+    }
+}
+
+#[derive(Default)]
+struct Occurrences(HashMap<String, usize>);
+
+impl Visitor for Occurrences {
+    fn identifier(&mut self, id: &mut Identifier, _: bool) {
+        *self.0.entry(id.name.to_owned()).or_default() += 1;
+    }
+}
+
+/// the names bound by imports, `import * from` binding every member of the module
+#[derive(Default)]
+struct Imports(HashSet<String>);
+
+impl Visitor for Imports {
+    fn statement(&mut self, node: &mut ASTNode) {
+        let ASTNode::ImportDeclaration { specifiers, source } = node else {
+            return;
+        };
+
+        for specifier in specifiers {
+            if specifier.local.name != "*" {
+                self.0.insert(specifier.local.name.to_owned());
+            } else if let Some(module) = comfy::get_module(&source.value) {
+                self.0
+                    .extend(module.members().into_iter().map(|(name, _)| name));
+            }
+        }
+    }
+}
+
+/// `a` to `Z`, then `aa`, `ba` and so on
+fn short_name(mut index: usize) -> String {
+    let mut name = String::new();
+
+    loop {
+        name.push(LETTERS[index % LETTERS.len()] as char);
+        index /= LETTERS.len();
+
+        if index == 0 {
+            return name;
+        }
+        index -= 1;
+    }
+}
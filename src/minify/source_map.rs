@@ -0,0 +1,338 @@
+// source maps relating a minified script to the one it was minified from, following the
+// Source Map v3 format: every statement of the minified script is a segment pointing to
+// where the statement was written, lines and columns being counted from zero and columns
+// in UTF-16 code units. Runtime errors of a script naming its map in a last
+// `//# sourceMappingURL=` comment are reported against the original script
+
+use std::{
+    error::Error,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use codespan_reporting::files::SimpleFile;
+
+use super::{
+    map_path,
+    visit::{walk, Visitor},
+};
+use crate::{
+    comfy::json,
+    errors::ComfyError,
+    interpreter::{errors::RuntimeError, value::Value},
+    parser::{
+        ast::{ASTNode, Span, Statement},
+        parse_input,
+    },
+};
+
+const URL_PREFIX: &str = "//# sourceMappingURL=";
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// a line and column of the minified script and the line and column they come from
+type Segment = ((usize, usize), (usize, usize));
+
+/// the map of a script minified from `program` into `output`, the original script being
+/// `source` and holding `content`
+pub fn generate(
+    program: &ASTNode,
+    minified: &str,
+    output: &Path,
+    source: &Path,
+    content: &str,
+) -> Result<String, Box<dyn Error>> {
+    let reparsed = parse_input(minified)
+        .map_err(|_| "The minified script does not parse, please report this as a bug!")?;
+
+    // both programs have the same statements, visited in the same order
+    let mut segments: Vec<Segment> = spans(&reparsed)
+        .into_iter()
+        .zip(spans(program))
+        .map(|(generated, original)| {
+            (
+                position(minified, generated.start),
+                position(content, original.start),
+            )
+        })
+        .collect();
+    segments.sort();
+    segments.dedup_by_key(|(generated, _)| *generated);
+
+    let file = output.file_name().ok_or("Invalid file path!")?;
+    let directory = output.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut map = String::from(r#"{"version":3,"file":"#);
+    json::write_string(&mut map, &file.to_string_lossy());
+    map.push_str(r#","sources":["#);
+    json::write_string(&mut map, &relative(directory, source)?);
+    map.push_str(r#"],"sourcesContent":["#);
+    json::write_string(&mut map, content);
+    map.push_str(r#"],"names":[],"mappings":""#);
+    map.push_str(&encode(&segments));
+    map.push_str("\"}\n");
+
+    Ok(map)
+}
+
+/// the comment ending a script minified into `output`, naming its map
+pub fn url_comment(output: &Path) -> String {
+    let map = map_path(output);
+    let map_name = map.file_name().unwrap_or_default().to_string_lossy();
+
+    format!("\n{URL_PREFIX}{map_name}\n")
+}
+
+/// points a runtime error of a minified script at the statement of the original script
+/// it comes from, any other error being reported against the script itself
+pub fn remap<Name: Display + Clone>(
+    script: &Path,
+    err: ComfyError,
+    file: SimpleFile<Name, String>,
+) -> (ComfyError, SimpleFile<String, String>) {
+    let original = match &err {
+        ComfyError::Runtime(RuntimeError {
+            span: Some(span), ..
+        }) => original(script, file.source(), span),
+        _ => None,
+    };
+
+    match (err, original) {
+        (ComfyError::Runtime(mut err), Some((original, span))) => {
+            err.span = Some(span);
+            (ComfyError::Runtime(err), original)
+        }
+        (err, _) => (
+            err,
+            SimpleFile::new(file.name().to_string(), file.source().to_owned()),
+        ),
+    }
+}
+
+/// the original script and the span there of the statement written at `span`, when the
+/// script names a map that can be read
+fn original(
+    script: &Path,
+    content: &str,
+    span: &Span,
+) -> Option<(SimpleFile<String, String>, Span)> {
+    let url = content
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix(URL_PREFIX))?;
+    let directory = script.parent().unwrap_or_else(|| Path::new(""));
+
+    let map = json::parse(&fs::read_to_string(directory.join(url.trim())).ok()?).ok()?;
+    let Value::Object(map) = map else {
+        return None;
+    };
+    let map = map.borrow();
+
+    // only the first source is read, as minified scripts have a single one
+    let first = |field: &str| match map.get(field)? {
+        Value::Array(values) => match values.borrow().first()? {
+            Value::Str(value) => Some(value.to_string()),
+            _ => None,
+        },
+        _ => None,
+    };
+    let source = directory.join(first("sources")?);
+    let original = match first("sourcesContent") {
+        Some(original) => original,
+        None => fs::read_to_string(&source).ok()?,
+    };
+
+    let Value::Str(mappings) = map.get("mappings")? else {
+        return None;
+    };
+    let generated = position(content, span.start);
+    let (_, (line, column)) = decode(mappings)?
+        .into_iter()
+        .filter(|(segment, _)| *segment <= generated)
+        .max()?;
+    let start = offset(&original, line, column)?;
+
+    // the whole statement, as for errors of the original script
+    let span = parse_input(&original)
+        .ok()
+        .and_then(|program| spans(&program).into_iter().find(|span| span.start == start))
+        .unwrap_or(start..start);
+
+    Some((
+        SimpleFile::new(source.display().to_string(), original),
+        span,
+    ))
+}
+
+/// the path leading to `path` from `directory`, as written in URLs
+fn relative(directory: &Path, path: &Path) -> Result<String, Box<dyn Error>> {
+    let absolute = |path: &Path| match path.as_os_str().is_empty() {
+        true => std::path::absolute("."),
+        false => std::path::absolute(path),
+    };
+    let (directory, path) = (absolute(directory)?, absolute(path)?);
+
+    let common = directory
+        .components()
+        .zip(path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in directory.components().skip(common) {
+        relative.push("..");
+    }
+    relative.extend(path.components().skip(common));
+
+    Ok(relative.to_string_lossy().replace('\\', "/"))
+}
+
+/// the spans of the statements of a program, comments apart
+fn spans(program: &ASTNode) -> Vec<Span> {
+    struct Spans(Vec<Span>);
+
+    impl Visitor for Spans {
+        fn statements(&mut self, body: &mut Vec<Statement>) {
+            let written = body.iter().filter(|statement| !statement.node.is_comment());
+            self.0
+                .extend(written.map(|statement| statement.span.clone()));
+        }
+    }
+
+    let mut spans = Spans(Vec::new());
+    walk(&mut spans, &mut program.clone());
+    spans.0
+}
+
+/// the line and column of a byte offset
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+    (
+        before.matches('\n').count(),
+        before[line_start..].encode_utf16().count(),
+    )
+}
+
+/// the byte offset of a line and column
+fn offset(text: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start = match line {
+        0 => 0,
+        line => text.match_indices('\n').nth(line - 1)?.0 + 1,
+    };
+
+    let mut units = 0;
+    for (index, c) in text[line_start..].char_indices() {
+        if units >= column || c == '\n' {
+            return (units == column).then_some(line_start + index);
+        }
+        units += c.len_utf16();
+    }
+
+    (units == column).then_some(text.len())
+}
+
+/// the `mappings` field: lines are separated by `;` and segments by `,`, a segment holding
+/// the column, the source, the original line and the original column, each one relative
+/// to the previous segment, the column to the previous one on the same line
+fn encode(segments: &[Segment]) -> String {
+    let mut mappings = String::new();
+    let (mut line, mut column) = (0, 0);
+    let mut previous = (0, 0);
+
+    for &((generated_line, generated_column), original) in segments {
+        if generated_line > line {
+            mappings.push_str(&";".repeat(generated_line - line));
+            (line, column) = (generated_line, 0);
+        } else if !mappings.is_empty() {
+            mappings.push(',');
+        }
+
+        for value in [
+            generated_column as i64 - column as i64,
+            0,
+            original.0 as i64 - previous.0 as i64,
+            original.1 as i64 - previous.1 as i64,
+        ] {
+            write_vlq(&mut mappings, value);
+        }
+        (column, previous) = (generated_column, original);
+    }
+
+    mappings
+}
+
+fn decode(mappings: &str) -> Option<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut previous = (0, 0);
+
+    for (line, segments_on_line) in mappings.split(';').enumerate() {
+        let mut column = 0;
+
+        for segment in segments_on_line.split(',').filter(|s| !s.is_empty()) {
+            let values = read_vlqs(segment)?;
+            column += values[0];
+
+            // segments of a single field point to nothing
+            if let [_, _, line_delta, column_delta, ..] = values[..] {
+                previous = (previous.0 + line_delta, previous.1 + column_delta);
+                segments.push((
+                    (line, usize::try_from(column).ok()?),
+                    (
+                        usize::try_from(previous.0).ok()?,
+                        usize::try_from(previous.1).ok()?,
+                    ),
+                ));
+            }
+        }
+    }
+
+    Some(segments)
+}
+
+/// a base64 VLQ: the sign is the lowest bit of the value, then written five bits at a
+/// time from the lowest ones, every digit but the last having its sixth bit set
+fn write_vlq(output: &mut String, value: i64) {
+    let mut rest = (value.unsigned_abs() << 1) | u64::from(value < 0);
+
+    loop {
+        let digit = rest & 31;
+        rest >>= 5;
+
+        let continued = if rest > 0 { 32 } else { 0 };
+        output.push(BASE64[(digit | continued) as usize] as char);
+
+        if rest == 0 {
+            break;
+        }
+    }
+}
+
+fn read_vlqs(segment: &str) -> Option<Vec<i64>> {
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0i64, 0);
+
+    for byte in segment.bytes() {
+        let digit = BASE64.iter().position(|&b| b == byte)? as i64;
+        if shift > 55 {
+            return None;
+        }
+        value |= (digit & 31) << shift;
+
+        if digit & 32 == 0 {
+            let magnitude = value >> 1;
+            values.push(if value & 1 == 1 {
+                -magnitude
+            } else {
+                magnitude
+            });
+            (value, shift) = (0, 0);
+        } else {
+            shift += 5;
+        }
+    }
+
+    Some(values)
+}
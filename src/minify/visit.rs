@@ -0,0 +1,157 @@
+// a mutable walk over the tree shared by the minifier passes, hooks being called once
+// the children of what they get were visited
+
+use std::sync::Arc;
+
+use crate::parser::ast::{identifier::Identifier, ASTNode, Expression, Statement};
+
+pub trait Visitor: Sized {
+    fn statement(&mut self, _node: &mut ASTNode) {}
+
+    /// the statements of the program and of every block
+    fn statements(&mut self, _body: &mut Vec<Statement>) {}
+
+    fn expression(&mut self, _expr: &mut Expression) {}
+
+    /// a variable, declared when `is_binding`; property names and imports are not visited
+    fn identifier(&mut self, _id: &mut Identifier, _is_binding: bool) {}
+
+    /// the parameters and body of a function, walked into unless overridden
+    fn function(&mut self, params: &mut [Identifier], body: &mut ASTNode) {
+        walk_function(self, params, body);
+    }
+}
+
+pub fn walk_function(visitor: &mut impl Visitor, params: &mut [Identifier], body: &mut ASTNode) {
+    for param in params {
+        visitor.identifier(param, true);
+    }
+    walk(visitor, body);
+}
+
+pub fn walk(visitor: &mut impl Visitor, node: &mut ASTNode) {
+    match node {
+        ASTNode::Program { body } | ASTNode::BlockStatement { body } => {
+            for statement in body.iter_mut() {
+                walk(visitor, &mut statement.node);
+            }
+            visitor.statements(body);
+        }
+        ASTNode::ImportDeclaration { .. } => {}
+        ASTNode::VariableDeclaration { declarations, .. } => {
+            for declaration in declarations {
+                walk_expression(visitor, &mut declaration.init);
+                visitor.identifier(&mut declaration.id, true);
+            }
+        }
+        ASTNode::ExpressionStatement { expression } => walk_expression(visitor, expression),
+        ASTNode::FunctionDeclaration {
+            id, params, body, ..
+        } => {
+            visitor.identifier(id, true);
+            visitor.function(params, Arc::make_mut(body));
+        }
+        ASTNode::ForStatement {
+            declarations,
+            source,
+            body,
+            ..
+        } => {
+            walk_expression(visitor, source);
+            for declaration in declarations {
+                visitor.identifier(declaration, true);
+            }
+            walk(visitor, Arc::make_mut(body));
+        }
+        ASTNode::WhileStatement { test, body } => {
+            walk_expression(visitor, test);
+            walk(visitor, Arc::make_mut(body));
+        }
+        ASTNode::IfStatement {
+            test,
+            body,
+            alternate,
+        } => {
+            walk_expression(visitor, test);
+            walk(visitor, Arc::make_mut(body));
+            if let Some(alternate) = alternate {
+                walk(visitor, Arc::make_mut(alternate));
+            }
+        }
+        ASTNode::TryStatement {
+            block,
+            param,
+            handler,
+        } => {
+            walk(visitor, Arc::make_mut(block));
+            if let Some(param) = param {
+                visitor.identifier(param, true);
+            }
+            walk(visitor, Arc::make_mut(handler));
+        }
+        ASTNode::ReturnStatement { argument, .. } | ASTNode::YieldStatement { argument } => {
+            walk_expression(visitor, argument)
+        }
+    }
+
+    visitor.statement(node);
+}
+
+pub fn walk_expression(visitor: &mut impl Visitor, expr: &mut Expression) {
+    match expr {
+        Expression::Literal { .. } | Expression::Comment { .. } => {}
+        Expression::TemplateLiteral { expressions, .. } => {
+            for expr in expressions {
+                walk_expression(visitor, expr);
+            }
+        }
+        Expression::Range { from, to, step, .. } => {
+            for part in [from, to, step].into_iter().flatten() {
+                walk_expression(visitor, part);
+            }
+        }
+        Expression::Array { elements } => {
+            for element in elements {
+                walk_expression(visitor, element);
+            }
+        }
+        Expression::Object { properties } => {
+            for property in properties {
+                walk_expression(visitor, &mut property.value);
+            }
+        }
+        Expression::BinaryExpression { left, right, .. } => {
+            walk_expression(visitor, left);
+            walk_expression(visitor, right);
+        }
+        Expression::MemberExpression {
+            indexed,
+            property,
+            computed,
+        } => {
+            walk_expression(visitor, indexed);
+            // `a.b` names a property rather than a variable
+            if *computed {
+                walk_expression(visitor, property);
+            }
+        }
+        Expression::CallExpression { callee, args } => {
+            walk_expression(visitor, callee);
+            for arg in args {
+                walk_expression(visitor, arg);
+            }
+        }
+        Expression::AssignmentExpression { id, assigned, .. } => {
+            walk_expression(visitor, id);
+            walk_expression(visitor, assigned);
+        }
+        Expression::IdentifierExpression(id) => visitor.identifier(id, false),
+        Expression::Parenthesized(inner) => walk_expression(visitor, inner),
+        Expression::FnExpression { params, body, .. } => {
+            visitor.function(params, Arc::make_mut(body))
+        }
+        Expression::AwaitExpression { argument } => walk_expression(visitor, argument),
+    }
+
+    visitor.expression(expr);
+}
@@ -0,0 +1,55 @@
+use std::{
+    error::Error,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    collect_scripts,
+    minify::{minify_file, MinifyOptions, Report},
+};
+
+/// minifies every script of a folder and its subfolders into the same tree under `out`
+pub fn minify_folder(
+    folder_path: &Path,
+    out: &Path,
+    options: &MinifyOptions,
+) -> Result<Vec<Report>, Box<dyn Error>> {
+    let mut files = vec![];
+    collect_scripts(folder_path, Some(out), &mut files)?;
+    files.sort();
+
+    let reports = Arc::new(Mutex::new(vec![]));
+    let errors = Arc::new(Mutex::new(vec![]));
+
+    files.par_iter().for_each(|file_path| {
+        let relative = file_path.strip_prefix(folder_path).unwrap_or(file_path);
+
+        match minify_file(file_path, &out.join(relative), options) {
+            Ok(report) => reports.lock().unwrap().push(report),
+            Err(e) => errors
+                .lock()
+                .unwrap()
+                .push(format!("{}: {e}", file_path.display())),
+        }
+    });
+
+    let errors = Arc::try_unwrap(errors).expect("Failed to unwrap errors Arc");
+    let errors_list = errors.into_inner().unwrap();
+
+    for err in &errors_list {
+        eprintln!("\x1b[31mError\x1b[0m: {err}");
+    }
+
+    if !errors_list.is_empty() {
+        return Err(format!("{} script(s) could not be minified!", errors_list.len()).into());
+    }
+
+    let reports = Arc::try_unwrap(reports).expect("Failed to unwrap reports Arc");
+    let mut reports = reports.into_inner().unwrap();
+    reports.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(reports)
+}
@@ -1,43 +1,58 @@
-mod assignment;
+pub mod assignment;
 pub mod ast;
 pub mod comment;
+pub mod cst;
 pub mod errors;
 pub mod expression;
 mod function;
 mod if_block;
 mod import;
+pub mod lexer;
 mod loop_for;
 mod loop_while;
-mod operations;
+pub mod operations;
+mod try_catch;
 
 use self::{
     assignment::{initial::parse_var_init, reassign::parse_assignment},
-    ast::ASTNode,
+    ast::{ASTNode, Statement},
     comment::parse_comment_statement,
     expression::parse_expression_statement,
-    function::{parse_function, return_expression::parse_return_statement},
+    function::{
+        parse_function, return_expression::parse_return_statement,
+        yield_statement::parse_yield_statement,
+    },
     if_block::parse_if_statement,
     loop_for::parse_for_statement,
     loop_while::parse_while_statement,
+    try_catch::parse_try_statement,
 };
 use crate::parser::import::parse_import;
 use nom::{
     branch::alt,
-    bytes::complete::take_while1,
+    bytes::complete::take_while,
     character::complete::{char, space0},
     multi::many0,
     IResult, Parser,
 };
 use nom_supreme::{error::ErrorTree, final_parser::final_parser, ParserExt};
+use std::cell::Cell;
+
+thread_local! {
+    // the length of the script being parsed, parsers only seeing what remains of it
+    static INPUT_LENGTH: Cell<usize> = const { Cell::new(0) };
+}
 
 pub fn parse_input(input: &str) -> Result<ASTNode, ErrorTree<&str>> {
+    INPUT_LENGTH.with(|length| length.set(input.len()));
+
     final_parser(parse_code)(input)
 }
 
-fn parse_code<'a>(input: &'a str) -> IResult<&'a str, ASTNode, ErrorTree<&'a str>> {
+fn parse_code(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
     let (input, _) = parse_new_lines.opt().parse(input)?;
 
-    let (input, statements) = many0(parse_statement.terminated(parse_new_lines.opt()))
+    let (input, statements) = many0(parse_located_statement.terminated(parse_new_lines.opt()))
         .cut()
         .all_consuming()
         .parse(input)?;
@@ -45,7 +60,7 @@ fn parse_code<'a>(input: &'a str) -> IResult<&'a str, ASTNode, ErrorTree<&'a str
     Ok((input, ASTNode::Program { body: statements }))
 }
 
-fn parse_block<'a>(input: &'a str) -> IResult<&'a str, ASTNode, ErrorTree<&'a str>> {
+fn parse_block(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
     let (input, _) = char('{')
         .opt_preceded_by(parse_new_lines)
         .cut()
@@ -54,7 +69,7 @@ fn parse_block<'a>(input: &'a str) -> IResult<&'a str, ASTNode, ErrorTree<&'a st
 
     let (input, _) = parse_new_lines.opt().parse(input)?;
 
-    let (input, statements) = many0(parse_statement.delimited_by(parse_new_lines.opt()))
+    let (input, statements) = many0(parse_located_statement.delimited_by(parse_new_lines.opt()))
         .cut()
         .parse(input)?;
 
@@ -67,6 +82,21 @@ fn parse_block<'a>(input: &'a str) -> IResult<&'a str, ASTNode, ErrorTree<&'a st
     Ok((input, ASTNode::BlockStatement { body: statements }))
 }
 
+/// a statement with its span, which leaves out the spaces following it
+fn parse_located_statement(input: &str) -> IResult<&str, Statement, ErrorTree<&str>> {
+    let (rest, node) = parse_statement(input)?;
+
+    let written = input[..input.len() - rest.len()].trim_end();
+    let start = INPUT_LENGTH.with(Cell::get) - input.len();
+
+    let statement = Statement {
+        node,
+        span: start..start + written.len(),
+    };
+
+    Ok((rest, statement))
+}
+
 fn parse_statement(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
     alt((
         parse_var_init,
@@ -75,8 +105,10 @@ fn parse_statement(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
         parse_for_statement,
         parse_while_statement,
         parse_if_statement,
+        parse_try_statement,
         parse_function,
         parse_return_statement,
+        parse_yield_statement,
         parse_comment_statement,
         parse_expression_statement,
     ))
@@ -85,7 +117,7 @@ fn parse_statement(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
 
 fn parse_new_lines(i: &str) -> IResult<&str, &str, ErrorTree<&str>> {
     let (i, _) = space0(i)?;
-    let (i, removed) = take_while1(|c: char| c == ';' || c.is_ascii_whitespace()).parse(i)?;
+    let (i, removed) = take_while(|c: char| c == ';' || c.is_ascii_whitespace()).parse(i)?;
 
     Ok((i, removed))
 }
@@ -73,11 +73,19 @@ pub fn parse_single_declaration(input: &str) -> IResult<&str, VariableDeclarator
         return Ok((input, declarator));
     }
 
-    let (input, _) = alt((tag("\n"), tag(","), tag(";"), tag("//").complete()))
-        .peek()
-        .context("unexpected")
-        .cut()
-        .parse(input)?;
+    let (input, _) = alt((
+        tag("\n"),
+        tag("\r\n"),
+        tag(","),
+        tag(";"),
+        tag("}"),
+        tag("//").complete(),
+        tag("/*").complete(),
+    ))
+    .peek()
+    .context("unexpected")
+    .cut()
+    .parse(input)?;
 
     Ok((input, declarator))
 }
@@ -2,20 +2,17 @@ use nom::{branch::alt, character::complete::space0, IResult, Parser};
 use nom_supreme::{error::ErrorTree, tag::complete::tag, ParserExt};
 
 use crate::parser::{
-    ast::{identifier::parse_identifier_expression, ASTNode, Expression},
+    ast::{ASTNode, Expression},
     comment::jump_comments,
-    expression::{indexing::parse_indexing, member_expr::parse_member_expr, parse_expression},
+    expression::{member_expr::parse_member_expr, parse_expression},
     operations::assignment::parse_assignment_operator,
 };
 
 pub fn parse_assignment(i: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
-    let (i, id) = alt((
-        parse_indexing,
-        parse_member_expr,
-        parse_identifier_expression,
-    ))
-    .map(|e| Box::new(e))
-    .parse(i)?;
+    let (i, id) = parse_member_expr
+        .verify(is_assignable)
+        .map(Box::new)
+        .parse(i)?;
 
     let (i, _) = jump_comments(i)?;
 
@@ -37,11 +34,26 @@ pub fn parse_assignment(i: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
         return Ok((i, expr_statement));
     }
 
-    let (i, _) = alt((tag("\n"), tag(","), tag(";"), tag("//").complete()))
-        .peek()
-        .context("unexpected")
-        .cut()
-        .parse(i)?;
+    let (i, _) = alt((
+        tag("\n"),
+        tag("\r\n"),
+        tag(","),
+        tag(";"),
+        tag("}"),
+        tag("//").complete(),
+        tag("/*").complete(),
+    ))
+    .peek()
+    .context("unexpected")
+    .cut()
+    .parse(i)?;
 
     Ok((i, expr_statement))
 }
+
+fn is_assignable(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::IdentifierExpression(_) | Expression::MemberExpression { .. }
+    )
+}
@@ -17,12 +17,29 @@ use super::{
     assignment::initial::VariableKeyword,
     operations::{assignment::AssignmentOperator, binary::BinaryOperator},
 };
-use std::fmt;
+use std::{fmt, ops::Range, sync::Arc};
 
+/// byte offsets into the script
+pub type Span = Range<usize>;
+
+/// a statement of a program or a block, with where it was written
 #[derive(Debug, Clone)]
+pub struct Statement {
+    pub node: ASTNode,
+    pub span: Span,
+}
+
+// statements are the same wherever they were written
+impl PartialEq for Statement {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ASTNode {
     Program {
-        body: Vec<ASTNode>,
+        body: Vec<Statement>,
     },
     ImportDeclaration {
         specifiers: Vec<ImportSpecifier>,
@@ -40,37 +57,53 @@ pub enum ASTNode {
         id: Identifier,
         // if None then anon func
         params: Vec<Identifier>,
-        body: Box<ASTNode>,
+        // bodies are shared by the functions, loops and generators running them, threads
+        // included, rather than copied each time
+        body: Arc<ASTNode>,
 
         is_shortcut: bool,
         // if is_shortcut == true then body = ASTNode::ReturnStatement
+
+        // calls return a promise
+        is_async: bool,
     },
     ForStatement {
         declarations: Vec<Identifier>,
         kind: VariableKeyword,
         source: Expression,
-        body: Box<ASTNode>,
+        body: Arc<ASTNode>,
     },
     WhileStatement {
         test: Expression,
-        body: Box<ASTNode>,
+        body: Arc<ASTNode>,
     },
     IfStatement {
         test: Expression,
-        body: Box<ASTNode>,
-        alternate: Option<Box<ASTNode>>,
+        body: Arc<ASTNode>,
+        alternate: Option<Arc<ASTNode>>,
         // alternate may either be None, a BlockStatement or an IfStatement
     },
     BlockStatement {
-        body: Vec<ASTNode>,
+        body: Vec<Statement>,
+    },
+    TryStatement {
+        block: Arc<ASTNode>,
+        // the identifier the caught error is bound to, if any
+        param: Option<Identifier>,
+        handler: Arc<ASTNode>,
     },
     ReturnStatement {
         argument: Expression,
         is_shortcut: bool,
     },
+    // suspends the generator the statement belongs to, handing out the argument
+    YieldStatement {
+        argument: Expression,
+    },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names, dead_code)]
 pub enum Expression {
     Literal {
         value: LiteralValue, // can be either a string or a number
@@ -82,10 +115,12 @@ pub enum Expression {
         // syntax like this: #"hey {name}, I am {age} years old"
     },
     Range {
-        // similar to rust for instance 0..10
-        from: Box<Expression>,
+        // similar to rust for instance 0..10, either end may be omitted as in `..10` or `2..`
+        from: Option<Box<Expression>>,
         limits: RangeType,
-        to: Box<Expression>,
+        to: Option<Box<Expression>>,
+        // the distance between values, as in `0..100 step 5`
+        step: Option<Box<Expression>>,
     },
     Array {
         elements: Vec<Expression>,
@@ -122,64 +157,51 @@ pub enum Expression {
     },
     FnExpression {
         params: Vec<Identifier>,
-        body: Box<ASTNode>,
+        body: Arc<ASTNode>,
         is_shortcut: bool,
+        is_async: bool,
+    },
+    AwaitExpression {
+        argument: Box<Expression>,
     },
 }
 
-// display is used to minify the content
+// display is used to minify the content: comments are dropped and only the spaces,
+// separators and parentheses needed to parse the same tree back are written
 impl fmt::Display for ASTNode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ASTNode::Program { body, .. } => Ok(for node in body {
-                write!(f, "{}", node)?;
-            }),
+            ASTNode::Program { body } => write_statements(f, body),
             ASTNode::ImportDeclaration { specifiers, source } => {
                 write!(f, "import ")?;
+                write_list(f, specifiers)?;
 
-                for (i, specifier) in specifiers.iter().enumerate() {
-                    if i == specifiers.len() - 1 {
-                        write!(f, "{}", specifier)?;
-                    } else {
-                        write!(f, "{},", specifier)?;
-                    }
+                // only a specifier list has to be separated from `from`
+                match specifiers.as_slice() {
+                    [specifier] if specifier.local.name == "*" => write!(f, "from {source}"),
+                    _ => write!(f, " from {source}"),
                 }
-
-                write!(f, " from \"{}\";", source)
             }
             ASTNode::VariableDeclaration { declarations, kind } => {
-                write!(f, "{} ", kind)?;
-
-                for (i, declaration) in declarations.iter().enumerate() {
-                    if i == declarations.len() - 1 {
-                        write!(f, "{}", declaration)?;
-                    } else {
-                        write!(f, "{},", declaration)?;
-                    }
-                }
-
-                write!(f, ";")
-            }
-            ASTNode::ExpressionStatement { expression } => {
-                write!(f, "{};", expression)
+                write!(f, "{kind} ")?;
+                write_list(f, declarations)
             }
+            ASTNode::ExpressionStatement { expression } => write!(f, "{expression}"),
             ASTNode::FunctionDeclaration {
-                id, params, body, ..
+                id,
+                params,
+                body,
+                is_async,
+                ..
             } => {
-                write!(f, "fn {}(", id.clone())?;
-
-                for (i, param) in params.into_iter().enumerate() {
-                    if i == params.len() - 1 {
-                        write!(f, "{}", param)?;
-                    } else {
-                        write!(f, "{},", param)?;
-                    }
+                if *is_async {
+                    write!(f, "async ")?;
                 }
+                write!(f, "fn {id}(")?;
+                write_list(f, params)?;
 
-                write!(f, ")")?;
-
-                write!(f, " {}", body)
-                // either put a block statement or a return statement (with shortcut)
+                // either a block statement or a return statement (with shortcut)
+                write!(f, "){body}")
             }
             ASTNode::ForStatement {
                 declarations,
@@ -187,56 +209,57 @@ impl fmt::Display for ASTNode {
                 body,
                 kind,
             } => {
-                write!(f, "for {kind} ")?;
+                write!(f, "for ")?;
 
-                for declaration in declarations {
-                    write!(f, "{},", declaration)?;
+                // `let` is implied, unless the first name would be read as the keyword
+                let first = &declarations[0].name;
+                if *kind == VariableKeyword::Var
+                    || first.starts_with("let")
+                    || first.starts_with("var")
+                {
+                    write!(f, "{kind} ")?;
                 }
-                write!(f, " in ")?;
-                write!(f, "{}", source)?;
-
-                write!(f, " {}", body)
-            }
-            ASTNode::WhileStatement { test, body } => {
-                write!(f, "while ")?;
 
-                write!(f, "{test}")?;
-
-                write!(f, " {}", body)
-            }
-            ASTNode::ReturnStatement {
-                argument,
-                is_shortcut,
-            } => {
-                if *is_shortcut {
-                    write!(f, ">> ")?;
-                } else {
-                    write!(f, "return ")?;
-                }
-
-                write!(f, "{}", argument)
+                write_list(f, declarations)?;
+                write!(f, " in {source}{body}")
             }
+            ASTNode::WhileStatement { test, body } => write!(f, "while {test}{body}"),
+            // `>>` returns from any function, not only from shortcut ones
+            ASTNode::ReturnStatement { argument, .. } => write!(f, ">>{argument}"),
+            ASTNode::YieldStatement { argument } => write_keyword(f, "yield", argument),
             ASTNode::IfStatement {
                 test,
                 body,
                 alternate,
             } => {
-                write!(f, "if {test} {body}")?;
+                write!(f, "if {test}{body}")?;
 
-                if let Some(alternate) = alternate {
-                    write!(f, " else ")?;
-                    write!(f, "{alternate}")
-                } else {
-                    write!(f, "")
+                match alternate {
+                    Some(alternate) if matches!(**alternate, ASTNode::IfStatement { .. }) => {
+                        write!(f, "else {alternate}")
+                    }
+                    Some(alternate) => write!(f, "else{alternate}"),
+                    None => Ok(()),
                 }
             }
             ASTNode::BlockStatement { body } => {
-                write!(f, " {{")?;
-                for node in body {
-                    write!(f, "{}", node)?;
-                }
+                write!(f, "{{")?;
+                write_statements(f, body)?;
                 write!(f, "}}")
             }
+            ASTNode::TryStatement {
+                block,
+                param,
+                handler,
+            } => {
+                write!(f, "try{block}catch")?;
+
+                if let Some(param) = param {
+                    write!(f, " {param}")?;
+                }
+
+                write!(f, "{handler}")
+            }
         }
     }
 }
@@ -244,28 +267,32 @@ impl fmt::Display for ASTNode {
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Expression::Literal { raw, .. } => {
-                write!(f, "{}", raw)
-            }
-            Expression::TemplateLiteral { value, .. } => {
-                write!(f, "{}", value)? //todo! update n the future
-                ;
-                todo!()
+            Expression::Literal {
+                value: LiteralValue::Number(n),
+                raw,
+            } => write!(f, "{}", shortest_number(*n, raw)),
+            Expression::Literal {
+                value: LiteralValue::Str(value),
+                raw,
+            } => {
+                let quoted = quote(value);
+
+                if quoted.len() < raw.len() {
+                    write!(f, "{quoted}")
+                } else {
+                    write!(f, "{raw}")
+                }
             }
+            Expression::Literal { raw, .. } => write!(f, "{raw}"),
+            Expression::TemplateLiteral { value, .. } => write!(f, "{}", quote(value)),
             Expression::Array { elements } => {
                 write!(f, "[")?;
-                for element in elements {
-                    write!(f, "{},", element)?;
-                }
-
+                write_list(f, elements)?;
                 write!(f, "]")
             }
             Expression::Object { properties } => {
                 write!(f, "{{")?;
-                for prop in properties {
-                    write!(f, "{},", prop)?;
-                }
-
+                write_list(f, properties)?;
                 write!(f, "}}")
             }
             Expression::BinaryExpression {
@@ -273,79 +300,284 @@ impl fmt::Display for Expression {
                 operator,
                 right,
             } => {
-                write!(f, "{}", left)?;
-                write!(f, "{}", operator)?;
-                write!(f, "{}", right)
+                let precedence = operator.get_precedence();
+                let left = Operand(left, Position::Left(precedence)).to_string();
+                let operator = operator.to_string();
+                let right = Operand(right, Position::Right(precedence)).to_string();
+
+                write_joined(f, &[&left, &operator, &right])
             }
             Expression::MemberExpression {
-                indexed, property, ..
+                indexed,
+                property,
+                computed,
             } => {
-                write!(f, "{}.{}", indexed, property)
+                let indexed = Operand(indexed, Position::Receiver);
+
+                if *computed {
+                    write!(f, "{indexed}[{property}]")
+                } else {
+                    write!(f, "{indexed}.{property}")
+                }
             }
             Expression::CallExpression { callee, args } => {
-                write!(f, "{}(", callee)?;
-                for (i, arg) in args.into_iter().enumerate() {
-                    if i == args.len() - 1 {
-                        write!(f, "{}", arg)?;
-                    } else {
-                        write!(f, "{},", arg)?;
-                    }
-                }
-
+                write!(f, "{}(", Operand(callee, Position::Receiver))?;
+                write_list(f, args)?;
                 write!(f, ")")
             }
             Expression::AssignmentExpression {
                 operator,
                 id,
                 assigned,
+            } => write!(f, "{id}{operator}{assigned}"),
+            Expression::IdentifierExpression(identifier) => write!(f, "{identifier}"),
+            // the parentheses that are needed are written around the operands
+            Expression::Parenthesized(expr) => write!(f, "{expr}"),
+            Expression::Comment { raw_value, .. } => write!(f, "{raw_value}"),
+            Expression::FnExpression {
+                params,
+                body,
+                is_async,
+                ..
             } => {
-                write!(f, "{}", id)?;
-                write!(f, "{}", operator)?;
-                write!(f, "{}", assigned)
-            }
-            Expression::IdentifierExpression(identifier) => {
-                write!(f, "{}", identifier)
+                if *is_async {
+                    write!(f, "async ")?;
+                }
+                write!(f, "|")?;
+                write_list(f, params)?;
+                write!(f, "|{body}")
             }
-            Expression::Parenthesized(expr) => {
-                write!(f, "({})", expr)
+            Expression::AwaitExpression { argument } => {
+                write_keyword(f, "await", &Operand(argument, Position::Awaited))
             }
-            Expression::Comment { raw_value, .. } => {
-                write!(f, "{}", raw_value)
+            Expression::Range {
+                from,
+                limits,
+                to,
+                step,
+            } => {
+                if let Some(from) = from {
+                    write!(f, "{}", Operand(from, Position::RangePart))?;
+                }
+                write!(f, "{limits}")?;
+                if let Some(to) = to {
+                    write!(f, "{}", Operand(to, Position::RangePart))?;
+                }
+                if let Some(step) = step {
+                    write!(f, " step {}", Operand(step, Position::RangePart))?;
+                }
+                Ok(())
             }
-            Expression::FnExpression { params, body, .. } => {
-                write!(f, "|")?;
+        }
+    }
+}
 
-                for (i, param) in params.into_iter().enumerate() {
-                    if i == params.len() - 1 {
-                        write!(f, "{}", param)?;
-                    } else {
-                        write!(f, "{},", param)?;
-                    }
+/// where an expression is written, which decides whether it needs parentheses
+#[derive(Clone, Copy)]
+enum Position {
+    // operands of a binary operator with the given precedence
+    Left(u8),
+    Right(u8),
+    // the parts of a range, which are parsed without ranges of their own
+    RangePart,
+    // what `.property`, `[index]` or `(args)` apply to
+    Receiver,
+    // the argument of `await`, parsed up to its postfix forms
+    Awaited,
+}
+
+/// an expression written in parentheses when its position requires them
+struct Operand<'a>(&'a Expression, Position);
+
+impl fmt::Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Operand(mut expr, position) = *self;
+
+        while let Expression::Parenthesized(inner) = expr {
+            expr = inner;
+        }
+
+        // a shortcut function returns the whole expression following `>>`
+        let is_loose = matches!(
+            expr,
+            Expression::Range { .. }
+                | Expression::AssignmentExpression { .. }
+                | Expression::FnExpression {
+                    is_shortcut: true,
+                    ..
                 }
+        );
 
-                write!(f, "|")?;
+        let needs_parens = is_loose
+            || match (position, expr) {
+                (Position::Left(precedence), Expression::BinaryExpression { operator, .. }) => {
+                    operator.get_precedence() < precedence
+                }
+                (Position::Right(precedence), Expression::BinaryExpression { operator, .. }) => {
+                    operator.get_precedence() <= precedence
+                }
+                (Position::Receiver | Position::Awaited, Expression::BinaryExpression { .. }) => {
+                    true
+                }
+                // `1.5.floor()` would read the second dot as a decimal point
+                (
+                    Position::Receiver,
+                    Expression::AwaitExpression { .. }
+                    | Expression::Literal {
+                        value: LiteralValue::Number(_),
+                        ..
+                    },
+                ) => true,
+                _ => false,
+            };
 
-                write!(f, " {}", body)
+        if needs_parens {
+            write!(f, "({expr})")
+        } else {
+            write!(f, "{expr}")
+        }
+    }
+}
+
+/// the statements without comments, separated unless the previous one ends with a block
+fn write_statements(f: &mut fmt::Formatter<'_>, body: &[Statement]) -> fmt::Result {
+    let mut previous: Option<&ASTNode> = None;
+
+    for Statement { node, .. } in body.iter().filter(|statement| !statement.node.is_comment()) {
+        let written = node.to_string();
+
+        if let Some(previous) = previous {
+            // a following `else…` would be read as the alternate of an `if`
+            let is_else =
+                matches!(previous, ASTNode::IfStatement { .. }) && written.starts_with("else");
+
+            if !previous.ends_with_block() || is_else {
+                write!(f, ";")?;
             }
-            Expression::Range { from, limits, to } => {
-                write!(f, "{from}{limits}{to}")
+        }
+
+        write!(f, "{written}")?;
+        previous = Some(node);
+    }
+
+    Ok(())
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, items: &[T]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ",")?;
+        }
+        write!(f, "{item}")?;
+    }
+
+    Ok(())
+}
+
+/// a keyword followed by its argument, with a space only when they would run together
+fn write_keyword(
+    f: &mut fmt::Formatter<'_>,
+    keyword: &str,
+    argument: &dyn fmt::Display,
+) -> fmt::Result {
+    let argument = argument.to_string();
+
+    if argument.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        write!(f, "{keyword} {argument}")
+    } else {
+        write!(f, "{keyword}{argument}")
+    }
+}
+
+/// writes the parts one after the other, apart where a `/` followed by `/` or `*` would
+/// start a comment, as in `a / /b/`
+fn write_joined(f: &mut fmt::Formatter<'_>, parts: &[&str]) -> fmt::Result {
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 && parts[i - 1].ends_with('/') && part.starts_with(['/', '*']) {
+            write!(f, " ")?;
+        }
+        write!(f, "{part}")?;
+    }
+
+    Ok(())
+}
+
+/// the shortest of the ways to write a number that reads back as the same value
+fn shortest_number(n: f64, raw: &str) -> String {
+    [raw.replace('_', ""), format!("{n}"), format!("{n:e}")]
+        .into_iter()
+        .filter(|written| written.parse::<f64>() == Ok(n))
+        .min_by_key(String::len)
+        .unwrap_or_else(|| raw.to_owned())
+}
+
+/// a string literal for the value, in the quotes needing the fewest escapes
+pub fn quote(value: &str) -> String {
+    let quote = if value.matches('"').count() > value.matches('\'').count() {
+        '\''
+    } else {
+        '"'
+    };
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push(quote);
+
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            '\0' => quoted.push_str("\\0"),
+            c if c == quote => {
+                quoted.push('\\');
+                quoted.push(c);
             }
+            c => quoted.push(c),
         }
     }
+
+    quoted.push(quote);
+    quoted
 }
 
-impl Into<Expression> for ASTNode {
-    fn into(self) -> Expression {
+impl ASTNode {
+    pub fn is_comment(&self) -> bool {
+        matches!(
+            self,
+            ASTNode::ExpressionStatement {
+                expression: Expression::Comment { .. }
+            }
+        )
+    }
+
+    /// whether the statement ends with a block, so that the next one needs no separator
+    pub fn ends_with_block(&self) -> bool {
         match self {
+            ASTNode::ForStatement { .. }
+            | ASTNode::WhileStatement { .. }
+            | ASTNode::IfStatement { .. }
+            | ASTNode::TryStatement { .. } => true,
+            ASTNode::FunctionDeclaration { is_shortcut, .. } => !is_shortcut,
+            _ => false,
+        }
+    }
+}
+
+impl From<ASTNode> for Expression {
+    fn from(node: ASTNode) -> Self {
+        match node {
             ASTNode::FunctionDeclaration {
                 params,
                 body,
                 is_shortcut,
+                is_async,
                 ..
             } => Expression::FnExpression {
                 params,
                 body,
                 is_shortcut,
+                is_async,
             },
             _ => unreachable!(),
         }
@@ -14,7 +14,7 @@ pub struct Identifier {
 pub fn parse_identifier(i: &str) -> IResult<&str, Identifier, ErrorTree<&str>> {
     let (i, name) = many1(alt((tag("_"), alphanumeric1)))
         .map(|list| list.join(""))
-        .verify(is_id_valid)
+        .verify(|word: &String| is_id_valid(word))
         .context("identifier")
         .parse(i)?;
 
@@ -23,8 +23,8 @@ pub fn parse_identifier(i: &str) -> IResult<&str, Identifier, ErrorTree<&str>> {
     Ok((i, identifier))
 }
 
-fn is_id_valid(word: &String) -> bool {
-    !RESERVED_KEYWORD.contains(&word.as_str())
+fn is_id_valid(word: &str) -> bool {
+    !RESERVED_KEYWORD.contains(&word)
 }
 
 pub fn parse_unchecked_id(i: &str) -> IResult<&str, String, ErrorTree<&str>> {
@@ -35,6 +35,13 @@ pub fn parse_unchecked_id(i: &str) -> IResult<&str, String, ErrorTree<&str>> {
     Ok((i, s))
 }
 
+/// a property name, which unlike a variable name may be a keyword as in `request.method`
+pub fn parse_property_name(i: &str) -> IResult<&str, Identifier, ErrorTree<&str>> {
+    let (i, name) = parse_unchecked_id.context("identifier").parse(i)?;
+
+    Ok((i, Identifier { name }))
+}
+
 pub fn parse_identifier_expression(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
     let (i, id) = parse_identifier(i)?;
 
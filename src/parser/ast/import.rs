@@ -1,13 +1,13 @@
 use std::fmt;
 
-use super::identifier::Identifier;
+use super::{identifier::Identifier, quote};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ImportSpecifier {
     pub local: Identifier,
     pub imported: Identifier, // name locally
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ImportSource {
     pub value: String,
 }
@@ -23,6 +23,6 @@ impl fmt::Display for ImportSpecifier {
 }
 impl fmt::Display for ImportSource {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.value)
+        write!(f, "{}", quote(&self.value))
     }
 }
@@ -1,7 +1,9 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum LiteralValue {
-    Number(f32),
+    Number(f64),
     Str(String),
     Boolean(bool),
     Nil,
+    // compiled when evaluated, an invalid pattern being a runtime error
+    Regex { pattern: String, flags: String },
 }
@@ -2,7 +2,8 @@ use std::fmt;
 
 use super::{identifier::Identifier, Expression};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
 pub struct Property {
     pub is_method: bool,
     pub shorthand: bool,
@@ -11,7 +12,8 @@ pub struct Property {
     pub kind: PropertyKind,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
 pub enum PropertyKind {
     Init,
     Get,
@@ -1,6 +1,9 @@
 use super::ast::{ASTNode, Expression};
 use nom::{
-    branch::alt, bytes::complete::take_until, character::complete::multispace0, multi::many0,
+    branch::alt,
+    bytes::complete::{take_till, take_until},
+    character::complete::multispace0,
+    multi::many0,
     IResult, Parser,
 };
 use nom_supreme::{error::ErrorTree, tag::complete::tag, ParserExt};
@@ -33,12 +36,15 @@ pub fn jump_comments(input: &str) -> IResult<&str, String, ErrorTree<&str>> {
 
 pub fn parse_line_comment(input: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
     let (input, comment_opening) = tag("//").complete().parse(input)?;
-    let (input, comment_value) = take_until("\n")(input)?;
-    let (input, comment_closing) = tag("\n").complete().parse(input)?;
+    let (input, comment_value) = take_till(|c| c == '\n')(input)?;
+    // the last line of a script may not end with a new line
+    let (input, comment_closing) = tag("\n").complete().opt().parse(input)?;
 
     let comment_expr = Expression::Comment {
         is_line: true,
-        raw_value: comment_opening.to_string() + &comment_value + &comment_closing,
+        raw_value: comment_opening.to_string()
+            + comment_value
+            + comment_closing.unwrap_or_default(),
     };
 
     Ok((input, comment_expr))
@@ -51,7 +57,7 @@ pub fn parse_multiline_comment(input: &str) -> IResult<&str, Expression, ErrorTr
 
     let comment_expr = Expression::Comment {
         is_line: false,
-        raw_value: comment_opening.to_owned() + &comment_value + &comment_closing,
+        raw_value: comment_opening.to_owned() + comment_value + comment_closing,
     };
 
     Ok((input, comment_expr))
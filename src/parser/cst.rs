@@ -0,0 +1,228 @@
+// the concrete syntax tree of a script, holding every token along with the whitespace and
+// comments around it so that the script is written back byte for byte. The tokens of the
+// lexer are read into nodes for the statements and the expressions, following the grammar of
+// the parser, and the AST is derived from those nodes, so that tools may edit a script down
+// to its expressions without losing its comments
+
+mod grammar;
+mod lower;
+
+use std::{fmt, mem};
+
+use nom_supreme::error::ErrorTree;
+
+use self::{grammar::Parser, lower::Lowering};
+use super::{
+    ast::{ASTNode, Span},
+    lexer::{lex, TokenKind},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Program,
+    // statements between braces
+    Block,
+    Import,
+    // a name imported, or `*`, with the name it is bound to
+    ImportSpecifier,
+    VariableDeclaration,
+    Declarator,
+    Function,
+    // the parameters of a function between parentheses, or of a closure between bars
+    Parameters,
+    For,
+    While,
+    // a condition, the `else if` following it being a node of its own
+    If,
+    Try,
+    Return,
+    Yield,
+    ExpressionStatement,
+    // a number, string, boolean, nil or regular expression, a number keeping its sign
+    Literal,
+    Identifier,
+    Array,
+    Object,
+    Property,
+    Binary,
+    // `.property`
+    Member,
+    // `[index]`
+    Index,
+    Call,
+    // the arguments of a call, with their parentheses
+    Arguments,
+    Assignment,
+    Range,
+    Parenthesized,
+    Await,
+    Closure,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub span: Span,
+    pub children: Vec<Element>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    Node(Node),
+    Token(SyntaxToken),
+}
+
+/// a token with the trivia before it, and the trivia following it on its line
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub kind: TokenKind,
+    pub span: Span,
+    pub text: String,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
+
+/// whitespace, a new line or a comment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TokenKind,
+    pub span: Span,
+    pub text: String,
+}
+
+/// reads the tokens of the script into the nodes of its statements and expressions
+pub fn parse_tree(input: &str) -> Result<Node, ErrorTree<&str>> {
+    Parser::new(input, syntax_tokens(input)).program()
+}
+
+/// the tokens of the script, each one holding the trivia around it, the last one being an
+/// empty token holding the trivia ending the script
+fn syntax_tokens(input: &str) -> Vec<SyntaxToken> {
+    let mut tokens: Vec<SyntaxToken> = vec![];
+    let mut leading = vec![];
+    // whether the trivia met still follows the last token on its line
+    let mut trailing = false;
+
+    for token in lex(input) {
+        let text = input[token.span.clone()].to_owned();
+
+        if !token.kind.is_trivia() {
+            tokens.push(SyntaxToken {
+                kind: token.kind,
+                span: token.span,
+                text,
+                leading: mem::take(&mut leading),
+                trailing: vec![],
+            });
+            trailing = true;
+            continue;
+        }
+
+        trailing &= token.kind != TokenKind::NewLine;
+        let trivia = Trivia {
+            kind: token.kind,
+            span: token.span,
+            text,
+        };
+
+        match tokens.last_mut() {
+            Some(last) if trailing => last.trailing.push(trivia),
+            _ => leading.push(trivia),
+        }
+    }
+
+    tokens.push(SyntaxToken {
+        kind: TokenKind::End,
+        span: input.len()..input.len(),
+        text: String::new(),
+        leading,
+        trailing: vec![],
+    });
+
+    tokens
+}
+
+impl Node {
+    /// a node spanning its children, from its first token to its last one
+    fn new(kind: NodeKind, children: Vec<Element>) -> Node {
+        let span = |element: Option<&Element>| match element {
+            Some(Element::Node(node)) => node.span.clone(),
+            Some(Element::Token(token)) => token.span.clone(),
+            None => 0..0,
+        };
+        let span = span(children.first()).start..span(children.last()).end;
+
+        Node {
+            kind,
+            span,
+            children,
+        }
+    }
+
+    /// the AST of the program, or of the block or the statement the node is
+    pub fn ast(&self) -> ASTNode {
+        Lowering::new(self).node(self)
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|child| match child {
+            Element::Node(node) => Some(node),
+            Element::Token(_) => None,
+        })
+    }
+
+    /// the tokens of the node itself, without those of the nodes it holds
+    fn own_tokens(&self) -> impl Iterator<Item = &SyntaxToken> {
+        self.children.iter().filter_map(|child| match child {
+            Element::Token(token) => Some(token),
+            Element::Node(_) => None,
+        })
+    }
+
+    /// the tokens of the node and of the nodes it holds, in the order they were written
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens = vec![];
+
+        for child in &self.children {
+            match child {
+                Element::Node(node) => tokens.extend(node.tokens()),
+                Element::Token(token) => tokens.push(token),
+            }
+        }
+
+        tokens
+    }
+
+    /// every comment, those written inside expressions included
+    pub fn comments(&self) -> Vec<&Trivia> {
+        self.tokens()
+            .into_iter()
+            .flat_map(|token| token.leading.iter().chain(&token.trailing))
+            .filter(|trivia| trivia.kind.is_comment())
+            .collect()
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self.tokens() {
+            write!(f, "{token}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for trivia in &self.leading {
+            write!(f, "{}", trivia.text)?;
+        }
+        write!(f, "{}", self.text)?;
+        for trivia in &self.trailing {
+            write!(f, "{}", trivia.text)?;
+        }
+
+        Ok(())
+    }
+}
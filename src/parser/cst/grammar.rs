@@ -0,0 +1,629 @@
+// reads the tokens of a script into nodes following the grammar of the parser, so that a
+// script is split into the same statements and expressions. Where the parser only allows
+// spaces, as before the parenthesis of a call, the trivia before a token tells whether a new
+// line or a comment was written there
+
+use nom_supreme::error::{BaseErrorKind, ErrorTree, Expectation};
+
+use super::{lower::literal, Element, Node, NodeKind, SyntaxToken};
+use crate::parser::{
+    expression::range::{is_open_end, is_step},
+    lexer::TokenKind,
+    operations::{
+        binary::{parse_binary_operator, BinaryOperator},
+        nest_by_precedence,
+    },
+};
+
+/// what was written between a token and the one before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Gap {
+    None,
+    // spaces on the same line
+    Spaces,
+    // spaces and new lines
+    Lines,
+    // a comment, along with anything else
+    Comments,
+}
+
+const ASSIGNMENT_OPERATORS: [&str; 6] = ["=", "+=", "-=", "*=", "/=", "%="];
+
+type Parsed<'a, T = Node> = Result<T, ErrorTree<&'a str>>;
+
+pub struct Parser<'a> {
+    input: &'a str,
+    // the tokens left to read, those read having been moved to their nodes
+    tokens: Vec<Option<SyntaxToken>>,
+    gaps: Vec<Gap>,
+    position: usize,
+    // the end of the last token read
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str, tokens: Vec<SyntaxToken>) -> Self {
+        let gaps = (0..tokens.len())
+            .map(|index| {
+                let before = index.checked_sub(1).map(|index| &tokens[index].trailing);
+                let trivia = before.into_iter().flatten().chain(&tokens[index].leading);
+
+                trivia
+                    .map(|trivia| match trivia.kind {
+                        TokenKind::NewLine => Gap::Lines,
+                        kind if kind.is_comment() => Gap::Comments,
+                        _ => Gap::Spaces,
+                    })
+                    .max()
+                    .unwrap_or(Gap::None)
+            })
+            .collect();
+
+        Parser {
+            input,
+            tokens: tokens.into_iter().map(Some).collect(),
+            gaps,
+            position: 0,
+            end: 0,
+        }
+    }
+
+    pub fn program(mut self) -> Parsed<'a> {
+        let mut children = vec![];
+
+        loop {
+            self.separators(&mut children);
+            if self.token(0).kind == TokenKind::End {
+                break;
+            }
+            children.push(Element::Node(self.statement()?));
+        }
+        // the end of the script, holding the trivia after the last statement
+        children.push(self.bump());
+
+        Ok(Node {
+            kind: NodeKind::Program,
+            span: 0..self.input.len(),
+            children,
+        })
+    }
+
+    fn block(&mut self) -> Parsed<'a> {
+        let mut children = vec![self.expect(TokenKind::Punctuation, "{")?];
+
+        loop {
+            self.separators(&mut children);
+            if self.is(TokenKind::Punctuation, "}") {
+                break;
+            }
+            children.push(Element::Node(self.statement()?));
+        }
+        children.push(self.bump());
+
+        Ok(Node::new(NodeKind::Block, children))
+    }
+
+    /// the semicolons between statements
+    fn separators(&mut self, children: &mut Vec<Element>) {
+        while self.is(TokenKind::Punctuation, ";") {
+            children.push(self.bump());
+        }
+    }
+
+    fn statement(&mut self) -> Parsed<'a> {
+        let token = self.token(0);
+        let (kind, text) = (token.kind, token.text.clone());
+
+        match (kind, text.as_str()) {
+            (TokenKind::Keyword, "let" | "var") => self.variables(),
+            (TokenKind::Keyword, "import") => self.import(),
+            (TokenKind::Keyword, "for") => self.for_loop(),
+            (TokenKind::Keyword, "while") => self.while_loop(),
+            (TokenKind::Keyword, "if") => self.condition(),
+            (TokenKind::Keyword, "try") => self.try_catch(),
+            (TokenKind::Keyword, "fn") => self.function(),
+            (TokenKind::Keyword, "async") if self.token(1).text == "fn" => self.function(),
+            (TokenKind::Operator, ">>") | (TokenKind::Keyword, "return") => {
+                self.keyword_statement(NodeKind::Return)
+            }
+            (TokenKind::Keyword, "yield") => self.keyword_statement(NodeKind::Yield),
+            _ => self.expression_statement(),
+        }
+    }
+
+    fn variables(&mut self) -> Parsed<'a> {
+        let mut children = vec![self.bump()];
+
+        loop {
+            let declarator = vec![
+                self.identifier()?,
+                self.expect(TokenKind::Operator, "=")?,
+                Element::Node(self.expression()?),
+            ];
+            children.push(Element::Node(Node::new(NodeKind::Declarator, declarator)));
+
+            if !self.is(TokenKind::Punctuation, ",") {
+                break;
+            }
+            children.push(self.bump());
+        }
+
+        Ok(Node::new(NodeKind::VariableDeclaration, children))
+    }
+
+    fn import(&mut self) -> Parsed<'a> {
+        let mut children = vec![self.bump()];
+
+        loop {
+            let mut specifier = match self.is(TokenKind::Operator, "*") {
+                true => vec![self.bump()],
+                false => vec![self.identifier()?],
+            };
+            if self.is(TokenKind::Keyword, "as") {
+                specifier.extend([self.bump(), self.identifier()?]);
+            }
+            children.push(Element::Node(Node::new(
+                NodeKind::ImportSpecifier,
+                specifier,
+            )));
+
+            if !self.is(TokenKind::Punctuation, ",") {
+                break;
+            }
+            children.push(self.bump());
+            // a trailing comma
+            if self.is(TokenKind::Keyword, "from") {
+                break;
+            }
+        }
+
+        children.push(self.expect(TokenKind::Keyword, "from")?);
+        match self.token(0).kind {
+            TokenKind::String => children.push(self.bump()),
+            _ => return Err(self.error("import source")),
+        }
+
+        Ok(Node::new(NodeKind::Import, children))
+    }
+
+    fn for_loop(&mut self) -> Parsed<'a> {
+        let mut children = vec![self.bump()];
+        if self.is(TokenKind::Keyword, "let") || self.is(TokenKind::Keyword, "var") {
+            children.push(self.bump());
+        }
+
+        loop {
+            children.push(self.identifier()?);
+
+            if !self.is(TokenKind::Punctuation, ",") {
+                break;
+            }
+            children.push(self.bump());
+            if self.is(TokenKind::Keyword, "in") {
+                break;
+            }
+        }
+
+        children.push(self.expect(TokenKind::Keyword, "in")?);
+        children.push(Element::Node(self.expression()?));
+        children.push(Element::Node(self.block()?));
+
+        Ok(Node::new(NodeKind::For, children))
+    }
+
+    fn while_loop(&mut self) -> Parsed<'a> {
+        let children = vec![
+            self.bump(),
+            Element::Node(self.expression()?),
+            Element::Node(self.block()?),
+        ];
+
+        Ok(Node::new(NodeKind::While, children))
+    }
+
+    fn condition(&mut self) -> Parsed<'a> {
+        let mut children = vec![
+            self.bump(),
+            Element::Node(self.expression()?),
+            Element::Node(self.block()?),
+        ];
+
+        if self.gap() <= Gap::Lines && self.is(TokenKind::Keyword, "else") {
+            children.push(self.bump());
+
+            let alternate = match self.is(TokenKind::Keyword, "if") {
+                true => self.condition()?,
+                false => self.block()?,
+            };
+            children.push(Element::Node(alternate));
+        }
+
+        Ok(Node::new(NodeKind::If, children))
+    }
+
+    fn try_catch(&mut self) -> Parsed<'a> {
+        let mut children = vec![
+            self.bump(),
+            Element::Node(self.block()?),
+            self.expect(TokenKind::Keyword, "catch")?,
+        ];
+        // the error binding is optional
+        if self.token(0).kind == TokenKind::Identifier {
+            children.push(self.bump());
+        }
+        children.push(Element::Node(self.block()?));
+
+        Ok(Node::new(NodeKind::Try, children))
+    }
+
+    fn function(&mut self) -> Parsed<'a> {
+        let mut children = vec![];
+        if self.is(TokenKind::Keyword, "async") {
+            children.push(self.bump());
+        }
+
+        children.extend([
+            self.expect(TokenKind::Keyword, "fn")?,
+            self.identifier()?,
+            Element::Node(self.parameters("(", ")")?),
+            Element::Node(self.function_body()?),
+        ]);
+
+        Ok(Node::new(NodeKind::Function, children))
+    }
+
+    fn parameters(&mut self, open: &'static str, close: &'static str) -> Parsed<'a> {
+        // a closure without parameters
+        if open == "|" && self.is(TokenKind::Operator, "||") {
+            return Ok(Node::new(NodeKind::Parameters, vec![self.bump()]));
+        }
+
+        let mut children = vec![self.expect(TokenKind::Punctuation, open)?];
+
+        while self.token(0).kind == TokenKind::Identifier {
+            children.push(self.bump());
+
+            if !self.is(TokenKind::Punctuation, ",") {
+                break;
+            }
+            children.push(self.bump());
+        }
+        children.push(self.expect(TokenKind::Punctuation, close)?);
+
+        Ok(Node::new(NodeKind::Parameters, children))
+    }
+
+    /// a block, or the expression returned right away by `>> value`
+    fn function_body(&mut self) -> Parsed<'a> {
+        match self.is(TokenKind::Operator, ">>") || self.is(TokenKind::Keyword, "return") {
+            true => self.keyword_statement(NodeKind::Return),
+            false => self.block(),
+        }
+    }
+
+    /// a return or a yield, the keyword followed by an expression
+    fn keyword_statement(&mut self, kind: NodeKind) -> Parsed<'a> {
+        let children = vec![self.bump(), Element::Node(self.expression()?)];
+
+        Ok(Node::new(kind, children))
+    }
+
+    fn expression_statement(&mut self) -> Parsed<'a> {
+        let expression = if self.is_range() {
+            self.expression()?
+        } else {
+            let target = self.postfix()?;
+            let assignable = matches!(
+                target.kind,
+                NodeKind::Identifier | NodeKind::Member | NodeKind::Index
+            );
+
+            if assignable && self.is_assignment_operator() {
+                let children = vec![
+                    Element::Node(target),
+                    self.bump(),
+                    Element::Node(self.expression()?),
+                ];
+                Node::new(NodeKind::Assignment, children)
+            } else {
+                self.expression_from(target)?
+            }
+        };
+
+        Ok(Node::new(
+            NodeKind::ExpressionStatement,
+            vec![Element::Node(expression)],
+        ))
+    }
+
+    fn expression(&mut self) -> Parsed<'a> {
+        // a range without a start, as in `arr[..3]`
+        if self.is_range() {
+            return self.range(None);
+        }
+
+        let first = self.postfix()?;
+        self.expression_from(first)
+    }
+
+    /// the expression starting with an operand already read
+    fn expression_from(&mut self, first: Node) -> Parsed<'a> {
+        let from = self.binary(first)?;
+
+        // a range has the lowest precedence: `a + 1..b` is `(a + 1)..b`
+        match self.gap() <= Gap::Lines && self.is_range() {
+            true => self.range(Some(from)),
+            false => Ok(from),
+        }
+    }
+
+    fn binary(&mut self, first: Node) -> Parsed<'a> {
+        let mut operands = vec![first];
+        let mut operators = vec![];
+
+        while let Some(operator) = self.binary_operator() {
+            operators.push((self.bump(), operator));
+            operands.push(self.postfix()?);
+        }
+
+        Ok(nest_by_precedence(
+            operands,
+            operators,
+            |(_, operator)| operator.get_precedence(),
+            |left, (operator, _), right| {
+                let children = vec![Element::Node(left), operator, Element::Node(right)];
+                Node::new(NodeKind::Binary, children)
+            },
+        ))
+    }
+
+    fn binary_operator(&self) -> Option<BinaryOperator> {
+        let token = self.token(0);
+        if token.kind != TokenKind::Operator {
+            return None;
+        }
+
+        match parse_binary_operator(&token.text) {
+            Ok(("", operator)) => Some(operator),
+            _ => None,
+        }
+    }
+
+    fn range(&mut self, from: Option<Node>) -> Parsed<'a> {
+        let mut children: Vec<Element> = from.into_iter().map(Element::Node).collect();
+
+        let open = self.is(TokenKind::Operator, "..")
+            && is_open_end(&self.input[self.token(0).span.end..]);
+        children.push(self.bump());
+
+        if !open {
+            let to = self.postfix()?;
+            children.push(Element::Node(self.binary(to)?));
+        }
+
+        if is_step(&self.input[self.end..]) {
+            children.push(self.bump());
+
+            let step = self.postfix()?;
+            children.push(Element::Node(self.binary(step)?));
+        }
+
+        Ok(Node::new(NodeKind::Range, children))
+    }
+
+    /// a primary expression followed by its members, indexes and calls
+    fn postfix(&mut self) -> Parsed<'a> {
+        let mut expression = self.primary()?;
+
+        loop {
+            let gap = self.gap();
+
+            expression = if gap <= Gap::Spaces && self.is(TokenKind::Punctuation, "(") {
+                let arguments = self.list(NodeKind::Arguments, ")", Self::expression)?;
+                let children = vec![Element::Node(expression), Element::Node(arguments)];
+                Node::new(NodeKind::Call, children)
+            } else if gap <= Gap::Spaces && self.is(TokenKind::Punctuation, "[") {
+                let children = vec![
+                    Element::Node(expression),
+                    self.bump(),
+                    Element::Node(self.expression()?),
+                    self.expect(TokenKind::Punctuation, "]")?,
+                ];
+                Node::new(NodeKind::Index, children)
+            } else if gap <= Gap::Lines && self.is(TokenKind::Punctuation, ".") && self.is_name(1) {
+                let children = vec![Element::Node(expression), self.bump(), self.bump()];
+                Node::new(NodeKind::Member, children)
+            } else {
+                break;
+            };
+        }
+
+        Ok(expression)
+    }
+
+    fn primary(&mut self) -> Parsed<'a> {
+        let token = self.token(0);
+        let (kind, text) = (token.kind, token.text.clone());
+
+        match (kind, text.as_str()) {
+            (TokenKind::Punctuation, "[") => self.list(NodeKind::Array, "]", Self::expression),
+            (TokenKind::Punctuation, "{") => self.list(NodeKind::Object, "}", Self::property),
+            (TokenKind::Punctuation, "(") => {
+                let children = vec![
+                    self.bump(),
+                    Element::Node(self.expression()?),
+                    self.expect(TokenKind::Punctuation, ")")?,
+                ];
+                Ok(Node::new(NodeKind::Parenthesized, children))
+            }
+            (TokenKind::String | TokenKind::Regex, _)
+            | (TokenKind::Keyword, "true" | "false" | "nil") => self.literal(1),
+            (TokenKind::Number, _) => self.literal(self.number_length(0)),
+            // the sign of a number, written right before it
+            (TokenKind::Operator, "-" | "+")
+                if self.token(1).kind == TokenKind::Number && self.gap_at(1) == Gap::None =>
+            {
+                self.literal(1 + self.number_length(1))
+            }
+            (TokenKind::Keyword, "await") => {
+                let children = vec![self.bump(), Element::Node(self.postfix()?)];
+                Ok(Node::new(NodeKind::Await, children))
+            }
+            (TokenKind::Identifier, _) => Ok(Node::new(NodeKind::Identifier, vec![self.bump()])),
+            (TokenKind::Keyword, "async")
+            | (TokenKind::Punctuation, "|")
+            | (TokenKind::Operator, "||") => self.closure(),
+            _ => Err(self.error("expression")),
+        }
+    }
+
+    /// a literal written with the next tokens, which the parser of literals must read whole
+    fn literal(&mut self, tokens: usize) -> Parsed<'a> {
+        let error = self.error("literal");
+        let children: Vec<Element> = (0..tokens).map(|_| self.bump()).collect();
+
+        let node = Node::new(NodeKind::Literal, children);
+        match literal(&node) {
+            Some(_) => Ok(node),
+            None => Err(error),
+        }
+    }
+
+    /// the tokens of the number `ahead`, a dot right after it belonging to it as in `1.`
+    fn number_length(&self, ahead: usize) -> usize {
+        let dot = self.token(ahead + 1).kind == TokenKind::Punctuation
+            && self.token(ahead + 1).text == "."
+            && self.gap_at(ahead + 1) == Gap::None
+            && !self.is_name(ahead + 2);
+
+        1 + usize::from(dot)
+    }
+
+    fn closure(&mut self) -> Parsed<'a> {
+        let mut children = vec![];
+        if self.is(TokenKind::Keyword, "async") {
+            children.push(self.bump());
+        }
+
+        children.extend([
+            Element::Node(self.parameters("|", "|")?),
+            Element::Node(self.function_body()?),
+        ]);
+
+        Ok(Node::new(NodeKind::Closure, children))
+    }
+
+    fn property(&mut self) -> Parsed<'a> {
+        if !self.is_name(0) {
+            return Err(self.error("identifier"));
+        }
+
+        let children = vec![
+            self.bump(),
+            self.expect(TokenKind::Punctuation, ":")?,
+            Element::Node(self.expression()?),
+        ];
+
+        Ok(Node::new(NodeKind::Property, children))
+    }
+
+    /// items separated by commas between brackets, a comma being allowed after the last one
+    fn list(
+        &mut self,
+        kind: NodeKind,
+        close: &'static str,
+        item: fn(&mut Self) -> Parsed<'a>,
+    ) -> Parsed<'a> {
+        let mut children = vec![self.bump()];
+
+        while !self.is(TokenKind::Punctuation, close) {
+            children.push(Element::Node(item(self)?));
+
+            if !self.is(TokenKind::Punctuation, ",") {
+                break;
+            }
+            children.push(self.bump());
+        }
+        children.push(self.expect(TokenKind::Punctuation, close)?);
+
+        Ok(Node::new(kind, children))
+    }
+
+    fn identifier(&mut self) -> Parsed<'a, Element> {
+        match self.token(0).kind {
+            TokenKind::Identifier => Ok(self.bump()),
+            _ => Err(self.error("identifier")),
+        }
+    }
+
+    /// whether the token is a property name right after the `.` before it, which unlike a
+    /// variable name may be a keyword, as in `request.method`
+    fn is_name(&self, ahead: usize) -> bool {
+        let token = self.token(ahead);
+        let word = token
+            .text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        matches!(
+            token.kind,
+            TokenKind::Identifier | TokenKind::Keyword | TokenKind::Number
+        ) && word
+            && (ahead == 0 || self.gap_at(ahead) == Gap::None)
+    }
+
+    fn is_range(&self) -> bool {
+        self.is(TokenKind::Operator, "..") || self.is(TokenKind::Operator, "..=")
+    }
+
+    fn is_assignment_operator(&self) -> bool {
+        let token = self.token(0);
+        token.kind == TokenKind::Operator && ASSIGNMENT_OPERATORS.contains(&token.text.as_str())
+    }
+
+    fn is(&self, kind: TokenKind, text: &str) -> bool {
+        let token = self.token(0);
+        token.kind == kind && token.text == text
+    }
+
+    /// the token `ahead` of the next one, the end of the script being the last
+    fn token(&self, ahead: usize) -> &SyntaxToken {
+        let index = (self.position + ahead).min(self.tokens.len() - 1);
+        self.tokens[index]
+            .as_ref()
+            .expect("the token was already read")
+    }
+
+    fn gap(&self) -> Gap {
+        self.gap_at(0)
+    }
+
+    fn gap_at(&self, ahead: usize) -> Gap {
+        self.gaps[(self.position + ahead).min(self.gaps.len() - 1)]
+    }
+
+    /// moves the next token out, to the node being read
+    fn bump(&mut self) -> Element {
+        let token = self.tokens[self.position]
+            .take()
+            .expect("the token was already read");
+        self.position += 1;
+        self.end = token.span.end;
+
+        Element::Token(token)
+    }
+
+    fn expect(&mut self, kind: TokenKind, text: &'static str) -> Parsed<'a, Element> {
+        match self.is(kind, text) {
+            true => Ok(self.bump()),
+            false => Err(self.error(text)),
+        }
+    }
+
+    fn error(&self, expected: &'static str) -> ErrorTree<&'a str> {
+        ErrorTree::Base {
+            location: &self.input[self.token(0).span.start..],
+            kind: BaseErrorKind::Expected(Expectation::Tag(expected)),
+        }
+    }
+}
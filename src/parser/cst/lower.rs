@@ -0,0 +1,385 @@
+// derives the AST from the nodes of the concrete syntax tree, as the parser builds it: the
+// comments between statements are statements of their own, those inside expressions being
+// left out
+
+use std::sync::Arc;
+
+use nom::IResult;
+use nom_supreme::error::ErrorTree;
+
+use super::{Element, Node, NodeKind, SyntaxToken, Trivia};
+use crate::parser::{
+    assignment::initial::VariableKeyword,
+    ast::{
+        identifier::Identifier,
+        import::{ImportSource, ImportSpecifier},
+        literal_value::LiteralValue,
+        object::{Property, PropertyKind},
+        range::RangeType,
+        vars::VariableDeclarator,
+        ASTNode, Expression, Span, Statement,
+    },
+    expression::parse_primitive_value,
+    lexer::TokenKind,
+    operations::{assignment::parse_assignment_operator, binary::parse_binary_operator},
+};
+
+pub struct Lowering<'a> {
+    // every comment of the tree, in the order they were written
+    comments: Vec<&'a Trivia>,
+}
+
+impl<'a> Lowering<'a> {
+    pub fn new(tree: &'a Node) -> Self {
+        Lowering {
+            comments: tree.comments(),
+        }
+    }
+
+    pub fn node(&self, node: &Node) -> ASTNode {
+        match node.kind {
+            NodeKind::Program => ASTNode::Program {
+                body: self.statements(node, node.span.clone()),
+            },
+            _ => self.statement(node),
+        }
+    }
+
+    /// the statements of the program or of a block, along with the comments written between
+    /// them in `region`
+    fn statements(&self, node: &Node, region: Span) -> Vec<Statement> {
+        let first = self
+            .comments
+            .partition_point(|comment| comment.span.start < region.start);
+        let mut comments = self.comments[first..]
+            .iter()
+            .take_while(|comment| comment.span.end <= region.end)
+            .peekable();
+
+        let mut body = vec![];
+        for statement in node.nodes() {
+            while let Some(comment) =
+                comments.next_if(|comment| comment.span.start < statement.span.start)
+            {
+                body.push(comment_statement(comment, region.end));
+            }
+            // the comments inside the statement are left to its blocks
+            while comments
+                .next_if(|comment| comment.span.start < statement.span.end)
+                .is_some()
+            {}
+
+            body.push(Statement {
+                node: self.statement(statement),
+                span: statement.span.clone(),
+            });
+        }
+        body.extend(comments.map(|comment| comment_statement(comment, region.end)));
+
+        body
+    }
+
+    fn block(&self, node: &Node) -> ASTNode {
+        let mut braces = node.own_tokens();
+        let open = braces.next().map_or(0, |brace| brace.span.end);
+        let close = braces.last().map_or(open, |brace| brace.span.start);
+
+        ASTNode::BlockStatement {
+            body: self.statements(node, open..close),
+        }
+    }
+
+    fn statement(&self, node: &Node) -> ASTNode {
+        let mut nodes = node.nodes();
+        let mut next = || nodes.next().expect("the node is missing a part");
+
+        match node.kind {
+            NodeKind::Block => self.block(node),
+            NodeKind::Import => {
+                let specifiers = node
+                    .nodes()
+                    .map(|specifier| {
+                        let mut names = specifier.own_tokens().map(identifier);
+                        let imported = names.next().expect("the import has no name");
+
+                        ImportSpecifier {
+                            local: names.last().unwrap_or_else(|| imported.clone()),
+                            imported,
+                        }
+                    })
+                    .collect();
+
+                let Some(Expression::Literal {
+                    value: LiteralValue::Str(value),
+                    ..
+                }) = node
+                    .own_tokens()
+                    .last()
+                    .and_then(|source| literal_of(&source.text))
+                else {
+                    unreachable!("the import source is a string")
+                };
+
+                ASTNode::ImportDeclaration {
+                    specifiers,
+                    source: ImportSource { value },
+                }
+            }
+            NodeKind::VariableDeclaration => ASTNode::VariableDeclaration {
+                declarations: node
+                    .nodes()
+                    .map(|declarator| VariableDeclarator {
+                        id: identifiers(declarator).next().expect("the name is missing"),
+                        init: self.expression(declarator.nodes().next().expect("no value")),
+                    })
+                    .collect(),
+                kind: variable_keyword(node),
+            },
+            NodeKind::Function => {
+                let id = identifiers(node).next().expect("the name is missing");
+                let params = identifiers(next()).collect();
+                let (body, is_shortcut) = self.body(next());
+
+                ASTNode::FunctionDeclaration {
+                    id,
+                    params,
+                    body,
+                    is_shortcut,
+                    is_async: has_token(node, "async"),
+                }
+            }
+            NodeKind::For => ASTNode::ForStatement {
+                declarations: identifiers(node).collect(),
+                kind: variable_keyword(node),
+                source: self.expression(next()),
+                body: Arc::new(self.block(next())),
+            },
+            NodeKind::While => ASTNode::WhileStatement {
+                test: self.expression(next()),
+                body: Arc::new(self.block(next())),
+            },
+            NodeKind::If => ASTNode::IfStatement {
+                test: self.expression(next()),
+                body: Arc::new(self.block(next())),
+                // a block, or the condition of an `else if`
+                alternate: nodes
+                    .next()
+                    .map(|alternate| Arc::new(self.statement(alternate))),
+            },
+            NodeKind::Try => ASTNode::TryStatement {
+                block: Arc::new(self.block(next())),
+                param: identifiers(node).next(),
+                handler: Arc::new(self.block(next())),
+            },
+            NodeKind::Return => ASTNode::ReturnStatement {
+                argument: self.expression(next()),
+                is_shortcut: has_token(node, ">>"),
+            },
+            NodeKind::Yield => ASTNode::YieldStatement {
+                argument: self.expression(next()),
+            },
+            NodeKind::ExpressionStatement => ASTNode::ExpressionStatement {
+                expression: self.expression(next()),
+            },
+            kind => unreachable!("{kind:?} is not a statement"),
+        }
+    }
+
+    /// the body of a function or a closure, and whether it only returns an expression
+    fn body(&self, node: &Node) -> (Arc<ASTNode>, bool) {
+        let is_shortcut = node.kind == NodeKind::Return;
+
+        (Arc::new(self.statement(node)), is_shortcut)
+    }
+
+    fn expression(&self, node: &Node) -> Expression {
+        let mut nodes = node.nodes();
+        let mut next = || {
+            let part = nodes.next().expect("the node is missing a part");
+            Box::new(self.expression(part))
+        };
+
+        match node.kind {
+            NodeKind::Literal => literal(node).expect("the literal was read whole"),
+            NodeKind::Identifier => {
+                Expression::IdentifierExpression(identifiers(node).next().expect("no name"))
+            }
+            NodeKind::Array => Expression::Array {
+                elements: node
+                    .nodes()
+                    .map(|element| self.expression(element))
+                    .collect(),
+            },
+            NodeKind::Object => Expression::Object {
+                properties: node
+                    .nodes()
+                    .map(|property| {
+                        let key = property.own_tokens().next().map(identifier);
+                        let value = self.expression(property.nodes().next().expect("no value"));
+
+                        Property {
+                            is_method: matches!(value, Expression::FnExpression { .. }),
+                            shorthand: false,
+                            key: key.expect("the property has no name"),
+                            value,
+                            kind: PropertyKind::Init,
+                        }
+                    })
+                    .collect(),
+            },
+            NodeKind::Binary => {
+                let operator = operator(node, parse_binary_operator);
+
+                Expression::BinaryExpression {
+                    left: next(),
+                    operator,
+                    right: next(),
+                }
+            }
+            NodeKind::Member => Expression::MemberExpression {
+                indexed: next(),
+                property: Box::new(Expression::IdentifierExpression(
+                    node.own_tokens().last().map(identifier).expect("no name"),
+                )),
+                computed: false,
+            },
+            NodeKind::Index => Expression::MemberExpression {
+                indexed: next(),
+                property: next(),
+                computed: true,
+            },
+            NodeKind::Call => Expression::CallExpression {
+                callee: next(),
+                args: node
+                    .nodes()
+                    .nth(1)
+                    .into_iter()
+                    .flat_map(Node::nodes)
+                    .map(|arg| self.expression(arg))
+                    .collect(),
+            },
+            NodeKind::Assignment => {
+                let operator = operator(node, parse_assignment_operator);
+
+                Expression::AssignmentExpression {
+                    operator,
+                    id: next(),
+                    assigned: next(),
+                }
+            }
+            NodeKind::Range => self.range(node),
+            NodeKind::Parenthesized => Expression::Parenthesized(next()),
+            NodeKind::Await => Expression::AwaitExpression { argument: next() },
+            NodeKind::Closure => {
+                let params = identifiers(nodes.next().expect("no parameters")).collect();
+                let (body, is_shortcut) = self.body(nodes.next().expect("no body"));
+
+                Expression::FnExpression {
+                    params,
+                    body,
+                    is_shortcut,
+                    is_async: has_token(node, "async"),
+                }
+            }
+            kind => unreachable!("{kind:?} is not an expression"),
+        }
+    }
+
+    /// the parts of a range are told apart by the tokens between them: `from..to step step`
+    fn range(&self, node: &Node) -> Expression {
+        let (mut from, mut to, mut step) = (None, None, None);
+        let mut limits = RangeType::Dot;
+        let mut part = &mut from;
+
+        for child in &node.children {
+            match child {
+                Element::Node(expression) => {
+                    *part = Some(Box::new(self.expression(expression)));
+                }
+                Element::Token(token) if token.text == "step" => part = &mut step,
+                Element::Token(token) => {
+                    if token.text == "..=" {
+                        limits = RangeType::DotEqual;
+                    }
+                    part = &mut to;
+                }
+            }
+        }
+
+        Expression::Range {
+            from,
+            limits,
+            to,
+            step,
+        }
+    }
+}
+
+/// a comment between statements, a line comment holding the new line ending it unless it
+/// ends the region
+fn comment_statement(comment: &Trivia, end: usize) -> Statement {
+    let is_line = comment.kind == TokenKind::LineComment;
+
+    let mut raw_value = comment.text.clone();
+    if is_line && comment.span.end < end {
+        raw_value.push('\n');
+    }
+
+    let start = comment.span.start;
+    Statement {
+        node: ASTNode::ExpressionStatement {
+            expression: Expression::Comment { is_line, raw_value },
+        },
+        span: start..start + comment.text.trim_end().len(),
+    }
+}
+
+/// the literal the tokens of the node write, read by the parser of literals
+pub fn literal(node: &Node) -> Option<Expression> {
+    let text: String = node.own_tokens().map(|token| token.text.as_str()).collect();
+    literal_of(&text)
+}
+
+fn literal_of(text: &str) -> Option<Expression> {
+    match parse_primitive_value(text) {
+        Ok(("", literal)) => Some(literal),
+        _ => None,
+    }
+}
+
+fn identifier(token: &SyntaxToken) -> Identifier {
+    Identifier {
+        name: token.text.clone(),
+    }
+}
+
+/// the names the node declares, leaving out keywords
+fn identifiers(node: &Node) -> impl Iterator<Item = Identifier> + '_ {
+    node.own_tokens()
+        .filter(|token| token.kind == TokenKind::Identifier)
+        .map(identifier)
+}
+
+fn variable_keyword(node: &Node) -> VariableKeyword {
+    match has_token(node, "var") {
+        true => VariableKeyword::Var,
+        false => VariableKeyword::Let,
+    }
+}
+
+fn has_token(node: &Node, text: &str) -> bool {
+    node.own_tokens().any(|token| token.text == text)
+}
+
+/// the operator of the node, read by the parser of operators
+fn operator<O>(node: &Node, parse: fn(&str) -> IResult<&str, O, ErrorTree<&str>>) -> O {
+    let token = node
+        .own_tokens()
+        .find(|token| token.kind == TokenKind::Operator)
+        .expect("the operator is missing");
+
+    match parse(&token.text) {
+        Ok(("", operator)) => operator,
+        _ => unreachable!("{} is not an operator", token.text),
+    }
+}
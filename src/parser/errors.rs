@@ -1,12 +1,5 @@
-use codespan_reporting::{
-    diagnostic::{Diagnostic, Label},
-    files::SimpleFile,
-    term::{
-        self,
-        termcolor::{ColorChoice, StandardStream},
-    },
-};
-use std::{error::Error, fmt::Display};
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use std::fmt;
 
 #[derive(Debug)]
 pub struct SyntaxError<FileId> {
@@ -20,20 +13,7 @@ pub struct SyntaxError<FileId> {
     pub notes: Vec<String>,
 }
 
-impl SyntaxError<()> {
-    pub fn print<Name: Display + AsRef<str> + Clone, Content: AsRef<str>>(
-        self,
-        file: SimpleFile<Name, Content>,
-    ) -> Result<(), Box<dyn Error>> {
-        let diagnostic = self.generate_diagnostic();
-        let writer = StandardStream::stderr(ColorChoice::Always);
-        let config = codespan_reporting::term::Config::default();
-
-        term::emit(&mut writer.lock(), &config, &file, &diagnostic)?;
-        Ok(())
-    }
-}
-
+#[allow(dead_code)]
 impl<FileId> SyntaxError<FileId> {
     pub fn new(
         message: String,
@@ -191,9 +171,9 @@ pub fn get_opposing_tag(opening_tag: &str) -> &str {
 #[derive(Debug, Clone, Copy)]
 pub struct ErrorCode(usize);
 
-impl ToString for ErrorCode {
-    fn to_string(&self) -> String {
-        format!("E{:03}", self.0)
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "E{:03}", self.0)
     }
 }
 
@@ -1,6 +1,7 @@
 mod array;
+mod await_expr;
 mod bool;
-mod function_call;
+pub mod function_call;
 pub mod indexing;
 pub mod member_expr;
 mod nil;
@@ -8,12 +9,14 @@ mod numbers;
 mod object;
 mod parenthesized;
 pub mod range;
+pub mod regex;
 pub mod strings;
 
 use self::{
-    array::parse_array, bool::parse_bool, function_call::parse_fn_call, indexing::parse_indexing,
+    array::parse_array, await_expr::parse_await_expression, bool::parse_bool,
     member_expr::parse_member_expr, nil::parse_nil, numbers::parse_number, object::parse_object,
-    parenthesized::parse_parenthesized, range::parse_range, strings::parse_string,
+    parenthesized::parse_parenthesized, range::parse_range_end, regex::parse_regex,
+    strings::parse_string,
 };
 use super::{
     ast::{identifier::parse_identifier_expression, ASTNode},
@@ -39,26 +42,57 @@ pub fn parse_expression_statement(input: &str) -> IResult<&str, ASTNode, ErrorTr
         return Ok((input, expr_statement));
     }
 
-    let (input, _) = alt((tag("\n"), tag(";"), tag("//").complete()))
-        .peek()
-        .context("unexpected")
-        .cut()
-        .parse(input)?;
+    let (input, _) = alt((
+        tag("\n"),
+        tag("\r\n"),
+        tag(";"),
+        tag("}"),
+        tag("//").complete(),
+        tag("/*").complete(),
+    ))
+    .peek()
+    .context("unexpected")
+    .cut()
+    .parse(input)?;
 
     Ok((input, expr_statement))
 }
 
 pub fn parse_expression(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
-    let (i, result) = parse_expression_with(parse_basic_expression).parse(i)?;
+    // a range without a start, as in `arr[..3]`
+    if let Ok((i, (limits, to, step))) = parse_range_end(i) {
+        let expr = Expression::Range {
+            from: None,
+            limits,
+            to: to.map(Box::new),
+            step: step.map(Box::new),
+        };
+        return Ok((i, expr));
+    }
+
+    let (i, from) = parse_expression_with(parse_basic_expression).parse(i)?;
+
+    // a range has the lowest precedence: `a + 1..b` is `(a + 1)..b`
+    let (i, range_end) = parse_range_end.opt().parse(i)?;
 
-    Ok((i, result))
+    let expr = match range_end {
+        Some((limits, to, step)) => Expression::Range {
+            from: Some(Box::new(from)),
+            limits,
+            to: to.map(Box::new),
+            step: step.map(Box::new),
+        },
+        None => from,
+    };
+
+    Ok((i, expr))
 }
 
 pub fn parse_expression_with<'a, F>(
     parser: F,
-) -> impl Fn(&'a str) -> IResult<&'a str, Expression, ErrorTree<&str>>
+) -> impl Fn(&'a str) -> IResult<&'a str, Expression, ErrorTree<&'a str>>
 where
-    F: Fn(&'a str) -> IResult<&'a str, Expression, ErrorTree<&str>>,
+    F: Fn(&'a str) -> IResult<&'a str, Expression, ErrorTree<&'a str>>,
 {
     move |input| {
         let parser_closure = |i| parser(i);
@@ -89,15 +123,17 @@ where
     }
 }
 
-fn parse_basic_expression(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
+pub fn parse_basic_expression(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
+    // member expressions, calls and indexing are all postfix forms of a primary expression
+    parse_member_expr(i)
+}
+
+pub fn parse_primary_expression(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
     let (i, expr) = alt((
-        parse_member_expr,
-        parse_indexing,
-        parse_fn_call,
-        parse_range,
         parse_composite_value,
         parse_primitive_value,
         parse_parenthesized,
+        parse_await_expression,
         parse_identifier_expression,
         parse_fn_expression,
     ))
@@ -106,8 +142,14 @@ fn parse_basic_expression(i: &str) -> IResult<&str, Expression, ErrorTree<&str>>
     Ok((i, expr))
 }
 
-fn parse_primitive_value(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
-    alt((parse_string, parse_bool, parse_number, parse_nil))(i)
+pub fn parse_primitive_value(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
+    alt((
+        parse_string,
+        parse_bool,
+        parse_number,
+        parse_nil,
+        parse_regex,
+    ))(i)
 }
 fn parse_composite_value(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
     alt((parse_array, parse_object))(i)
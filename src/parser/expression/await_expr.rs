@@ -0,0 +1,30 @@
+use nom::{
+    character::complete::{multispace0, satisfy},
+    combinator::not,
+    IResult, Parser,
+};
+use nom_supreme::{error::ErrorTree, tag::complete::tag, ParserExt};
+
+use super::parse_basic_expression;
+use crate::parser::ast::Expression;
+
+/// `await` applies to a whole member expression or call, as in `await http.fetch(url)`
+pub fn parse_await_expression(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
+    let (i, _) = tag("await")
+        .complete()
+        .terminated(not(satisfy(|c| c.is_alphanumeric() || c == '_')))
+        .parse(i)?;
+
+    let (i, _) = multispace0(i)?;
+
+    let (i, argument) = parse_basic_expression
+        .cut()
+        .context("expression")
+        .parse(i)?;
+
+    let expr = Expression::AwaitExpression {
+        argument: Box::new(argument),
+    };
+
+    Ok((i, expr))
+}
@@ -1,20 +1,17 @@
-use crate::parser::{
-    ast::{identifier::parse_identifier_expression, Expression},
-    comment::jump_comments,
-    expression::parse_expression_with,
-};
+use crate::parser::{ast::Expression, comment::jump_comments};
 
 use nom::{
-    branch::alt, character::complete::char, character::complete::multispace0,
-    multi::separated_list0, IResult, Parser,
+    character::complete::{char, space0},
+    multi::separated_list0,
+    IResult, Parser,
 };
 use nom_supreme::{error::ErrorTree, ParserExt};
 
-use super::{parenthesized::parse_parenthesized, parse_expression};
+use super::parse_expression;
 
-pub fn parse_fn_call(input: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
-    let (input, id) = parse_expression_with(parse_expression_except_fn_call)(input)?;
-    let (input, _) = multispace0(input)?;
+pub fn parse_fn_call_args(input: &str) -> IResult<&str, Vec<Expression>, ErrorTree<&str>> {
+    // the parenthesis must be on the same line as the callee
+    let (input, _) = space0(input)?;
 
     let (input, _) = char('(')(input)?;
     let (input, args) = separated_list0(
@@ -27,22 +24,5 @@ pub fn parse_fn_call(input: &str) -> IResult<&str, Expression, ErrorTree<&str>>
 
     let (input, _) = char(')').preceded_by(jump_comments).cut().parse(input)?;
 
-    let expr = Expression::CallExpression {
-        callee: Box::new(id),
-        args,
-    };
-
-    Ok((input, expr))
-}
-
-fn parse_expression_except_fn_call(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
-    let (i, _) = jump_comments(i)?;
-
-    let (i, expr) = alt((
-        parse_parenthesized,
-        parse_identifier_expression,
-        // avoid adding to many parser here
-    ))(i)?;
-
-    Ok((i, expr))
+    Ok((input, args))
 }
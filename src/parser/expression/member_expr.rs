@@ -1,72 +1,70 @@
-use crate::parser::ast::identifier::parse_identifier_expression;
+use crate::parser::ast::identifier::parse_property_name;
 use crate::parser::ast::Expression;
-use crate::parser::comment::jump_comments;
-use nom::branch::alt;
-use nom::character::complete::char;
-use nom::multi::separated_list1;
+use nom::character::complete::{char, multispace0};
+use nom::combinator::not;
 use nom::{IResult, Parser};
 use nom_supreme::error::ErrorTree;
+use nom_supreme::ParserExt;
 
-use super::function_call::parse_fn_call;
-use super::indexing::parse_indexing;
-use super::parenthesized::parse_parenthesized;
-use super::parse_expression_with;
+use super::function_call::parse_fn_call_args;
+use super::indexing::parse_index;
+use super::parse_primary_expression;
 
-pub fn parse_member_expr(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
-    let (i, mut ids) = separated_list1(
-        char('.'),
-        parse_expression_with(parse_expression_except_member_expr),
-    )(i)?;
-    // we are sure that ids length is >= 2 here
+enum Postfix {
+    Member(Expression),
+    Index(Expression),
+    Call(Vec<Expression>),
+}
 
-    let property = Box::new(ids.pop().unwrap());
+pub fn parse_member_expr(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
+    let (mut i, mut expr) = parse_primary_expression(i)?;
 
-    if ids.len() == 0 {
-        return Err(nom::Err::Error(ErrorTree::Alt(vec![])));
+    // fold every `.property`, `[index]` and `(args)` following the primary expression
+    while let Ok((rest, postfix)) = parse_postfix(i) {
+        expr = match postfix {
+            Postfix::Member(property) => Expression::MemberExpression {
+                indexed: Box::new(expr),
+                property: Box::new(property),
+                computed: false,
+            },
+            Postfix::Index(property) => Expression::MemberExpression {
+                indexed: Box::new(expr),
+                property: Box::new(property),
+                computed: true,
+            },
+            Postfix::Call(args) => Expression::CallExpression {
+                callee: Box::new(expr),
+                args,
+            },
+        };
+        i = rest;
     }
 
-    let indexed = if ids.len() == 1 {
-        Box::new(ids.remove(0))
-    } else {
-        Box::new(build_member_expr(ids))
-    };
-
-    let expr = Expression::MemberExpression {
-        indexed,
-        property,
-        computed: false,
-    };
-
     Ok((i, expr))
 }
 
-fn build_member_expr(mut ids: Vec<Expression>) -> Expression {
-    if ids.len() == 1 {
-        return ids.remove(0);
+fn parse_postfix(i: &str) -> IResult<&str, Postfix, ErrorTree<&str>> {
+    if let Ok((i, args)) = parse_fn_call_args(i) {
+        return Ok((i, Postfix::Call(args)));
     }
 
-    let property = Box::new(ids.pop().unwrap());
+    if let Ok((i, index)) = parse_index(i) {
+        return Ok((i, Postfix::Index(index)));
+    }
 
-    let expr = Expression::MemberExpression {
-        indexed: Box::new(build_member_expr(ids)),
-        property,
-        computed: false,
-    };
+    let (i, property) = parse_property(i)?;
 
-    expr
+    Ok((i, Postfix::Member(property)))
 }
 
-fn parse_expression_except_member_expr(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
-    let (i, _) = jump_comments(i)?;
+fn parse_property(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
+    // a member access may be chained on the next line, but `..` is a range
+    let (i, _) = char('.')
+        .preceded_by(multispace0)
+        .terminated(not(char('.')))
+        .parse(i)?;
 
-    let (i, expr) = alt((
-        parse_parenthesized,
-        parse_indexing,
-        parse_fn_call,
-        parse_identifier_expression,
-        // avoid adding to many parser here
-    ))
-    .parse(i)?;
-
-    Ok((i, expr))
+    parse_property_name
+        .map(Expression::IdentifierExpression)
+        .parse(i)
 }
@@ -1,5 +1,9 @@
-use nom::bytes::complete::{take, take_until1};
-use nom::number::complete::float;
+use nom::bytes::complete::take;
+use nom::character::complete::{digit1, one_of};
+use nom::combinator::recognize;
+use nom::multi::separated_list1;
+use nom::number::complete::double;
+use nom::sequence::{pair, tuple};
 use nom::Parser;
 use nom::{branch::alt, character::complete::char, combinator::opt, IResult};
 use nom_supreme::error::ErrorTree;
@@ -11,19 +15,25 @@ use crate::parser::ast::Expression;
 pub fn parse_number(initial_i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
     let (base_input, sign) = opt(alt((char('+'), char('-'))))(initial_i)?;
 
-    // is normal to avoid having things such as Infinity considered numbers
-    let (i, num) = float.verify(|num| num.is_normal()).parse(base_input)?;
-    let (_, other_dot) = opt(char('.'))(i)?;
+    let (i, num) = match parse_separated_digits(base_input) {
+        Ok(separated) => separated,
+        Err(_) => {
+            // is finite to avoid having things such as Infinity considered numbers
+            let (i, num) = double.verify(|num| num.is_finite()).parse(base_input)?;
+            let parsed = &base_input[..base_input.len() - i.len()];
 
-    // check in case a range is following
-    let (i, num) = if other_dot.is_some() {
-        let (i, num_string) = take_until1(".")(base_input)?;
-        (i, num_string.parse::<f32>().unwrap())
-    } else {
-        (i, num)
+            // check in case a range is following: `0..10` must not eat the first dot
+            let i = if parsed.ends_with('.') && i.starts_with('.') {
+                &base_input[parsed.len() - 1..]
+            } else {
+                i
+            };
+
+            (i, num)
+        }
     };
 
-    let (_, raw) = take((initial_i.len() - i.len()) as usize)(initial_i)?;
+    let (_, raw) = take(initial_i.len() - i.len())(initial_i)?;
 
     let num = match sign {
         Some('-') => -num,
@@ -38,3 +48,20 @@ pub fn parse_number(initial_i: &str) -> IResult<&str, Expression, ErrorTree<&str
         },
     ))
 }
+
+/// a number written with underscores between its digits, as in `1_000_000`
+fn parse_separated_digits(i: &str) -> IResult<&str, f64, ErrorTree<&str>> {
+    let exponent = tuple((one_of("eE"), opt(one_of("+-")), digits));
+
+    let (i, written) = recognize(tuple((digits, opt(pair(char('.'), digits)), opt(exponent))))
+        .verify(|written: &&str| written.contains('_'))
+        .parse(i)?;
+
+    let num = written.replace('_', "").parse().unwrap_or(f64::INFINITY);
+
+    Ok((i, num))
+}
+
+fn digits(i: &str) -> IResult<&str, &str, ErrorTree<&str>> {
+    recognize(separated_list1(char('_'), digit1))(i)
+}
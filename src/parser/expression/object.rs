@@ -6,7 +6,7 @@ use nom_supreme::{error::ErrorTree, ParserExt};
 
 use crate::parser::{
     ast::{
-        identifier::parse_identifier,
+        identifier::parse_property_name,
         object::{Property, PropertyKind},
         Expression,
     },
@@ -22,13 +22,10 @@ pub fn parse_object(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
     let (i, elements) = separated_list0(char(','), parse_property.delimited_by(jump_comments))
         .cut()
         .parse(i)?;
-    println!("1 {:?}", i);
 
     let (i, _) = char(',').preceded_by(multispace0).opt().parse(i)?;
     let (i, _) = multispace0(i)?;
-    println!("2 {:?}", i);
     let (i, _) = char('}').context("unexpected").cut().parse(i)?;
-    println!("3 {:?}", i);
 
     Ok((
         i,
@@ -39,7 +36,7 @@ pub fn parse_object(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
 }
 
 fn parse_property(i: &str) -> IResult<&str, Property, ErrorTree<&str>> {
-    let (i, id) = parse_identifier.terminated(multispace0).parse(i)?;
+    let (i, id) = parse_property_name.terminated(multispace0).parse(i)?;
 
     let (i, _) = char(':')(i)?;
 
@@ -49,10 +46,7 @@ fn parse_property(i: &str) -> IResult<&str, Property, ErrorTree<&str>> {
         .context("expression")
         .parse(i)?;
 
-    let is_method = match expr {
-        Expression::FnExpression { .. } => true,
-        _ => false,
-    };
+    let is_method = matches!(expr, Expression::FnExpression { .. });
 
     Ok((
         i,
@@ -1,51 +1,90 @@
-use crate::parser::ast::identifier::parse_identifier_expression;
 use crate::parser::ast::range::RangeType;
 use crate::parser::ast::Expression;
 use nom::branch::alt;
-use nom::character::complete::multispace0;
-use nom::combinator::map;
+use nom::character::complete::{multispace0, space0};
 use nom::{IResult, Parser};
 use nom_supreme::ParserExt;
 use nom_supreme::{error::ErrorTree, tag::complete::tag};
 
-use super::function_call::parse_fn_call;
-use super::indexing::parse_indexing;
-use super::member_expr::parse_member_expr;
-use super::parenthesized::parse_parenthesized;
-use super::{parse_expression_with, parse_primitive_value};
+use crate::parser::lexer::{first_token, TokenKind};
 
-pub fn parse_range(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
-    let (i, from) = map(
-        parse_expression_with(parse_expression_except_range),
-        |expr| Box::new(expr),
-    )(i)?;
+use super::{parse_basic_expression, parse_expression_with};
 
+type RangeEnd = (RangeType, Option<Expression>, Option<Expression>);
+
+/// the limits, the end and the step of a range, which is open when `..` ends the line
+/// or comes right before a delimiter, as in `arr[2..]` or `for i in 0.. {`
+pub fn parse_range_end(i: &str) -> IResult<&str, RangeEnd, ErrorTree<&str>> {
     let (i, _) = multispace0(i)?;
 
     let (i, limits) = parse_range_type(i)?;
 
-    let (i, _) = multispace0(i)?;
+    let (i, to) = if matches!(limits, RangeType::Dot) && is_open_end(i) {
+        (i, None)
+    } else {
+        let (i, _) = multispace0(i)?;
 
-    let (i, to) = map(
-        parse_expression_with(parse_expression_except_range),
-        |expr| Box::new(expr),
-    )
-    .cut()
-    .parse(i)?;
+        parse_expression_with(parse_basic_expression)
+            .cut()
+            .context("expression")
+            .map(Some)
+            .parse(i)?
+    };
+
+    let (i, step) = match is_step(i) {
+        true => parse_step.map(Some).parse(i)?,
+        false => (i, None),
+    };
+
+    Ok((i, (limits, to, step)))
+}
 
-    Ok((i, Expression::Range { from, limits, to }))
+/// `step` is only a keyword on the line of a range, as in `0..100 step 5`
+fn parse_step(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
+    let (i, _) = space0(i)?;
+    let (i, _) = tag("step").parse(i)?;
+    let (i, _) = space0(i)?;
+
+    parse_expression_with(parse_basic_expression)
+        .cut()
+        .context("expression")
+        .parse(i)
+}
+
+pub fn is_open_end(i: &str) -> bool {
+    let line = i.trim_start_matches([' ', '\t']);
+    if line.is_empty() || line.starts_with(['\n', '\r']) || line.starts_with("//") {
+        return true;
+    }
+    if is_step(i) {
+        return true;
+    }
+
+    i.trim_start().starts_with([']', ')', ',', '{', '}', ';'])
 }
 
-fn parse_expression_except_range(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
-    // to avoid recursive calls to range parser
-    alt((
-        parse_member_expr,
-        parse_indexing,
-        parse_fn_call,
-        parse_primitive_value,
-        parse_parenthesized,
-        parse_identifier_expression,
-    ))(i)
+/// whether `step` is the keyword rather than a variable, which it is when an operand follows
+/// it on the same line: `0.. step 2` and `0..9 step -2` have a step, `0..step`, `0..step - 1`,
+/// `0..step-1` and `0..step step 2` end with one
+pub fn is_step(i: &str) -> bool {
+    let Some(rest) = i.trim_start_matches([' ', '\t']).strip_prefix("step") else {
+        return false;
+    };
+    // the keyword is followed by a space, `step(2)` and `step-1` using a variable
+    let after = rest.trim_start_matches([' ', '\t']);
+    if after.len() == rest.len() {
+        return false;
+    }
+
+    // a minus right before the step negates it, rather than subtracting from the end
+    let rest = after.strip_prefix('-').unwrap_or(after);
+    let operand = first_token(rest).is_some_and(|token| match token.kind {
+        TokenKind::Identifier | TokenKind::Number => true,
+        TokenKind::Punctuation => token.text(rest) == "(",
+        _ => false,
+    });
+
+    operand && !is_step(rest)
 }
 
 fn parse_range_type(i: &str) -> IResult<&str, RangeType, ErrorTree<&str>> {
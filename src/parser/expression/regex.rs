@@ -0,0 +1,55 @@
+use nom::{
+    bytes::complete::take_while,
+    character::complete::{char, one_of},
+    combinator::{fail, not},
+    IResult, Parser,
+};
+use nom_supreme::{error::ErrorTree, ParserExt};
+
+use crate::parser::ast::{literal_value::LiteralValue, Expression};
+
+// `/pattern/flags`, only ever parsed where an operand is expected so that a `/` there
+// cannot be a division; `//` and `/*` are left to comments
+pub fn parse_regex(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
+    let (rest, _) = char('/').terminated(not(one_of("/*"))).parse(i)?;
+
+    let Some(length) = pattern_length(rest) else {
+        return fail(i);
+    };
+    let (pattern, rest) = rest.split_at(length);
+    let (rest, _) = char('/').parse(rest)?;
+    let (rest, flags) = take_while(|c: char| c.is_ascii_alphabetic())(rest)?;
+
+    Ok((
+        rest,
+        Expression::Literal {
+            value: LiteralValue::Regex {
+                pattern: pattern.to_owned(),
+                flags: flags.to_owned(),
+            },
+            raw: format!("/{pattern}/{flags}"),
+        },
+    ))
+}
+
+// the length in bytes of the pattern, up to the first `/` that is neither escaped nor in
+// a character class; patterns do not span lines
+pub fn pattern_length(i: &str) -> Option<usize> {
+    let mut chars = i.char_indices();
+    let mut in_class = false;
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next().filter(|(_, c)| *c != '\n')?;
+            }
+            '\n' => return None,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => return (index > 0).then_some(index),
+            _ => {}
+        }
+    }
+
+    None
+}
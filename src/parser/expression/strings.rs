@@ -1,7 +1,4 @@
-use nom::{
-    branch::alt, bytes::complete::take_until, character::complete::char, combinator::map, IResult,
-    Parser,
-};
+use nom::{branch::alt, character::complete::char, combinator::map, IResult, Parser};
 use nom_supreme::{error::ErrorTree, ParserExt};
 
 use crate::parser::ast::{literal_value::LiteralValue, Expression};
@@ -9,25 +6,16 @@ use crate::parser::ast::{literal_value::LiteralValue, Expression};
 pub fn parse_string(i: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
     let (i, quote) = parse_quote(i)?;
 
-    let (i, result) = take_until(&*quote.to_string()).cut().parse(i)?;
-    let (i, c) = char(quote)(i)?;
+    let (result, i) = i.split_at(string_length(i, quote));
+    let (i, c) = char(quote).cut().parse(i)?;
 
-    return Ok((
+    Ok((
         i,
         Expression::Literal {
-            value: LiteralValue::Str(result.to_owned()),
+            value: LiteralValue::Str(unescape(result)),
             raw: c.to_string() + result + &c.to_string(),
         },
-    ));
-}
-
-pub fn parse_unchecked_string(i: &str) -> IResult<&str, String, ErrorTree<&str>> {
-    let (i, quote) = parse_quote(i)?;
-
-    let (i, result) = take_until(&*quote.to_string()).cut().parse(i)?;
-    let (i, c) = char(quote)(i)?;
-
-    return Ok((i, c.to_string() + result + &c.to_string()));
+    ))
 }
 
 fn parse_quote(i: &str) -> IResult<&str, char, ErrorTree<&str>> {
@@ -39,3 +27,48 @@ fn parse_quote(i: &str) -> IResult<&str, char, ErrorTree<&str>> {
         map(char(simple_quote), move |_| simple_quote),
     ))(i)
 }
+
+// the length in bytes of the string content, up to the first unescaped quote
+pub fn string_length(i: &str, quote: char) -> usize {
+    let mut chars = i.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if c == quote => return index,
+            _ => {}
+        }
+    }
+
+    i.len()
+}
+
+fn unescape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('0') => result.push('\0'),
+            Some(c @ ('\\' | '"' | '\'')) => result.push(c),
+            // unknown escapes are kept as written
+            Some(c) => {
+                result.push('\\');
+                result.push(c);
+            }
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
@@ -1,4 +1,7 @@
 pub mod return_expression;
+pub mod yield_statement;
+
+use std::sync::Arc;
 
 use self::return_expression::parse_return_statement;
 
@@ -15,6 +18,7 @@ use nom::{
 use nom_supreme::{error::ErrorTree, tag::complete::tag, ParserExt};
 
 pub fn parse_function(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
+    let (input, is_async) = parse_async(input)?;
     let (input, _) = tag("fn").complete().parse(input)?;
     let (input, _) = multispace1.parse(input)?;
 
@@ -35,7 +39,7 @@ pub fn parse_function(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
 
     let (input, (body, is_shortcut)) = parse_fn_body
         .cut()
-        .map(|(b, s)| (Box::new(b), s))
+        .map(|(b, s)| (Arc::new(b), s))
         .parse(input)?;
 
     let node = ASTNode::FunctionDeclaration {
@@ -43,11 +47,13 @@ pub fn parse_function(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
         params,
         body,
         is_shortcut,
+        is_async,
     };
 
     Ok((input, node))
 }
 pub fn parse_fn_expression(input: &str) -> IResult<&str, Expression, ErrorTree<&str>> {
+    let (input, is_async) = parse_async(input)?;
     let (input, _) = tag("|")(input)?;
     let (input, _) = multispace0(input)?;
 
@@ -58,17 +64,29 @@ pub fn parse_fn_expression(input: &str) -> IResult<&str, Expression, ErrorTree<&
 
     let (input, _) = multispace0(input)?;
 
-    let (input, (body, is_shortcut)) = parse_fn_body.map(|(b, s)| (Box::new(b), s)).parse(input)?;
+    let (input, (body, is_shortcut)) = parse_fn_body.map(|(b, s)| (Arc::new(b), s)).parse(input)?;
 
     let node = Expression::FnExpression {
         params,
         body,
         is_shortcut,
+        is_async,
     };
 
     Ok((input, node))
 }
 
+/// the `async` keyword of a function, calls then returning a promise
+fn parse_async(input: &str) -> IResult<&str, bool, ErrorTree<&str>> {
+    let (input, keyword) = tag("async")
+        .complete()
+        .terminated(multispace1)
+        .opt()
+        .parse(input)?;
+
+    Ok((input, keyword.is_some()))
+}
+
 fn parse_fn_params(input: &str) -> IResult<&str, Vec<Identifier>, ErrorTree<&str>> {
     let (input, params) = separated_list0(tag(","), parse_fn_param).parse(input)?;
 
@@ -0,0 +1,22 @@
+use nom::{
+    character::complete::{multispace0, satisfy},
+    combinator::not,
+    IResult, Parser,
+};
+use nom_supreme::{error::ErrorTree, tag::complete::tag, ParserExt};
+
+use crate::parser::{ast::ASTNode, expression::parse_expression};
+
+/// a statement making the enclosing function a generator
+pub fn parse_yield_statement(i: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
+    let (i, _) = tag("yield")
+        .complete()
+        .terminated(not(satisfy(|c| c.is_alphanumeric() || c == '_')))
+        .parse(i)?;
+
+    let (i, _) = multispace0(i)?;
+
+    let (i, argument) = parse_expression.cut().context("expression").parse(i)?;
+
+    Ok((i, ASTNode::YieldStatement { argument }))
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use super::{
     ast::{ASTNode, Expression},
     expression::parse_expression,
@@ -29,7 +31,7 @@ pub fn parse_if_statement(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>
     let (_, other_if) = tag("if").opt().parse(else_input)?;
 
     if other_if.is_none() {
-        let (else_input, alternate) = parse_block.map(|s| Some(Box::new(s))).parse(else_input)?;
+        let (else_input, alternate) = parse_block.map(|s| Some(Arc::new(s))).parse(else_input)?;
 
         let node = ASTNode::IfStatement {
             test,
@@ -43,7 +45,7 @@ pub fn parse_if_statement(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>
     let (else_input, _) = multispace0(else_input)?;
 
     let (input, alternate) = parse_if_statement
-        .map(|s| Some(Box::new(s)))
+        .map(|s| Some(Arc::new(s)))
         .parse(else_input)?;
 
     let node = ASTNode::IfStatement {
@@ -55,14 +57,14 @@ pub fn parse_if_statement(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>
     Ok((input, node))
 }
 
-fn parse_if_block(input: &str) -> IResult<&str, (Expression, Box<ASTNode>), ErrorTree<&str>> {
+fn parse_if_block(input: &str) -> IResult<&str, (Expression, Arc<ASTNode>), ErrorTree<&str>> {
     let (input, _) = tag("if").complete().parse(input)?;
     let (input, _) = multispace1.cut().parse(input)?;
 
     let (input, test) = parse_expression.cut().parse(input)?;
     let (input, _) = multispace0(input)?;
 
-    let (input, body) = parse_block.cut().map(|b| Box::new(b)).parse(input)?;
+    let (input, body) = parse_block.cut().map(Arc::new).parse(input)?;
 
     Ok((input, (test, body)))
 }
@@ -22,24 +22,12 @@ pub fn parse_import(i: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
 
     let (i, asterisk) = char('*').opt().parse(i)?;
 
-    let (i, specifiers) = if asterisk.is_none() {
-        let (i, specifiers) = separated_list1(
-            delimited(multispace0, char(','), multispace0),
-            parse_import_specifier,
-        )
-        .cut()
-        .parse(i)?;
-
-        let (i, _) = preceded(multispace0, char(',')).opt().parse(i)?;
-        let (i, _) = multispace1.cut().parse(i)?;
-
-        (i, specifiers)
-    } else {
+    let (i, specifiers) = if let Some(asterisk) = asterisk {
         let (i, local) = import_as.opt().parse(i)?;
         let (i, _) = multispace0(i)?;
 
         let asterisk = Identifier {
-            name: asterisk.unwrap().to_string(),
+            name: asterisk.to_string(),
         };
 
         if let Some(local) = local {
@@ -59,6 +47,18 @@ pub fn parse_import(i: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
                 }],
             )
         }
+    } else {
+        let (i, specifiers) = separated_list1(
+            delimited(multispace0, char(','), multispace0),
+            parse_import_specifier,
+        )
+        .cut()
+        .parse(i)?;
+
+        let (i, _) = preceded(multispace0, char(',')).opt().parse(i)?;
+        let (i, _) = multispace1.cut().parse(i)?;
+
+        (i, specifiers)
     };
 
     let (i, _) = tag("from").complete().cut().parse(i)?;
@@ -67,10 +67,10 @@ pub fn parse_import(i: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
     let (i, source) = parse_string.cut().context("import source").parse(i)?;
 
     let source = match source {
-        Expression::Literal { value, .. } => match value {
-            LiteralValue::Str(value) => ImportSource { value },
-            _ => unreachable!(),
-        },
+        Expression::Literal {
+            value: LiteralValue::Str(value),
+            ..
+        } => ImportSource { value },
         _ => unreachable!(),
     };
 
@@ -81,11 +81,18 @@ pub fn parse_import(i: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
         return Ok((i, import_declaration));
     }
 
-    let (i, _) = alt((tag("\n"), tag(","), tag(";"), tag("//").complete()))
-        .peek()
-        .context("unexpected")
-        .cut()
-        .parse(i)?;
+    let (i, _) = alt((
+        tag("\n"),
+        tag("\r\n"),
+        tag(","),
+        tag(";"),
+        tag("//").complete(),
+        tag("/*").complete(),
+    ))
+    .peek()
+    .context("unexpected")
+    .cut()
+    .parse(i)?;
 
     Ok((i, import_declaration))
 }
@@ -95,15 +102,13 @@ fn parse_import_specifier(i: &str) -> IResult<&str, ImportSpecifier, ErrorTree<&
     let (i, local_name) = import_as.opt().parse(i)?;
 
     match local_name {
-        Some(local_name) => {
-            return Ok((
-                i,
-                ImportSpecifier {
-                    local: local_name,
-                    imported: imported_name,
-                },
-            ));
-        }
+        Some(local_name) => Ok((
+            i,
+            ImportSpecifier {
+                local: local_name,
+                imported: imported_name,
+            },
+        )),
         None => Ok((
             i,
             ImportSpecifier {
@@ -121,5 +126,5 @@ fn import_as(i: &str) -> IResult<&str, Identifier, ErrorTree<&str>> {
     let (i, _) = multispace1.cut().parse(i)?;
     let (i, local_name) = parse_identifier.cut().parse(i)?;
 
-    return Ok((i, local_name));
+    Ok((i, local_name))
 }
@@ -0,0 +1,247 @@
+// splits a script into tokens, every byte of it belonging to one of them: the whitespace,
+// new lines and comments between the others included
+
+use std::fmt;
+
+use crate::reserved_keywords::RESERVED_KEYWORD;
+
+use super::{
+    ast::Span,
+    expression::{regex::pattern_length, strings::string_length},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    // one of the reserved keywords, `true`, `false` and `nil` included
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Regex,
+    // a binary, assignment or range operator, or the `>>` of returns
+    Operator,
+    // a bracket, a separator, the `.` of members or the `|` around parameters
+    Punctuation,
+    // a character no token starts with
+    Unknown,
+    Whitespace,
+    NewLine,
+    LineComment,
+    BlockComment,
+    // the empty token ending a script, never lexed
+    End,
+}
+
+impl TokenKind {
+    /// whether the token is left out by the parser, apart from the new lines ending statements
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            TokenKind::Whitespace
+                | TokenKind::NewLine
+                | TokenKind::LineComment
+                | TokenKind::BlockComment
+        )
+    }
+
+    pub fn is_comment(self) -> bool {
+        matches!(self, TokenKind::LineComment | TokenKind::BlockComment)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl Token {
+    pub fn text<'a>(&self, input: &'a str) -> &'a str {
+        &input[self.span.clone()]
+    }
+}
+
+// longest first, so that `..=` is not read as `..` followed by `=`
+const OPERATORS: [&str; 23] = [
+    "..=", "..", ">>", "**", "==", "!=", ">=", "<=", "&&", "||", "+=", "-=", "*=", "/=", "%=", "+",
+    "-", "*", "/", "%", "=", ">", "<",
+];
+
+const PUNCTUATION: [&str; 11] = ["|", ".", ",", ";", ":", "(", ")", "[", "]", "{", "}"];
+
+/// every token of the script, in the order they were written
+pub fn lex(input: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut start = 0;
+
+    // whether each brace still open starts the block of a statement, rather than an object or
+    // the body of a closure, and whether the last one closed did, another statement being able
+    // to start right after it
+    let mut braces: Vec<bool> = vec![];
+    let mut closed_block = false;
+
+    while start < input.len() {
+        let previous = tokens.iter().rev().find(|token| !token.kind.is_trivia());
+        let regex_allowed = previous.is_none_or(|token| {
+            operand_expected(token, input) || (closed_block && token.text(input) == "}")
+        });
+
+        let (kind, length) = next_token(&input[start..], regex_allowed);
+        let token = Token {
+            kind,
+            span: start..start + length,
+        };
+
+        match (kind, token.text(input)) {
+            (TokenKind::Punctuation, "{") => braces.push(opens_statement_block(&tokens, input)),
+            (TokenKind::Punctuation, "}") => closed_block = braces.pop().unwrap_or_default(),
+            _ => {}
+        }
+
+        tokens.push(token);
+        start += length;
+    }
+
+    tokens
+}
+
+/// whether a `{` following the tokens starts the block of a statement
+fn opens_statement_block(tokens: &[Token], input: &str) -> bool {
+    let previous = tokens.iter().rev().find(|token| !token.kind.is_trivia());
+
+    // after the end of a condition, of the parameters of a function or of what a loop goes
+    // through, the parameters of a closure expecting an operand
+    previous.is_some_and(|token| match (token.kind, token.text(input)) {
+        (TokenKind::Keyword, "else" | "try" | "catch") => true,
+        _ => !operand_expected(token, input),
+    })
+}
+
+/// whether an operand may follow the token, a `/` then starting a regular expression
+/// rather than being a division
+fn operand_expected(token: &Token, input: &str) -> bool {
+    match token.kind {
+        TokenKind::Operator => true,
+        TokenKind::Punctuation => !matches!(token.text(input), ")" | "]" | "}"),
+        TokenKind::Keyword => !matches!(token.text(input), "true" | "false" | "nil"),
+        _ => false,
+    }
+}
+
+/// the token `input` starts with, read as if an operand could start there
+pub fn first_token(input: &str) -> Option<Token> {
+    if input.is_empty() {
+        return None;
+    }
+
+    let (kind, length) = next_token(input, true);
+    Some(Token {
+        kind,
+        span: 0..length,
+    })
+}
+
+/// the kind and length in bytes of the token `input` starts with
+fn next_token(input: &str, regex_allowed: bool) -> (TokenKind, usize) {
+    let c = input.chars().next().unwrap_or_default();
+
+    match c {
+        '\n' => (TokenKind::NewLine, 1),
+        c if c.is_whitespace() => (
+            TokenKind::Whitespace,
+            length_while(input, |c| c.is_whitespace() && c != '\n'),
+        ),
+        _ if input.starts_with("//") => (
+            TokenKind::LineComment,
+            input.find('\n').unwrap_or(input.len()),
+        ),
+        _ if input.starts_with("/*") => (
+            TokenKind::BlockComment,
+            input[2..].find("*/").map_or(input.len(), |end| end + 4),
+        ),
+        '"' | '\'' => {
+            let length = 1 + string_length(&input[1..], c);
+            (TokenKind::String, (length + 1).min(input.len()))
+        }
+        '/' if regex_allowed && pattern_length(&input[1..]).is_some() => {
+            let length = 2 + pattern_length(&input[1..]).unwrap_or_default();
+            let flags = length_while(&input[length..], |c| c.is_ascii_alphabetic());
+            (TokenKind::Regex, length + flags)
+        }
+        '0'..='9' => (TokenKind::Number, number_length(input)),
+        c if c.is_ascii_alphabetic() || c == '_' => {
+            let length = word_length(input);
+            match RESERVED_KEYWORD.contains(&&input[..length]) {
+                true => (TokenKind::Keyword, length),
+                false => (TokenKind::Identifier, length),
+            }
+        }
+        _ => {
+            let starts = |symbols: &[&str]| {
+                symbols
+                    .iter()
+                    .find(|s| input.starts_with(*s))
+                    .map(|s| s.len())
+            };
+
+            if let Some(length) = starts(&OPERATORS) {
+                (TokenKind::Operator, length)
+            } else if let Some(length) = starts(&PUNCTUATION) {
+                (TokenKind::Punctuation, length)
+            } else {
+                (TokenKind::Unknown, c.len_utf8())
+            }
+        }
+    }
+}
+
+fn length_while(input: &str, predicate: impl Fn(char) -> bool) -> usize {
+    input.find(|c| !predicate(c)).unwrap_or(input.len())
+}
+
+fn word_length(input: &str) -> usize {
+    length_while(input, |c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// digits with underscores between them, a fraction only when a digit follows the dot so
+// that `0..10` is a range, and an exponent
+fn number_length(input: &str) -> usize {
+    let mut length = word_length(input);
+    let rest = &input[length..];
+
+    if rest.starts_with('.') && rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+        length += 1 + word_length(&rest[1..]);
+    }
+
+    let rest = &input[length..];
+    if input[..length].ends_with(['e', 'E'])
+        && rest.starts_with(['+', '-'])
+        && rest[1..].starts_with(|c: char| c.is_ascii_digit())
+    {
+        length += 1 + word_length(&rest[1..]);
+    }
+
+    length
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TokenKind::Keyword => "keyword",
+            TokenKind::Identifier => "identifier",
+            TokenKind::Number => "number",
+            TokenKind::String => "string",
+            TokenKind::Regex => "regex",
+            TokenKind::Operator => "operator",
+            TokenKind::Punctuation => "punctuation",
+            TokenKind::Unknown => "unknown",
+            TokenKind::Whitespace => "whitespace",
+            TokenKind::NewLine => "new line",
+            TokenKind::LineComment => "line comment",
+            TokenKind::BlockComment => "block comment",
+            TokenKind::End => "end",
+        };
+
+        write!(f, "{name}")
+    }
+}
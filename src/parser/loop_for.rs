@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use super::{
     assignment::initial::VariableKeyword,
     ast::{identifier::Identifier, ASTNode},
@@ -35,7 +37,7 @@ pub fn parse_for_statement(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str
 
     let (input, _) = multispace0(input)?;
 
-    let (input, body) = parse_block.cut().map(|b| Box::new(b)).parse(input)?;
+    let (input, body) = parse_block.cut().map(Arc::new).parse(input)?;
 
     let node = ASTNode::ForStatement {
         kind,
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use super::{ast::ASTNode, expression::parse_expression, parse_block};
 use nom::{
     character::complete::{multispace0, multispace1},
@@ -11,12 +13,11 @@ pub fn parse_while_statement(input: &str) -> IResult<&str, ASTNode, ErrorTree<&s
 
     let (input, test) = parse_expression.parse(input)?;
     let (input, _) = multispace0(input)?;
-    println!("efef {:?}", test);
     let (input, body) = parse_block.cut().parse(input)?;
 
     let node = ASTNode::WhileStatement {
         test,
-        body: Box::new(body),
+        body: Arc::new(body),
     };
 
     Ok((input, node))
@@ -9,31 +9,45 @@ use self::binary::BinaryOperator;
 use crate::parser::ast::Expression;
 
 pub fn build_binary_expression(
-    mut expressions: Vec<Expression>,
-    mut operators: Vec<BinaryOperator>,
+    expressions: Vec<Expression>,
+    operators: Vec<BinaryOperator>,
 ) -> Expression {
+    nest_by_precedence(
+        expressions,
+        operators,
+        BinaryOperator::get_precedence,
+        |left, operator, right| Expression::BinaryExpression {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        },
+    )
+}
+
+/// joins the operands two by two, the operator with the highest precedence first and the
+/// leftmost one among those with the same precedence
+pub fn nest_by_precedence<T, O>(
+    mut operands: Vec<T>,
+    mut operators: Vec<O>,
+    precedence: impl Fn(&O) -> u8,
+    mut join: impl FnMut(T, O, T) -> T,
+) -> T {
     while !operators.is_empty() {
         let max_precedence_index = operators
             .iter()
             .enumerate()
             .rev()
-            .max_by_key(|(_, op)| op.get_precedence())
+            .max_by_key(|(_, op)| precedence(op))
             .map(|(index, _)| index);
 
         if let Some(index) = max_precedence_index {
             let operator = operators.remove(index);
-            let right = expressions.remove(index + 1);
-            let left = expressions.remove(index);
-
-            let binary_op = Expression::BinaryExpression {
-                left: Box::new(left),
-                operator,
-                right: Box::new(right),
-            };
+            let right = operands.remove(index + 1);
+            let left = operands.remove(index);
 
-            expressions.insert(index, binary_op);
+            operands.insert(index, join(left, operator, right));
         }
     }
 
-    expressions.pop().unwrap()
+    operands.pop().unwrap()
 }
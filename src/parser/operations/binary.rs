@@ -88,15 +88,15 @@ impl BinaryOperator {
     pub fn get_precedence(&self) -> u8 {
         match self {
             BinaryOperator::And | BinaryOperator::Or => 0,
-            BinaryOperator::Plus | BinaryOperator::Minus => 1,
-            BinaryOperator::Times | BinaryOperator::Divide | BinaryOperator::Modulo => 2,
-            BinaryOperator::Exponential => 3,
             BinaryOperator::Equal
             | BinaryOperator::NotEqual
             | BinaryOperator::Greater
             | BinaryOperator::GreaterOrEqual
             | BinaryOperator::Smaller
-            | BinaryOperator::SmallerOrEqual => 4,
+            | BinaryOperator::SmallerOrEqual => 1,
+            BinaryOperator::Plus | BinaryOperator::Minus => 2,
+            BinaryOperator::Times | BinaryOperator::Divide | BinaryOperator::Modulo => 3,
+            BinaryOperator::Exponential => 4,
         }
     }
 }
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use super::{
+    ast::{identifier::parse_identifier, ASTNode},
+    parse_block,
+};
+use nom::{
+    character::complete::{multispace0, multispace1, satisfy},
+    combinator::not,
+    IResult, Parser,
+};
+use nom_supreme::{error::ErrorTree, tag::complete::tag, ParserExt};
+
+pub fn parse_try_statement(input: &str) -> IResult<&str, ASTNode, ErrorTree<&str>> {
+    // `tryIt()` is a call, not a try statement
+    let (input, _) = tag("try")
+        .complete()
+        .terminated(not(satisfy(|c| c.is_alphanumeric() || c == '_')))
+        .parse(input)?;
+    let (input, _) = multispace0(input)?;
+
+    let (input, block) = parse_block.cut().map(Arc::new).parse(input)?;
+    let (input, _) = multispace0(input)?;
+
+    let (input, _) = tag("catch")
+        .complete()
+        .terminated(not(satisfy(|c| c.is_alphanumeric() || c == '_')))
+        .cut()
+        .parse(input)?;
+
+    // the error binding is optional: `catch {` or `catch err {`
+    let (input, param) = parse_identifier
+        .preceded_by(multispace1)
+        .opt()
+        .parse(input)?;
+    let (input, _) = multispace0(input)?;
+
+    let (input, handler) = parse_block.cut().map(Arc::new).parse(input)?;
+
+    let node = ASTNode::TryStatement {
+        block,
+        param,
+        handler,
+    };
+
+    Ok((input, node))
+}
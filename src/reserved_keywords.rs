@@ -1,4 +1,5 @@
-pub const RESERVED_KEYWORD: [&str; 20] = [
+pub const RESERVED_KEYWORD: [&str; 25] = [
     "true", "false", "nil", "let", "var", "import", "export", "from", "anon", "fn", "return",
-    "for", "in", "while", "as", "class", "define", "method", "if", "else",
+    "for", "in", "while", "as", "class", "define", "method", "if", "else", "try", "catch", "yield",
+    "async", "await",
 ];
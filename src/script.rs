@@ -1,14 +1,18 @@
-use crate::parser::{
-    ast::{self, identifier::parse_unchecked_id},
-    comment::jump_comments,
-    errors::{get_opposing_tag, SyntaxError},
-    expression::strings::parse_unchecked_string,
-    parse_input,
+use crate::{
+    errors::ComfyError,
+    interpreter::Interpreter,
+    minify::{optimize, source_map, verify, Minified, MinifyOptions},
+    parser::{
+        ast,
+        comment::jump_comments,
+        errors::{get_opposing_tag, SyntaxError},
+        lexer::first_token,
+        parse_input,
+    },
 };
 use codespan_reporting::{diagnostic::Label, files::SimpleFile};
-use nom::{branch::alt, Parser};
 use nom_supreme::error::GenericErrorTree;
-use std::{error::Error, fmt::Display};
+use std::{error::Error, fmt::Display, path::Path};
 
 #[derive(Debug)]
 pub struct ComfyScript<Name: Display + Clone> {
@@ -23,7 +27,8 @@ impl<Name: Display + Clone> ComfyScript<Name> {
     pub fn new(name: Name, content: String) -> Self {
         Self { name, content }
     }
-    pub fn execute(&self) -> Result<(), (SyntaxError<()>, SimpleFile<Name, String>)> {
+    #[allow(clippy::result_large_err)]
+    pub fn execute(&self) -> Result<(), (ComfyError, SimpleFile<Name, String>)> {
         let content = &self.content;
         let file = SimpleFile::new(self.name.to_owned(), content.to_owned());
 
@@ -31,10 +36,10 @@ impl<Name: Display + Clone> ComfyScript<Name> {
             return Ok(());
         }
 
-        let program = match parse_input(&content) {
+        let program = match parse_input(content) {
             Ok(r) => r,
             Err(e) => {
-                return Err((self.match_error(&e), file));
+                return Err((ComfyError::Syntax(self.match_error(&e)), file));
             }
         };
 
@@ -43,11 +48,19 @@ impl<Name: Display + Clone> ComfyScript<Name> {
             _ => unreachable!(),
         };
 
-        for node in program {
-            println!("{:?}", node);
+        let mut interpreter = Interpreter::new();
+
+        // the event loop runs whatever the script scheduled once its last statement ran
+        match interpreter
+            .run(&program)
+            .and_then(|_| interpreter.run_event_loop())
+        {
+            Ok(()) => Ok(()),
+            Err(e) => match e.exit_code {
+                Some(code) => Err((ComfyError::Exit(code), file)),
+                None => Err((ComfyError::Runtime(e), file)),
+            },
         }
-
-        Ok(())
     }
 
     fn match_error(
@@ -59,7 +72,7 @@ impl<Name: Display + Clone> ComfyScript<Name> {
                 let ctx = contexts[contexts.len() - 1].1;
                 let location = contexts[0].0;
 
-                let location = self.location_with_last_no_whitespace(&location);
+                let location = self.location_with_last_no_whitespace(location);
 
                 let (place, length, found) = self.get_error_data(location);
 
@@ -89,7 +102,7 @@ impl<Name: Display + Clone> ComfyScript<Name> {
                 }
             }
             nom_supreme::error::GenericErrorTree::Base { location, kind } => {
-                let location = self.location_with_last_no_whitespace(&location);
+                let location = self.location_with_last_no_whitespace(location);
 
                 let err = match kind {
                     nom_supreme::error::BaseErrorKind::Expected(expec) => match expec {
@@ -105,7 +118,6 @@ impl<Name: Display + Clone> ComfyScript<Name> {
                         nom_supreme::error::Expectation::Char(expected_token) => {
                             let closing_tag = expected_token.to_string();
                             let opening_tag = get_opposing_tag(&closing_tag).to_owned();
-                            println!("open {}, close {}", opening_tag, closing_tag);
 
                             let (place, length, _) = self.get_error_data(location);
 
@@ -141,7 +153,6 @@ impl<Name: Display + Clone> ComfyScript<Name> {
                         }
                     },
                     nom_supreme::error::BaseErrorKind::Kind(kind) => {
-                        println!("{:?}", kind);
                         let (place, length, found) = self.get_error_data(location);
 
                         match kind {
@@ -195,13 +206,13 @@ impl<Name: Display + Clone> ComfyScript<Name> {
         let error_place = self.content.len() - error_content.len();
 
         let new_error_content = jump_comments(error_content)
-            .and_then(|(i, _)| Ok(i))
+            .map(|(i, _)| i)
             .unwrap_or(error_content);
 
-        let error_length = alt((parse_unchecked_id, parse_unchecked_string))
-            .parse(&new_error_content)
-            .and_then(|(_, w)| Ok(w.len()))
-            .unwrap_or(1);
+        // the whole token the error is at
+        let error_length = first_token(new_error_content)
+            .filter(|token| !token.kind.is_trivia())
+            .map_or(1, |token| token.span.len());
         let found = &new_error_content[0..error_length];
 
         (
@@ -223,29 +234,50 @@ impl<Name: Display + Clone> ComfyScript<Name> {
         }
     }
 
-    pub fn minify(&self) -> Result<String, Box<dyn Error>> {
+    /// the minified script, `output` being where it is written
+    pub fn minify(
+        &self,
+        options: &MinifyOptions,
+        output: &Path,
+    ) -> Result<Minified, Box<dyn Error>> {
         let content = &self.content;
 
         if content.is_empty() {
-            return Ok(content.to_owned());
+            return Ok(Minified {
+                script: content.to_owned(),
+                source_map: None,
+            });
         }
 
-        let program = match parse_input(&content) {
+        let mut program = match parse_input(content) {
             Ok(r) => r,
             Err(_) => return Err("Failed to parse script! Contains an error!".into()),
         };
 
-        let mut buffer = String::new();
+        if options.aggressive {
+            optimize(&mut program);
+        }
 
-        let program = match program {
-            ast::ASTNode::Program { body } => body,
-            _ => unreachable!(),
-        };
+        let mut minified = program.to_string();
 
-        for node in program {
-            buffer.push_str(&node.to_string())
+        if options.verify {
+            verify(&program, &minified, "minified")?;
         }
 
-        Ok(buffer)
+        let source_map = if options.source_map {
+            let source = self.name.to_string();
+            let map =
+                source_map::generate(&program, &minified, output, Path::new(&source), content)?;
+
+            minified.push_str(&source_map::url_comment(output));
+            Some(map)
+        } else {
+            None
+        };
+
+        Ok(Minified {
+            script: minified,
+            source_map,
+        })
     }
 }
@@ -0,0 +1,86 @@
+// helpers shared by the integration tests, which run the `comfy` binary on scripts written to
+// a temporary folder
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// a folder of the system temporary one, unique to the test and removed once it is dropped
+pub struct TempDir(PathBuf);
+
+pub fn temp_dir(name: &str) -> TempDir {
+    let dir = std::env::temp_dir().join(format!("comfy_{name}_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    TempDir(dir)
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn comfy() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_comfy"))
+}
+
+/// runs the script, which must succeed
+pub fn run(script: &Path) -> Output {
+    run_with_args(script, &[])
+}
+
+/// runs the script with the arguments following its path, which must succeed
+pub fn run_with_args(script: &Path, args: &[&str]) -> Output {
+    let output = comfy().arg(script).args(args).output().unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}: {}",
+        script.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+/// runs the script, which must fail, returning what it wrote to stderr
+pub fn fail(script: &Path) -> String {
+    let output = comfy().arg(script).output().unwrap();
+
+    assert!(!output.status.success(), "{}", script.display());
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// the `.cfs` fixtures of the tests folder
+pub fn fixtures() -> Vec<PathBuf> {
+    scripts_in("tests")
+}
+
+/// the `.cfs` fixtures of tests/parse, which are only meant to be parsed
+pub fn parse_fixtures() -> Vec<PathBuf> {
+    scripts_in("tests/parse")
+}
+
+fn scripts_in(folder: &str) -> Vec<PathBuf> {
+    let mut scripts: Vec<PathBuf> =
+        fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(folder))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "cfs"))
+            .collect();
+    scripts.sort();
+
+    scripts
+}
@@ -0,0 +1,116 @@
+// parses scripts to their concrete syntax tree, which must write them back byte for byte and
+// give the tree the parser produces
+
+mod common;
+
+use std::fs;
+
+use comfy::parser::{
+    ast::{ASTNode, Span},
+    cst::{parse_tree, Element, Node, NodeKind},
+    parse_input,
+};
+use common::{fixtures, parse_fixtures};
+
+const ODD: &[&str] = &[
+    "",
+    "\n\n\n",
+    "// only a comment",
+    "let a = 1",
+    "let a = 1\n",
+    "  \t let   a\t=  1   \n\n   ",
+    "let a = 1 // trailing\n\n// between\n\nlet b = 2 /* after */",
+    "let a = /* inside */ 1 + /* twice */ 2",
+    "let a = [\n  1, // first\n\n  2,\n]\n",
+    "fn f(a, b) {\n\t// body\n\n\t>> a + /* first */ b\n}\n// the end",
+    "if true {\n} else { // empty\n}\nwhile false { /* never */ }",
+    "let s = \"é // not a comment\" // ünïcode\n",
+    "let r = 10 / /2/i // a regex\n",
+    "let a = 1\r\nvar b = 2 // windows\r\nb = 3\r\nif a == 1 {\r\n    b = a\r\n}\r\n",
+    "let a = 1 /* after */\nprint(a) /* the end */",
+    "if true { }\n/[//]/.test(\"/\") // the only comment\n",
+    "f /* not a call */ (1)\nlet a = b\n  .c // member\n  - 1\n",
+    "let returned = [1., -2]\nreturned.push(..3)\n",
+    "import * as m from \"x\"\nimport a as b, c, from 'y'",
+    "let r = 0..10 step 2\nfor i in 1.. { }\nlet f = async |a, b| >> a ** b ** 2 - 1",
+    "try { tryIt() } catch err {\n} // after\nif a {} else if b {} else { c[0] += 1 }",
+];
+
+#[test]
+fn cst_fixtures() {
+    for path in fixtures().into_iter().chain(parse_fixtures()) {
+        let input = fs::read_to_string(&path).unwrap();
+        round_trip(&input);
+    }
+}
+
+#[test]
+fn cst_odd_layouts() {
+    for input in ODD {
+        round_trip(input);
+    }
+}
+
+#[test]
+fn cst_comments() {
+    let tree = parse_tree("if true { }\n/[//]/.test(\"/\") // the only comment\n").unwrap();
+    let comments: Vec<&str> = tree
+        .comments()
+        .into_iter()
+        .map(|comment| comment.text.as_str())
+        .collect();
+
+    assert_eq!(comments, ["// the only comment"]);
+}
+
+#[test]
+fn cst_expressions() {
+    let tree = parse_tree("let a = f(1) + b.c // sum\n").unwrap();
+
+    let mut kinds = vec![];
+    walk(&tree, &mut kinds);
+    assert_eq!(
+        kinds,
+        [
+            NodeKind::Program,
+            NodeKind::VariableDeclaration,
+            NodeKind::Declarator,
+            NodeKind::Binary,
+            NodeKind::Call,
+            NodeKind::Identifier,
+            NodeKind::Arguments,
+            NodeKind::Literal,
+            NodeKind::Member,
+            NodeKind::Identifier,
+        ]
+    );
+}
+
+fn walk(node: &Node, kinds: &mut Vec<NodeKind>) {
+    kinds.push(node.kind);
+    for child in &node.children {
+        if let Element::Node(child) = child {
+            walk(child, kinds);
+        }
+    }
+}
+
+fn round_trip(input: &str) {
+    let tree = parse_tree(input).unwrap_or_else(|err| panic!("{input:?}: {err:?}"));
+    assert_eq!(tree.to_string(), input);
+
+    let (ast, parsed) = (tree.ast(), parse_input(input).unwrap());
+    assert_eq!(ast, parsed, "{input:?}");
+    // the formatter places comments by the spans of the statements
+    assert_eq!(spans(&ast), spans(&parsed), "{input:?}");
+}
+
+fn spans(program: &ASTNode) -> Vec<Span> {
+    let ASTNode::Program { body } = program else {
+        unreachable!()
+    };
+
+    body.iter()
+        .map(|statement| statement.span.clone())
+        .collect()
+}
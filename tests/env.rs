@@ -0,0 +1,32 @@
+// runs a script with arguments following its path, which it reads from `env.args` before
+// exiting with a status of its own
+
+mod common;
+
+use std::fs;
+
+use common::{comfy, temp_dir};
+
+const SCRIPT: &str = r#"import * as env from "env"
+assert(env.args == ["first", "--flag", "two words"])
+print(env.args.len())
+env.exit(3)
+"#;
+
+#[test]
+fn env_args_and_exit() {
+    let dir = temp_dir("env");
+
+    let script = dir.join("script.cfs");
+    fs::write(&script, SCRIPT).unwrap();
+
+    let output = comfy()
+        .arg(&script)
+        .args(["first", "--flag", "two words"])
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(3), "{stderr}");
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("3\n"));
+}
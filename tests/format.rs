@@ -0,0 +1,162 @@
+// formats scripts with `comfy fmt`, checking the layout chosen for the width, that comments
+// and blank lines are kept, and that every fixture formats to a script formatted already
+
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{comfy, fixtures, parse_fixtures, run, temp_dir};
+
+const SCRIPT: &str = r#"// leading comment
+import   *   from "math"
+var   total=1+2*3   // trailing
+fn add(a,b){   >> a+b }
+
+
+fn long(first_parameter, second_parameter, third_parameter, fourth_parameter, fifth_parameter, sixth) {
+  let x = first_parameter + second_parameter + third_parameter + fourth_parameter + fifth_parameter
+  /* block
+     comment */
+  return x
+}
+let obj = {name: "a very long name that goes on and on", other: [1,2,3,4,5,6,7,8,9,10], third: nil}
+let doubled = [1, 2].map(|x| {
+// twice
+x*2
+})
+if total>2{assert(true)}else{assert(false)}
+total+=1
+let five = 2 + /* kept as written */ 3
+assert(add(total, five) == 13)
+"#;
+
+const FORMATTED: &str = r#"// leading comment
+import * from "math"
+var total = 1 + 2 * 3 // trailing
+fn add(a, b) {
+    >> a + b
+}
+
+fn long(
+    first_parameter,
+    second_parameter,
+    third_parameter,
+    fourth_parameter,
+    fifth_parameter,
+    sixth
+) {
+    let x = first_parameter +
+        second_parameter +
+        third_parameter +
+        fourth_parameter +
+        fifth_parameter
+    /* block
+     comment */
+    return x
+}
+let obj = {
+    name: "a very long name that goes on and on",
+    other: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+    third: nil,
+}
+let doubled = [1, 2].map(|x| {
+    // twice
+    x * 2
+})
+if total > 2 {
+    assert(true)
+} else {
+    assert(false)
+}
+total += 1
+let five = 2 + /* kept as written */ 3
+assert(add(total, five) == 13)
+"#;
+
+#[test]
+fn format_script() {
+    let dir = temp_dir("format");
+
+    let script = dir.join("script.cfs");
+    fs::write(&script, SCRIPT).unwrap();
+
+    assert_eq!(format(&script, &["--stdout"]), FORMATTED);
+
+    // the narrower the width, the more lists are broken
+    let narrow = format(&script, &["--stdout", "--width", "40", "--indent", "2"]);
+    assert!(narrow.contains("  other: [\n    1,\n    2,\n"), "{narrow}");
+    assert!(
+        narrow.contains("  let x = first_parameter +\n    second"),
+        "{narrow}"
+    );
+
+    // scripts are formatted in place, and run the same
+    format(&script, &[]);
+    assert_eq!(fs::read_to_string(&script).unwrap(), FORMATTED);
+    run(&script);
+}
+
+#[test]
+fn format_check() {
+    let dir = temp_dir("format_check");
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    fs::write(dir.join("formatted.cfs"), FORMATTED).unwrap();
+    fs::write(dir.join("nested/script.cfs"), SCRIPT).unwrap();
+
+    let output = comfy()
+        .args(["fmt", "--check"])
+        .arg(&*dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("nested/script.cfs: not formatted"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("formatted.cfs: "), "{stderr}");
+    assert!(stderr.contains("1 script(s) are not formatted"), "{stderr}");
+    assert_eq!(
+        fs::read_to_string(dir.join("nested/script.cfs")).unwrap(),
+        SCRIPT
+    );
+
+    let stderr = format(&dir, &[]);
+    assert!(stderr.contains("nested/script.cfs: formatted"), "{stderr}");
+    format(&dir, &["--check"]);
+}
+
+#[test]
+fn format_fixtures() {
+    let dir = temp_dir("format_fixtures");
+
+    for path in fixtures().into_iter().chain(parse_fixtures()) {
+        let formatted = format(&path, &["--stdout"]);
+        let copy = dir.join(path.file_name().unwrap());
+        fs::write(&copy, &formatted).unwrap();
+
+        assert_eq!(
+            format(&copy, &["--stdout"]),
+            formatted,
+            "{}",
+            path.display()
+        );
+    }
+}
+
+fn format(script: &Path, options: &[&str]) -> String {
+    let output = comfy()
+        .arg("fmt")
+        .args(options)
+        .arg(script)
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(output.status.success(), "{}: {stderr}", script.display());
+
+    match options.contains(&"--stdout") {
+        true => String::from_utf8_lossy(&output.stdout).into_owned(),
+        false => stderr,
+    }
+}
@@ -0,0 +1,219 @@
+// runs a script using the http module against a loopback server started by the test,
+// the server echoing every request back as JSON
+
+mod common;
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use common::{run_with_args, temp_dir};
+
+const SCRIPT: &str = r#"
+import * as http from "http"
+import * as promise from "promise"
+import * as time from "time"
+import args from "env"
+
+let base = args[0]
+
+var headers = {}
+headers["X-Test"] = "yes"
+let response = http.get(base + "/hello", { query: { name: "comfy script", n: 1 }, headers: headers })
+assert(response.status == 200 && response.ok && response.statusText == "OK")
+assert(response.headers["content-type"] == "application/json")
+var echo = response.json()
+assert(echo.verb == "GET")
+assert(echo.path == "/hello")
+assert(echo.query == "name=comfy+script&n=1" || echo.query == "n=1&name=comfy+script")
+assert(echo.headers["x-test"] == "yes")
+assert(typeof(response.text()) == "string" && response.bytes() == bytes(response.text()))
+
+echo = http.post(base + "/items", { name: "a", tags: [1, 2] }).json()
+assert(echo.verb == "POST")
+assert(echo.headers["content-type"] == "application/json")
+assert(echo.body == '{"name":"a","tags":[1,2]}')
+
+headers = {}
+headers["Content-Type"] = "text/x-comfy"
+echo = http.put(base + "/items/1", "plain", { headers: headers }).json()
+assert(echo.verb == "PUT" && echo.body == "plain")
+assert(echo.headers["content-type"] == "text/x-comfy")
+
+echo = http.delete(base + "/items/1").json()
+assert(echo.verb == "DELETE" && echo.body == "")
+
+echo = http.request("patch", base + "/items/1", { json: [true, nil] }).json()
+assert(echo.verb == "PATCH" && echo.body == "[true,null]")
+
+let missing = http.get(base + "/status/404")
+assert(missing.status == 404 && missing.ok == false)
+assert(missing.text() == "status 404")
+
+var caught = nil
+try {
+    http.get(base + "/slow", { timeout: 100 })
+} catch err {
+    caught = err
+}
+assert(caught.code == "E127" && caught.kind == "TimedOut")
+
+caught = nil
+try {
+    http.get(base, { retries: 3 })
+} catch err {
+    caught = err
+}
+assert(caught.code == "E120")
+
+// timeouts too long to be represented are refused, the others waited for
+for timeout in [1e300, 1.8e22, -1] {
+    caught = nil
+    try {
+        http.get(base, { timeout: timeout })
+    } catch err {
+        caught = err
+    }
+    assert(caught.code == "E120")
+}
+assert(http.get(base + "/hello", { timeout: 1e15 }).ok)
+
+caught = nil
+try {
+    http.get("http://127.0.0.1:1/")
+} catch err {
+    caught = err
+}
+assert(caught.code == "E127" && caught.url == "http://127.0.0.1:1/")
+
+// fetches run on other threads, slow requests overlapping
+let start = time.instant()
+let responses = await promise.all([
+    http.fetch(base + "/slow"),
+    http.fetch(base + "/slow"),
+    http.fetch(base + "/items", { method: "post", json: [1] }),
+])
+assert(time.elapsed(start) < time.seconds(3.5))
+assert(responses[0].text() == "slow" && responses[1].text() == "slow")
+echo = responses[2].json()
+assert(echo.verb == "POST" && echo.body == "[1]")
+
+caught = nil
+try {
+    await http.fetch("http://127.0.0.1:1/")
+} catch err {
+    caught = err
+}
+assert(caught.code == "E127")
+
+caught = nil
+try {
+    http.fetch(base, { method: "not a method" })
+} catch err {
+    caught = err
+}
+assert(caught.code == "E120")
+"#;
+
+#[test]
+fn http_client() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || respond(stream));
+        }
+    });
+
+    let dir = temp_dir("http_client");
+    let script = dir.join("script.cfs");
+    fs::write(&script, SCRIPT).unwrap();
+
+    run_with_args(&script, &[&base]);
+}
+
+fn respond(stream: TcpStream) {
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let target = parts.next().unwrap_or_default().to_owned();
+
+    let mut headers = Vec::new();
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        let (name, value) = line.split_once(':').unwrap();
+        let (name, value) = (name.to_lowercase(), value.trim().to_owned());
+        if name == "content-length" {
+            content_length = value.parse().unwrap();
+        }
+        headers.push((name, value));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+
+    let (status, content_type, body) = if path == "/slow" {
+        thread::sleep(Duration::from_secs(2));
+        (200, "text/plain", "slow".to_owned())
+    } else if let Some(status) = path.strip_prefix("/status/") {
+        (
+            status.parse().unwrap(),
+            "text/plain",
+            format!("status {status}"),
+        )
+    } else {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}", quote(name), quote(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        let echo = format!(
+            r#"{{"verb":{},"path":{},"query":{},"headers":{{{headers}}},"body":{}}}"#,
+            quote(&method),
+            quote(path),
+            quote(query),
+            quote(&String::from_utf8_lossy(&body)),
+        );
+        (200, "application/json", echo)
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        if status == 200 { "OK" } else { "Not Found" },
+        body.len(),
+    );
+
+    // the client may have given up already, as with timeouts
+    let _ = (&stream).write_all(response.as_bytes());
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,83 @@
+// runs scripts in a pipeline, feeding them stdin and checking exactly what they write to
+// stdout and stderr
+
+mod common;
+
+use std::{fs, io::Write, process::Stdio};
+
+use common::{comfy, temp_dir};
+
+const LINES: &str = r#"import * as io from "io"
+
+let first = io.readLine()
+let second = io.readLine()
+io.printWith([first, second], {sep: "|", end: ";\n"})
+
+for line in io.lines() {
+    print(line.len(), line)
+}
+print(io.readLine())
+
+io.write("no", " ", "newline")
+io.eprint("to", "stderr")
+io.eprintWith(["a", "b"], {sep: "-", end: "!\n"})
+print()
+"#;
+
+const ALL: &str = r#"import * as io from "io"
+
+let text = io.readAll()
+print(text.len(), text == "a\r\nb")
+print(io.readAll() == "", io.readLine())
+"#;
+
+#[test]
+fn io_lines() {
+    // windows line endings and a last line without one
+    let (stdout, stderr) = pipe("io_lines", LINES, "one\r\ntwo\nthree\r\nfour");
+
+    assert_eq!(stdout, "one|two;\n5 three\n4 four\nnil\nno newline\n");
+    assert_eq!(stderr, "to stderr\na-b!\n");
+}
+
+#[test]
+fn io_read_all() {
+    let (stdout, stderr) = pipe("io_read_all", ALL, "a\r\nb");
+
+    assert_eq!(stdout, "4 true\ntrue nil\n");
+    assert_eq!(stderr, "");
+}
+
+/// the stdout and stderr of the script fed `stdin`, without the execution time
+fn pipe(name: &str, script: &str, stdin: &str) -> (String, String) {
+    let dir = temp_dir(name);
+
+    let path = dir.join("script.cfs");
+    fs::write(&path, script).unwrap();
+
+    let mut child = comfy()
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(output.status.success(), "{stderr}");
+
+    let stderr = match stderr.rfind("Execution time: ") {
+        Some(end) => stderr[..end].to_owned(),
+        None => stderr,
+    };
+
+    (stdout, stderr)
+}
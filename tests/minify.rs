@@ -0,0 +1,331 @@
+// minifies every fixture with `--verify`, and scripts covering the spellings that need
+// care and the rewrites of `--aggressive`, which must run the same once minified
+
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{comfy, fail, fixtures, parse_fixtures, run, temp_dir};
+
+const SCRIPT: &str = r#"
+import floor from "math"
+
+// comments are dropped
+let y = { one: 1 }
+let key = "one"
+assert(y[key] == 1 && y["one"] == 1) // computed members keep their brackets
+
+/* precedence */
+let a = (1 + 2) * 3
+let b = 1 - (2 - 3)
+let c = 2 ** (3 ** 2)
+let d = (2 ** 3) ** 2
+let e = (1..4).len()
+let f = |x| >> x + 1
+let g = (|x| >> x * 2)(3)
+let h = floor((1.5))
+
+let s = 'it\'s'
+let t = "say \"hi\""
+let n = 1_000_000
+let m = 0.000001
+let r = 10 / /2/.captures("2").match.len()
+
+for let letter in ["a"] {
+    assert(letter == "a")
+}
+for var i, v in [1] {
+    assert(v == 1)
+}
+
+if a == 9 {
+    assert(true)
+} else if a == 1 {
+    assert(false)
+} else {
+    assert(false)
+}
+
+fn elsewhere() >> 1
+if false { }; elsewhere()
+
+try { assert(false) } catch { }
+try { assert(false) } catch err { assert(err.code == "E114") }
+
+let w = 0..10 step 2
+assert(w.len() == 5)
+
+assert(a == 9 && b == 2 && c == 512 && d == 64 && e == 3)
+assert(f(1) == 2 && g == 6 && h == 1)
+assert(s == "it's" && t.len() == 8 && n == 1000000 && m == 0.000_001 && r == 10)
+"#;
+
+const AGGRESSIVE: &str = r#"
+import floor from "math"
+
+let total = 10
+
+fn shadowing(count) {
+    // reads the global before declaring its own
+    let before = total
+    let total = count * 2
+    return before + total
+}
+
+fn closures(first, second) {
+    let unusedValue = 42
+    let sideEffect = print("")
+    fn unusedHelper() >> 1
+    fn helper(extra) >> first + extra
+    let inner = |value| >> helper(value) + second
+    return inner(1)
+    assert(false)
+}
+
+fn nested(outerName) {
+    fn deeper(innerName) {
+        let combined = outerName + innerName
+        return combined
+    }
+    return deeper("b")
+}
+
+fn usesImport(number) {
+    let floored = floor(number)
+    return floored
+}
+
+fn catcher() {
+    try { assert(false) } catch ignored { }
+    try { assert(false) } catch err { return err.code }
+}
+
+fn printLater() {
+    let print = 1
+    return print
+}
+
+let folded = 2 * 3 + 1
+let text = "a" + "b" + 1
+let chosen = false || "fallback"
+let kept = true && total
+let third = 10 / 3
+let huge = 1e300 * 10
+
+assert(shadowing(3) == 16)
+assert(closures(1, 2) == 4)
+assert(nested("a") == "ab")
+assert(usesImport(2.5) == 2)
+assert(catcher() == "E114")
+assert(printLater() == 1)
+assert(folded == 7 && text == "ab1" && chosen == "fallback" && kept == 10)
+assert(third * 3 == 10 && huge == 1e301)
+"#;
+
+const FAILING: &str = r#"
+let limit = 3
+
+fn check(count) {
+    let double = count * 2
+    assert(double < limit)
+}
+
+check(2)
+"#;
+
+#[test]
+fn minify_round_trip() {
+    let dir = temp_dir("minify");
+
+    let script = dir.join("script.cfs");
+    fs::write(&script, SCRIPT).unwrap();
+
+    minify(&script, &[]);
+    let minified = fs::read_to_string(dir.join("minified.script.cfs")).unwrap();
+
+    assert!(!minified.contains("//") && !minified.contains("/*"));
+    for spelling in [
+        "y[key]",
+        r#"y["one"]"#,
+        "(1+2)*3",
+        "1-(2-3)",
+        "2**(3**2)",
+        "2**3**2",
+        "(1..4).len()",
+        "(|x|>>x*2)(3)",
+        "floor(1.5)",
+        r#""it's""#,
+        r#"'say "hi"'"#,
+        "1e6",
+        "10/ /2/",
+        "for let letter in [",
+        "for var i,v in [1]{",
+        "if false{};elsewhere()",
+        "0..10 step 2",
+    ] {
+        assert!(
+            minified.contains(spelling),
+            "`{spelling}` not in {minified}"
+        );
+    }
+
+    run(&dir.join("minified.script.cfs"));
+}
+
+#[test]
+fn minify_aggressive() {
+    let dir = temp_dir("minify_aggressive");
+
+    let script = dir.join("script.cfs");
+    fs::write(&script, AGGRESSIVE).unwrap();
+
+    minify(&script, &["--aggressive"]);
+    let minified = fs::read_to_string(dir.join("minified.script.cfs")).unwrap();
+
+    for spelling in [
+        // the global is read before the local shadowing it is declared
+        "fn shadowing(a){let b=total;let total=a*2;>>b+total}",
+        r#"fn closures(a,b){print("");fn c(e)>>a+e;let d=|e|>>c(e)+b;>>d(1)}"#,
+        r#"fn nested(a){fn b(c){let d=a+c;>>d}>>b("b")}"#,
+        "let b=floor(a)",
+        "catch{}",
+        "let print=1",
+        r#"let folded=7;let text="ab1";let chosen="fallback";let kept=total;"#,
+        // results longer to write than the operation are left to the runtime
+        "let third=10/3;let huge=1e301;",
+    ] {
+        assert!(
+            minified.contains(spelling),
+            "`{spelling}` not in {minified}"
+        );
+    }
+    assert!(!minified.contains("unused"));
+
+    run(&dir.join("minified.script.cfs"));
+}
+
+#[test]
+fn minify_source_map() {
+    let dir = temp_dir("minify_source_map");
+
+    let script = dir.join("script.cfs");
+    fs::write(&script, FAILING).unwrap();
+
+    // errors of the original script point to the statement raising them
+    assert!(fail(&script).contains("script.cfs:6:5"));
+
+    minify(&script, &["--aggressive", "--source-map"]);
+    let minified = fs::read_to_string(dir.join("minified.script.cfs")).unwrap();
+    let map = fs::read_to_string(dir.join("minified.script.cfs.map")).unwrap();
+
+    assert!(minified.ends_with("\n//# sourceMappingURL=minified.script.cfs.map\n"));
+    assert!(
+        map.starts_with(r#"{"version":3,"file":"minified.script.cfs","sources":["script.cfs"]"#)
+    );
+    assert!(
+        map.contains(r#""mappings":"AACA,YAEA,YACI,UACA,gBAGJ""#),
+        "{map}"
+    );
+
+    // and so do the ones of the minified script, through its map
+    let stderr = fail(&dir.join("minified.script.cfs"));
+    assert!(stderr.contains("script.cfs:6:5"), "{stderr}");
+    assert!(stderr.contains("assert(double < limit)"), "{stderr}");
+}
+
+#[test]
+fn minify_folder() {
+    let dir = temp_dir("minify_folder");
+    fs::create_dir_all(dir.join("src/nested")).unwrap();
+    fs::write(dir.join("src/script.cfs"), SCRIPT).unwrap();
+    fs::write(dir.join("src/nested/aggressive.cfs"), AGGRESSIVE).unwrap();
+    fs::write(dir.join("src/nested/notes.txt"), "not a script").unwrap();
+
+    let out = dir.join("out");
+    let stderr = minify(&dir.join("src"), &["--out", out.to_str().unwrap()]);
+
+    // every script is reported and written to the same place in the mirrored tree
+    assert!(
+        stderr.contains(&format!("{} -> ", SCRIPT.len())),
+        "{stderr}"
+    );
+    assert!(stderr.contains("nested/aggressive.cfs: "), "{stderr}");
+    assert!(!out.join("nested/notes.txt").exists());
+    run(&out.join("script.cfs"));
+    run(&out.join("nested/aggressive.cfs"));
+
+    minify(
+        &dir.join("src"),
+        &["--out", out.to_str().unwrap(), "--check"],
+    );
+
+    fs::write(dir.join("src/nested/aggressive.cfs"), SCRIPT).unwrap();
+    let output = comfy()
+        .args(["minify", "--check", "--out"])
+        .arg(&out)
+        .arg(dir.join("src"))
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("nested/aggressive.cfs: not minified or out of date"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("1 script(s) must be minified again"),
+        "{stderr}"
+    );
+
+    // a single script can be printed rather than written
+    let output = comfy()
+        .args(["minify", "--stdout"])
+        .arg(dir.join("src/script.cfs"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        fs::read_to_string(out.join("script.cfs")).unwrap()
+    );
+    assert!(!dir.join("src/minified.script.cfs").exists());
+}
+
+#[test]
+fn minify_fixtures() {
+    let dir = temp_dir("minify_fixtures");
+
+    for path in fixtures() {
+        let name = path.file_name().unwrap().to_string_lossy();
+        let copy = dir.join(&*name);
+        fs::copy(&path, &copy).unwrap();
+
+        // the minified fixtures must still hold their assertions
+        for options in [&[][..], &["--aggressive"]] {
+            minify(&copy, options);
+            run(&dir.join(format!("minified.{name}")));
+        }
+    }
+
+    for path in parse_fixtures() {
+        let copy = dir.join(path.file_name().unwrap());
+        fs::copy(&path, &copy).unwrap();
+
+        minify(&copy, &[]);
+        minify(&copy, &["--aggressive"]);
+    }
+}
+
+fn minify(script: &Path, options: &[&str]) -> String {
+    let output = comfy()
+        .args(["minify", "--verify"])
+        .args(options)
+        .arg(script)
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(output.status.success(), "{}: {stderr}", script.display());
+
+    stderr
+}
@@ -0,0 +1,12 @@
+// runs every `.cfs` fixture, whose assertions must all hold
+
+mod common;
+
+use common::{fixtures, run};
+
+#[test]
+fn scripts() {
+    for path in fixtures() {
+        run(&path);
+    }
+}
@@ -0,0 +1,32 @@
+// lists the standard library with `comfy stdlib`, whose names are in camelCase apart from
+// the constants in upper case, none of them in snake case
+
+mod common;
+
+use common::comfy;
+
+#[test]
+fn stdlib_names() {
+    let output = comfy().arg("stdlib").output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let members = stdout.lines().filter_map(|line| line.strip_prefix("    "));
+
+    for member in members {
+        let name = member.split([' ', '(']).next().unwrap();
+
+        // constructors, like `Map`, start with a capital
+        let camel_case = name.chars().all(|c| c.is_ascii_alphanumeric());
+        let upper_case = name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+        assert!(camel_case || upper_case, "{member}");
+    }
+
+    let output = comfy().args(["stdlib", "path"]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("    withExtension(path: string, extension: string)\n"));
+    assert!(stdout.contains("    relativeTo(path: string, base: string)\n"));
+    assert!(stdout.contains("    isAbsolute(path: string)\n"));
+}
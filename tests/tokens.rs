@@ -0,0 +1,74 @@
+// lists the tokens of a script with `comfy tokens`, a `/` being read as a division or as
+// the start of a regular expression depending on what precedes it, and a `}` ending a
+// statement when it closes a block
+
+mod common;
+
+use std::fs;
+
+use common::{comfy, temp_dir};
+
+const SCRIPT: &str = r#"let half = 10 / /2/i // comment
+fn f(a) >> a ..= "say \"hi\""
+/* the end */
+"#;
+
+#[test]
+fn tokens() {
+    let lines = lex("tokens", SCRIPT);
+    assert_eq!(
+        lines,
+        [
+            r#"0..3 keyword "let""#,
+            r#"4..8 identifier "half""#,
+            r#"9..10 operator "=""#,
+            r#"11..13 number "10""#,
+            r#"14..15 operator "/""#,
+            r#"16..20 regex "/2/i""#,
+            r#"21..31 line comment "// comment""#,
+            r#"32..34 keyword "fn""#,
+            r#"35..36 identifier "f""#,
+            r#"36..37 punctuation "(""#,
+            r#"37..38 identifier "a""#,
+            r#"38..39 punctuation ")""#,
+            r#"40..42 operator ">>""#,
+            r#"43..44 identifier "a""#,
+            r#"45..48 operator "..=""#,
+            r#"49..61 string "\"say \\\"hi\\\"\"""#,
+            r#"62..75 block comment "/* the end */""#,
+        ]
+    );
+}
+
+#[test]
+fn tokens_after_blocks() {
+    let lines = lex(
+        "tokens_after_blocks",
+        "if true { }\n/[//]/.test(\"/\")\nlet o = {a: 1} / 2\nlet f = || { } / 2\n",
+    );
+
+    // a statement starts after the block, where objects and closures may still be divided
+    for token in [
+        r#"12..18 regex "/[//]/""#,
+        r#"44..45 operator "/""#,
+        r#"63..64 operator "/""#,
+    ] {
+        assert!(lines.iter().any(|line| line == token), "{token}: {lines:?}");
+    }
+    assert!(lines.iter().all(|line| !line.contains("comment")));
+}
+
+fn lex(name: &str, script: &str) -> Vec<String> {
+    let dir = temp_dir(name);
+
+    let path = dir.join("script.cfs");
+    fs::write(&path, script).unwrap();
+
+    let output = comfy().arg("tokens").arg(&path).output().unwrap();
+    assert!(output.status.success());
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}